@@ -0,0 +1,275 @@
+//! # Anonymize
+//!
+//! Player identities are found in the header properties as well as in the network data. An
+//! `Anonymizer` replaces the names and online ids in both places with pseudonyms so that the
+//! replay can be written back out with the `ReplayWriter`. The pseudonyms are handed out in the
+//! order identities are encountered, so reusing an `Anonymizer` across replays gives a player the
+//! same pseudonym in each of them.
+//!
+//! Replay names and other free text that may mention a player are left untouched.
+
+use crate::models::{HeaderProp, Replay};
+use crate::network::attributes::{Attribute, RemoteId, UniqueId};
+use crate::network::ObjectId;
+use std::collections::HashMap;
+
+const PLAYER_NAME: &str = "Engine.PlayerReplicationInfo:PlayerName";
+
+/// Replaces player identities with stable pseudonyms.
+///
+/// ```
+/// use boxcars::anonymize::Anonymizer;
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let mut replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let mut anonymizer = Anonymizer::new();
+/// anonymizer.anonymize(&mut replay);
+///
+/// let written = boxcars::ReplayWriter::new(&replay).write().unwrap();
+/// let reparsed = boxcars::ParserBuilder::new(&written[..])
+///     .always_check_crc()
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let header = reparsed.header();
+/// assert_eq!(header.goals[0].player_name, "Player 1");
+/// assert_eq!(header.goals[0].player_name, anonymizer.name("Cakeboss"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Anonymizer {
+    names: HashMap<String, String>,
+    ids: HashMap<u64, u64>,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Anonymizer::default()
+    }
+
+    /// Returns the pseudonym of a player name
+    pub fn name(&mut self, name: &str) -> String {
+        let next = self.names.len() + 1;
+        self.names
+            .entry(String::from(name))
+            .or_insert_with(|| format!("Player {}", next))
+            .clone()
+    }
+
+    /// Returns the pseudonym of an online id. Zero denotes a missing id and is kept as is.
+    pub fn online_id(&mut self, id: u64) -> u64 {
+        if id == 0 {
+            return 0;
+        }
+
+        let next = self.ids.len() as u64 + 1;
+        *self.ids.entry(id).or_insert(next)
+    }
+
+    /// Scrubs the identities from the header properties, debug info, and the network frames
+    pub fn anonymize(&mut self, replay: &mut Replay) {
+        for (key, prop) in replay.properties.iter_mut() {
+            match (key.as_str(), prop) {
+                ("PlayerName", prop) => self.anonymize_name_prop(prop),
+                ("PlayerStats", HeaderProp::Array(entries))
+                | ("Goals", HeaderProp::Array(entries)) => {
+                    for entry in entries.iter_mut() {
+                        for (key, prop) in entry.iter_mut() {
+                            match (key.as_str(), prop) {
+                                ("Name", prop) | ("PlayerName", prop) => {
+                                    self.anonymize_name_prop(prop)
+                                }
+                                ("OnlineID", HeaderProp::QWord(id)) => *id = self.online_id(*id),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for info in replay.debug_info.iter_mut() {
+            info.user = self.name(&info.user);
+        }
+
+        let player_name = replay
+            .objects
+            .iter()
+            .position(|x| x == PLAYER_NAME)
+            .map(|x| ObjectId(x as i32));
+
+        let frames = replay
+            .network_frames
+            .iter_mut()
+            .flat_map(|x| x.frames.iter_mut());
+        for frame in frames {
+            for update in frame.updated_actors.iter_mut() {
+                match &mut update.attribute {
+                    Attribute::String(name) if Some(update.object_id) == player_name => {
+                        *name = self.name(name)
+                    }
+                    Attribute::UniqueId(id) => self.anonymize_unique_id(id),
+                    Attribute::PartyLeader(Some(id)) => self.anonymize_unique_id(id),
+                    Attribute::Reservation(reservation) => {
+                        self.anonymize_unique_id(&mut reservation.unique_id);
+                        if let Some(name) = reservation.name.as_mut() {
+                            *name = self.name(name);
+                        }
+                    }
+                    Attribute::PrivateMatch(settings) => {
+                        settings.game_name.clear();
+                        settings.password.clear();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn anonymize_name_prop(&mut self, prop: &mut HeaderProp) {
+        if let HeaderProp::Str(name) | HeaderProp::Name(name) = prop {
+            *name = self.name(name);
+        }
+    }
+
+    fn anonymize_unique_id(&mut self, id: &mut UniqueId) {
+        match &mut id.remote_id {
+            RemoteId::Steam(x) | RemoteId::Xbox(x) | RemoteId::QQ(x) | RemoteId::Epic(x) => {
+                *x = self.online_id(*x)
+            }
+            RemoteId::PlayStation(x) => {
+                x.name = self.name(&x.name);
+                x.online_id = self.online_id(x.online_id);
+                x.unknown1.iter_mut().for_each(|x| *x = 0);
+            }
+            RemoteId::Switch(x) => {
+                x.online_id = self.online_id(x.online_id);
+                x.unknown1.iter_mut().for_each(|x| *x = 0);
+            }
+            RemoteId::PsyNet(x) => {
+                x.online_id = self.online_id(x.online_id);
+                x.unknown1.iter_mut().for_each(|x| *x = 0);
+            }
+            RemoteId::SplitScreen(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::attributes::Ps4Id;
+    use crate::ParserBuilder;
+    use crate::ReplayWriter;
+
+    fn network_names(replay: &Replay) -> Vec<String> {
+        let player_name = replay
+            .objects
+            .iter()
+            .position(|x| x == PLAYER_NAME)
+            .map(|x| ObjectId(x as i32));
+
+        replay
+            .network_frames
+            .iter()
+            .flat_map(|x| x.frames.iter())
+            .flat_map(|x| x.updated_actors.iter())
+            .filter(|x| Some(x.object_id) == player_name)
+            .filter_map(|x| match &x.attribute {
+                Attribute::String(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_anonymize_rumble() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let header = replay.header();
+        let mut names = network_names(&replay);
+        names.extend(header.player_stats.iter().map(|x| x.name.clone()));
+        names.extend(header.goals.iter().map(|x| x.player_name.clone()));
+        names.sort();
+        names.dedup();
+        let ids: Vec<u64> = header
+            .player_stats
+            .iter()
+            .filter_map(|x| x.online_id)
+            .collect();
+
+        let mut anonymizer = Anonymizer::new();
+        anonymizer.anonymize(&mut replay);
+        let written = ReplayWriter::new(&replay).write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let json = serde_json::to_string(&reparsed).unwrap();
+        for name in &names {
+            assert!(!json.contains(&format!("\"{}\"", name)), "{} remains", name);
+        }
+
+        for id in &ids {
+            assert!(!json.contains(&id.to_string()), "{} remains", id);
+        }
+
+        // The header and network data share pseudonyms
+        let pseudonyms: Vec<String> = names.iter().map(|x| anonymizer.name(x)).collect();
+        let header = reparsed.header();
+        assert!(header
+            .player_stats
+            .iter()
+            .all(|x| pseudonyms.contains(&x.name)));
+        assert!(network_names(&reparsed)
+            .iter()
+            .all(|x| pseudonyms.contains(x)));
+        assert_eq!(network_names(&reparsed), network_names(&replay),);
+    }
+
+    #[test]
+    fn test_anonymize_unique_id() {
+        let mut anonymizer = Anonymizer::new();
+        let mut id = UniqueId {
+            system_id: 2,
+            remote_id: RemoteId::PlayStation(Ps4Id {
+                online_id: 1234,
+                name: String::from("psn-player"),
+                unknown1: vec![1; 16],
+            }),
+            local_id: 0,
+        };
+
+        anonymizer.anonymize_unique_id(&mut id);
+        assert_eq!(
+            id.remote_id,
+            RemoteId::PlayStation(Ps4Id {
+                online_id: 1,
+                name: String::from("Player 1"),
+                unknown1: vec![0; 16],
+            })
+        );
+
+        // Ids are shared between platforms
+        let mut id = UniqueId {
+            system_id: 1,
+            remote_id: RemoteId::Steam(1234),
+            local_id: 0,
+        };
+        anonymizer.anonymize_unique_id(&mut id);
+        assert_eq!(id.remote_id, RemoteId::Steam(1));
+        assert_eq!(anonymizer.online_id(5678), 2);
+        assert_eq!(anonymizer.online_id(0), 0);
+    }
+}
@@ -50,7 +50,7 @@ impl<'a> RlBits for LittleEndianReader<'a> {
 
     #[inline]
     fn peek_bits_max_computed(&mut self, bits: u32, max: u64) -> u64 {
-        debug_assert!(core::cmp::max(bit_width(max) as u32, 1) == bits + 1);
+        debug_assert!(core::cmp::max(bit_width(max), 1) == bits + 1);
 
         let data = self.peek_and_consume(bits);
         let up = data + (1 << bits);
@@ -65,7 +65,7 @@ impl<'a> RlBits for LittleEndianReader<'a> {
 
     #[inline]
     fn read_bits_max_computed(&mut self, bits: u32, max: u64) -> Option<u64> {
-        debug_assert!(core::cmp::max(bit_width(max) as u32, 1) == bits + 1);
+        debug_assert!(core::cmp::max(bit_width(max), 1) == bits + 1);
         self.read_bits(bits).and_then(|data| {
             let up = data + (1 << bits);
             if up >= max {
@@ -77,3 +77,175 @@ impl<'a> RlBits for LittleEndianReader<'a> {
         })
     }
 }
+
+/// Writes bits in the same order that a `LittleEndianReader` reads them: the least significant
+/// bit of a value is written first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct BitWriter {
+    data: Vec<u8>,
+    acc: u64,
+    acc_bits: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// The number of bits written so far
+    pub fn bits_written(&self) -> usize {
+        self.data.len() * 8 + self.acc_bits as usize
+    }
+
+    #[inline]
+    pub fn write_bits(&mut self, bits: u32, value: u64) {
+        debug_assert!(bits <= 64);
+        debug_assert!(bits == 64 || value < (1u64 << bits));
+
+        // The accumulator holds fewer than 8 pending bits, so wide values are written in halves
+        // to not shift bits out of it
+        if bits > 32 {
+            self.write_bits(32, value & 0xFFFF_FFFF);
+            self.write_bits(bits - 32, value >> 32);
+            return;
+        }
+
+        self.acc |= value << self.acc_bits;
+        self.acc_bits += bits;
+        while self.acc_bits >= 8 {
+            self.data.push(self.acc as u8);
+            self.acc >>= 8;
+            self.acc_bits -= 8;
+        }
+    }
+
+    #[inline]
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(1, u64::from(bit));
+    }
+
+    #[inline]
+    pub fn write_u8(&mut self, val: u8) {
+        self.write_bits(8, u64::from(val));
+    }
+
+    #[inline]
+    pub fn write_i8(&mut self, val: i8) {
+        self.write_u8(val as u8);
+    }
+
+    #[inline]
+    pub fn write_u32(&mut self, val: u32) {
+        self.write_bits(32, u64::from(val));
+    }
+
+    #[inline]
+    pub fn write_i32(&mut self, val: i32) {
+        self.write_u32(val as u32);
+    }
+
+    #[inline]
+    pub fn write_f32(&mut self, val: f32) {
+        self.write_u32(val.to_bits());
+    }
+
+    #[inline]
+    pub fn write_u64(&mut self, val: u64) {
+        self.write_bits(64, val);
+    }
+
+    #[inline]
+    pub fn write_i64(&mut self, val: i64) {
+        self.write_u64(val as u64);
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        for &x in data {
+            self.write_u8(x);
+        }
+    }
+
+    /// The inverse of `RlBits::read_bits_max_computed`: the value must be less than max
+    pub fn write_bits_max_computed(&mut self, bits: u32, max: u64, value: u64) {
+        debug_assert!(core::cmp::max(bit_width(max), 1) == bits + 1);
+        debug_assert!(value < max);
+        let top = 1 << bits;
+        if value >= top {
+            self.write_bits(bits, value - top);
+            self.write_bit(true);
+        } else {
+            self.write_bits(bits, value);
+            if value + top < max {
+                self.write_bit(false);
+            }
+        }
+    }
+
+    /// Returns the written data with the last byte padded with zeros
+    pub fn into_inner(mut self) -> Vec<u8> {
+        if self.acc_bits > 0 {
+            self.data.push(self.acc as u8);
+        }
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_writer_round_trip() {
+        let mut writer = BitWriter::new();
+        writer.write_bit(true);
+        writer.write_bits(3, 5);
+        writer.write_u32(0xdead_beef);
+        writer.write_f32(1.5);
+        writer.write_u64(0x0123_4567_89ab_cdef);
+        writer.write_bytes(b"abc");
+        assert_eq!(writer.bits_written(), 1 + 3 + 32 + 32 + 64 + 24);
+
+        let data = writer.into_inner();
+        let mut bits = LittleEndianReader::new(&data);
+        assert_eq!(bits.read_bit(), Some(true));
+        assert_eq!(bits.read_bits(3), Some(5));
+        assert_eq!(bits.read_u32(), Some(0xdead_beef));
+        assert_eq!(bits.read_f32(), Some(1.5));
+        assert_eq!(RlBits::read_u64(&mut bits), Some(0x0123_4567_89ab_cdef));
+        let mut out = [0u8; 3];
+        assert!(bits.read_bytes(&mut out));
+        assert_eq!(&out, b"abc");
+    }
+
+    #[test]
+    fn test_write_wide_bits() {
+        let mut writer = BitWriter::new();
+        writer.write_bits(5, 0b10110);
+        writer.write_bits(40, 0xab_cdef_0123);
+        writer.write_bits(64, u64::MAX - 1);
+        assert_eq!(writer.bits_written(), 5 + 40 + 64);
+
+        let data = writer.into_inner();
+        let mut bits = LittleEndianReader::new(&data);
+        assert_eq!(bits.read_bits(5), Some(0b10110));
+        assert_eq!(bits.read_bits(40), Some(0xab_cdef_0123));
+        assert_eq!(RlBits::read_u64(&mut bits), Some(u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_bits_max_computed_round_trip() {
+        for &max in &[2u64, 14, 20, 22, 1023, 2047, 3000] {
+            let bits = core::cmp::max(bit_width(max), 1) - 1;
+            let mut writer = BitWriter::new();
+            for value in 0..max {
+                writer.write_bits_max_computed(bits, max, value);
+            }
+
+            let data = writer.into_inner();
+            let mut reader = LittleEndianReader::new(&data);
+            for value in 0..max {
+                assert_eq!(reader.read_bits_max_computed(bits, max), Some(value));
+            }
+        }
+    }
+}
@@ -0,0 +1,382 @@
+//! # Clip
+//!
+//! Cuts a replay down to a range of frames so that it can be written out as a shorter, playable
+//! replay with the `ReplayWriter`.
+//!
+//! The game seeks through a replay from its keyframes, so a clip starts at the nearest keyframe at
+//! or before the requested start. Actors that are alive at that point were spawned earlier in the
+//! replay, so their spawn records and latest attribute values are synthesized into the first frame
+//! of the clip. The keyframes, tick marks, debug info, and the `frame` of the `Goals` and
+//! `HighLights` header properties are re-based onto the clip and entries outside of the clip are
+//! dropped. The `NumFrames` header property is updated to the number of frames in the clip.
+
+use crate::errors::ClipError;
+use crate::models::{
+    DebugInfo, HeaderProp, KeyFrame, NetworkFailure, NetworkFrames, Replay, TickMark,
+};
+use crate::network::{ActorId, Frame, NewActor, UpdatedAttribute};
+use fnv::FnvHashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// An actor's spawn record and the latest update of each of its attributes
+#[derive(Debug, Clone, PartialEq)]
+struct ActorState {
+    spawn: NewActor,
+    attributes: Vec<UpdatedAttribute>,
+}
+
+/// Returns a replay of the frames in the range, extended back to the nearest keyframe.
+///
+/// ```
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// // 5 seconds before the first goal up to 3 seconds after it
+/// let goal = replay
+///     .tick_marks
+///     .iter()
+///     .find(|x| x.description.ends_with("Goal"))
+///     .unwrap()
+///     .frame as usize;
+/// let fps = replay.header().record_fps.unwrap() as usize;
+/// let clip = boxcars::clip::clip_frames(&replay, goal - 5 * fps..goal + 3 * fps).unwrap();
+///
+/// let written = boxcars::ReplayWriter::new(&clip).write().unwrap();
+/// let reparsed = boxcars::ParserBuilder::new(&written[..])
+///     .always_check_crc()
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = &reparsed.network_frames.as_ref().unwrap().frames;
+/// assert_eq!(reparsed.header().num_frames, Some(frames.len() as i32));
+/// assert_eq!(reparsed.keyframes[0].frame, 0);
+/// assert!(frames.len() >= 8 * fps);
+/// ```
+pub fn clip_frames(replay: &Replay, range: Range<usize>) -> Result<Replay, ClipError> {
+    let frames = &replay
+        .network_frames
+        .as_ref()
+        .ok_or(ClipError::MissingNetworkData)?
+        .frames;
+
+    if range.start >= range.end || range.end > frames.len() {
+        return Err(ClipError::FrameRange {
+            start: range.start,
+            end: range.end,
+            frames: frames.len(),
+        });
+    }
+
+    let start = replay
+        .keyframes
+        .iter()
+        .filter_map(|x| usize::try_from(x.frame).ok())
+        .filter(|&x| x <= range.start)
+        .max()
+        .unwrap_or(0);
+
+    let mut actors = FnvHashMap::default();
+    for frame in &frames[..start] {
+        apply_frame(&mut actors, frame);
+    }
+
+    let mut clipped = Vec::with_capacity(range.end - start);
+    clipped.push(first_frame(actors, &frames[start]));
+    clipped.extend_from_slice(&frames[start + 1..range.end]);
+
+    let in_clip = |frame: i32| -> Option<i32> {
+        usize::try_from(frame)
+            .ok()
+            .filter(|x| (start..range.end).contains(x))
+            .map(|x| (x - start) as i32)
+    };
+
+    let mut result = replay.clone();
+    result.keyframes = replay
+        .keyframes
+        .iter()
+        .filter_map(|x| in_clip(x.frame).map(|frame| (x, frame)))
+        .map(|(x, frame)| KeyFrame { frame, ..*x })
+        .collect();
+
+    result.tick_marks = replay
+        .tick_marks
+        .iter()
+        .filter_map(|x| in_clip(x.frame).map(|frame| (x, frame)))
+        .map(|(x, frame)| TickMark {
+            frame,
+            description: x.description.clone(),
+        })
+        .collect();
+
+    result.debug_info = replay
+        .debug_info
+        .iter()
+        .filter_map(|x| in_clip(x.frame).map(|frame| (x, frame)))
+        .map(|(x, frame)| DebugInfo { frame, ..x.clone() })
+        .collect();
+
+    for (key, prop) in result.properties.iter_mut() {
+        match (key.as_str(), prop) {
+            ("NumFrames", HeaderProp::Int(num_frames)) => *num_frames = clipped.len() as i32,
+            ("Goals", HeaderProp::Array(entries)) | ("HighLights", HeaderProp::Array(entries)) => {
+                let frame = |entry: &[(String, HeaderProp)]| {
+                    entry
+                        .iter()
+                        .find(|(key, _)| key == "frame")
+                        .and_then(|(_, prop)| prop.as_i32())
+                };
+
+                entries.retain(|entry| match frame(entry) {
+                    Some(x) => in_clip(x).is_some(),
+                    None => true,
+                });
+                for entry in entries.iter_mut() {
+                    for (key, prop) in entry.iter_mut() {
+                        if let ("frame", HeaderProp::Int(frame)) = (key.as_str(), prop) {
+                            *frame = in_clip(*frame).unwrap_or(*frame);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let failures = replay
+        .network_frames
+        .iter()
+        .flat_map(|x| x.failures.iter())
+        .filter_map(|x| in_clip(x.frame as i32).map(|frame| (x, frame)))
+        .map(|(x, frame)| NetworkFailure {
+            frame: frame as usize,
+            resumed_at: x
+                .resumed_at
+                .and_then(|resume| in_clip(resume as i32))
+                .map(|resume| resume as usize),
+            ..x.clone()
+        })
+        .collect();
+
+    let truncated =
+        matches!(&replay.network_frames, Some(x) if x.truncated) && range.end == frames.len();
+    result.network_frames = Some(NetworkFrames {
+        frames: clipped,
+        failures,
+        truncated,
+    });
+    Ok(result)
+}
+
+/// Returns a replay of the frames with a time in the range, extended back to the nearest
+/// keyframe. See `clip_frames`.
+pub fn clip_time(replay: &Replay, range: Range<f32>) -> Result<Replay, ClipError> {
+    let frames = &replay
+        .network_frames
+        .as_ref()
+        .ok_or(ClipError::MissingNetworkData)?
+        .frames;
+
+    let start = frames.partition_point(|x| x.time < range.start);
+    let end = frames.partition_point(|x| x.time < range.end);
+    clip_frames(replay, start..end)
+}
+
+/// Tracks the actors alive after the frame in the order that the frame encoder writes them
+fn apply_frame(actors: &mut FnvHashMap<ActorId, ActorState>, frame: &Frame) {
+    for actor_id in &frame.deleted_actors {
+        actors.remove(actor_id);
+    }
+
+    for actor in &frame.new_actors {
+        // Live actors are replicated again at keyframes, which doesn't reset their attributes
+        match actors.get_mut(&actor.actor_id) {
+            Some(state) if state.spawn.object_id == actor.object_id => state.spawn = *actor,
+            _ => {
+                actors.insert(
+                    actor.actor_id,
+                    ActorState {
+                        spawn: *actor,
+                        attributes: Vec::new(),
+                    },
+                );
+            }
+        }
+    }
+
+    for update in &frame.updated_actors {
+        if let Some(state) = actors.get_mut(&update.actor_id) {
+            let existing = state
+                .attributes
+                .iter_mut()
+                .find(|x| x.object_id == update.object_id);
+            match existing {
+                Some(existing) => *existing = update.clone(),
+                None => state.attributes.push(update.clone()),
+            }
+        }
+    }
+}
+
+/// Spawns the live actors with their latest attributes ahead of the contents of the frame
+fn first_frame(actors: FnvHashMap<ActorId, ActorState>, frame: &Frame) -> Frame {
+    let mut actors: Vec<ActorState> = actors
+        .into_iter()
+        .filter(|(id, _)| !frame.deleted_actors.contains(id))
+        .map(|(_, state)| state)
+        .filter(|state| {
+            !frame
+                .new_actors
+                .iter()
+                .any(|x| x.actor_id == state.spawn.actor_id && x.object_id != state.spawn.object_id)
+        })
+        .collect();
+    actors.sort_by_key(|x| x.spawn.actor_id);
+
+    let new_actors = actors
+        .iter()
+        .map(|x| &x.spawn)
+        .filter(|spawn| {
+            !frame
+                .new_actors
+                .iter()
+                .any(|x| x.actor_id == spawn.actor_id)
+        })
+        .chain(frame.new_actors.iter())
+        .cloned()
+        .collect();
+
+    let updated_actors = actors
+        .into_iter()
+        .flat_map(|x| x.attributes)
+        .chain(frame.updated_actors.iter().cloned())
+        .collect();
+
+    Frame {
+        time: frame.time,
+        delta: frame.delta,
+        new_actors,
+        deleted_actors: Vec::new(),
+        updated_actors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::attributes::Attribute;
+    use crate::test_utils::{frame, new_actor};
+    use crate::ParserBuilder;
+    use crate::ReplayWriter;
+
+    fn update(actor_id: i32, object_id: i32, value: i32) -> UpdatedAttribute {
+        crate::test_utils::update(actor_id, object_id, Attribute::Int(value))
+    }
+
+    #[test]
+    fn test_first_frame() {
+        let mut actors = FnvHashMap::default();
+        apply_frame(
+            &mut actors,
+            &frame(
+                vec![new_actor(1, 10), new_actor(2, 20), new_actor(3, 30)],
+                vec![],
+                vec![update(1, 11, 1), update(1, 12, 1), update(2, 21, 1)],
+            ),
+        );
+        apply_frame(
+            &mut actors,
+            &frame(vec![new_actor(1, 10)], vec![], vec![update(1, 11, 2)]),
+        );
+
+        // Actor 1 is replicated again, 2 is replaced, and 3 is deleted
+        let first = first_frame(
+            actors,
+            &frame(
+                vec![new_actor(1, 10), new_actor(2, 40)],
+                vec![3],
+                vec![update(1, 12, 3)],
+            ),
+        );
+
+        assert_eq!(first.new_actors, vec![new_actor(1, 10), new_actor(2, 40)]);
+        assert_eq!(first.deleted_actors, vec![]);
+        assert_eq!(
+            first.updated_actors,
+            vec![update(1, 11, 2), update(1, 12, 1), update(1, 12, 3)]
+        );
+    }
+
+    #[test]
+    fn test_clip_range() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let frames = replay.network_frames.as_ref().unwrap().frames.len();
+        assert_eq!(
+            clip_frames(&replay, 10..frames + 1),
+            Err(ClipError::FrameRange {
+                start: 10,
+                end: frames + 1,
+                frames
+            })
+        );
+        assert!(clip_frames(&replay, 10..10).is_err());
+
+        let header_only = ParserBuilder::new(&data[..])
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            clip_frames(&header_only, 0..10),
+            Err(ClipError::MissingNetworkData)
+        );
+    }
+
+    #[test]
+    fn test_clip_time() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let clip = clip_time(&replay, 80.0..130.0).unwrap();
+        let written = ReplayWriter::new(&clip).write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let original = &replay.network_frames.as_ref().unwrap().frames;
+        let start = original.iter().position(|x| x.time >= 80.0).unwrap();
+        let keyframe = replay
+            .keyframes
+            .iter()
+            .rev()
+            .find(|x| x.frame as usize <= start)
+            .unwrap();
+
+        let frames = &reparsed.network_frames.as_ref().unwrap().frames;
+        assert_eq!(frames[0].time, keyframe.time);
+        assert!(frames.last().unwrap().time < 130.0);
+        assert!(reparsed
+            .keyframes
+            .iter()
+            .all(|x| frames[x.frame as usize].time == x.time));
+        assert!(reparsed
+            .tick_marks
+            .iter()
+            .all(|x| (x.frame as usize) < frames.len()));
+        assert_eq!(reparsed.header().goals.len(), reparsed.tick_marks.len());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::errors::ParseError;
-use crate::parsing_utils::{decode_str, decode_utf16, decode_windows1252, le_i32};
+use crate::parsing_utils::{decode_str, decode_utf16, decode_windows1252_borrowed, le_i32};
+use std::borrow::Cow;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CoreParser<'a> {
@@ -92,8 +93,8 @@ impl<'a> CoreParser<'a> {
         CoreParser::repeat(size as usize, || f(self))
     }
 
-    pub fn text_list(&mut self) -> Result<Vec<String>, ParseError> {
-        self.list_of(CoreParser::parse_text)
+    pub fn text_list(&mut self) -> Result<Vec<Cow<'a, str>>, ParseError> {
+        self.list_of(CoreParser::parse_text_borrowed)
     }
 
     /// Parses UTF-8 string from replay
@@ -112,6 +113,12 @@ impl<'a> CoreParser<'a> {
 
     /// Parses either UTF-16 or Windows-1252 encoded strings
     pub fn parse_text(&mut self) -> Result<String, ParseError> {
+        self.parse_text_borrowed().map(Cow::into_owned)
+    }
+
+    /// Parses text like `parse_text`, but Windows-1252 text that is ASCII (the vast majority of
+    /// text in a replay) is borrowed from the replay
+    pub fn parse_text_borrowed(&mut self) -> Result<Cow<'a, str>, ParseError> {
         // The number of bytes that the string is composed of. If negative, the string is UTF-16,
         // else the string is windows 1252 encoded.
         let characters = self.take(4, le_i32)?;
@@ -119,17 +126,19 @@ impl<'a> CoreParser<'a> {
         // size.abs() will panic at min_value, so we eschew it for manual checking
         if characters == 0 {
             Err(ParseError::ZeroSize)
-        } else if characters > 10_000 || characters < -10_000 {
+        } else if !(-10_000..=10_000).contains(&characters) {
             Err(ParseError::TextTooLarge(characters))
         } else if characters < 0 {
             // We're dealing with UTF-16 and each character is two bytes, we
             // multiply the size by 2. The last two bytes included in the count are
             // null terminators
             let size = characters * -2;
-            self.take_data(size as usize).and_then(decode_utf16)
+            self.take_data(size as usize)
+                .and_then(decode_utf16)
+                .map(Cow::Owned)
         } else {
             self.take_data(characters as usize)
-                .and_then(decode_windows1252)
+                .and_then(decode_windows1252_borrowed)
         }
     }
 }
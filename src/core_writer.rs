@@ -0,0 +1,163 @@
+use crate::errors::WriteError;
+use encoding_rs::WINDOWS_1252;
+
+/// The inverse of `CoreParser`: appends little endian encoded primitives, strings, and lists to
+/// a growable buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoreWriter {
+    data: Vec<u8>,
+}
+
+impl CoreWriter {
+    pub fn new() -> Self {
+        CoreWriter { data: Vec::new() }
+    }
+
+    pub fn bytes_written(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn write_data(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    pub fn write_i32(&mut self, val: i32) {
+        self.write_data(&val.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        self.write_data(&val.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, val: f32) {
+        self.write_data(&val.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, val: u64) {
+        self.write_data(&val.to_le_bytes());
+    }
+
+    /// Overwrites a previously written 32 bit integer at the given offset. Used for length
+    /// prefixes that aren't known until the data that follows has been written.
+    pub fn patch_i32(&mut self, offset: usize, val: i32) {
+        self.data[offset..offset + 4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes the number of elements followed by each element. Lists larger than what the parser
+    /// is willing to decode are rejected.
+    pub fn list_of<T, F>(&mut self, items: &[T], mut f: F) -> Result<(), WriteError>
+    where
+        F: FnMut(&mut Self, &T) -> Result<(), WriteError>,
+    {
+        if items.len() > 25_000 {
+            return Err(WriteError::ListTooLarge(items.len()));
+        }
+
+        self.write_i32(items.len() as i32);
+        for item in items {
+            f(self, item)?;
+        }
+        Ok(())
+    }
+
+    pub fn text_list(&mut self, items: &[String]) -> Result<(), WriteError> {
+        self.list_of(items, |s, x| s.write_text(x))
+    }
+
+    /// Writes a null terminated UTF-8 string prefixed by its size
+    pub fn write_str(&mut self, val: &str) -> Result<(), WriteError> {
+        let size = val.len() + 1;
+        if size > 10_000 {
+            return Err(WriteError::TextTooLarge(String::from(val)));
+        }
+
+        self.write_i32(size as i32);
+        self.write_data(val.as_bytes());
+        self.write_data(&[0]);
+        Ok(())
+    }
+
+    /// Writes a string as windows-1252 when all characters can be represented in the encoding,
+    /// else the string is written as UTF-16 with a negative character count.
+    pub fn write_text(&mut self, val: &str) -> Result<(), WriteError> {
+        let (encoded, _, had_errors) = WINDOWS_1252.encode(val);
+        if !had_errors {
+            let characters = encoded.len() + 1;
+            if characters > 10_000 {
+                return Err(WriteError::TextTooLarge(String::from(val)));
+            }
+
+            self.write_i32(characters as i32);
+            self.write_data(&encoded);
+            self.write_data(&[0]);
+        } else {
+            let characters = val.encode_utf16().count() + 1;
+            if characters > 10_000 {
+                return Err(WriteError::TextTooLarge(String::from(val)));
+            }
+
+            self.write_i32(-(characters as i32));
+            for c in val.encode_utf16().chain(std::iter::once(0)) {
+                self.write_data(&c.to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_parser::CoreParser;
+
+    #[test]
+    fn write_str_encoding() {
+        let data = include_bytes!("../assets/replays/partial/text.replay");
+        let mut writer = CoreWriter::new();
+        writer.write_str("TAGame.Replay_Soccar_TA").unwrap();
+        assert_eq!(writer.into_inner(), &data[..]);
+    }
+
+    #[test]
+    fn write_utf16_text() {
+        let data = include_bytes!("../assets/replays/partial/utf-16-text.replay");
+        let mut writer = CoreWriter::new();
+        writer.write_text("\u{2623}D[e]!v1zz\u{2623}").unwrap();
+        assert_eq!(writer.into_inner(), &data[..]);
+    }
+
+    #[test]
+    fn write_windows1252_text() {
+        let data = include_bytes!("../assets/replays/partial/windows_1252.replay");
+        let mut writer = CoreWriter::new();
+        writer.write_text("caudillman6000\u{b3}(2)").unwrap();
+        assert_eq!(writer.into_inner(), &data[0x1ad..0x1c4]);
+    }
+
+    #[test]
+    fn write_text_round_trip() {
+        let mut writer = CoreWriter::new();
+        writer
+            .text_list(&[String::from("abc"), String::from("\u{2623}")])
+            .unwrap();
+        let data = writer.into_inner();
+        let mut parser = CoreParser::new(&data[..]);
+        assert_eq!(
+            parser.text_list().unwrap(),
+            vec![String::from("abc"), String::from("\u{2623}")]
+        );
+    }
+
+    #[test]
+    fn write_text_too_large() {
+        let mut writer = CoreWriter::new();
+        let text = "a".repeat(10_000);
+        let err = writer.write_text(&text).unwrap_err();
+        assert_eq!(err, WriteError::TextTooLarge(text));
+        assert_eq!(writer.bytes_written(), 0);
+    }
+}
@@ -0,0 +1,416 @@
+//! # Entities
+//!
+//! Players, cars, teams, and balls are spread across several actors in the network data that
+//! reference each other through attributes. A car references its player's replication info
+//! through `Engine.Pawn:PlayerReplicationInfo`, the replication info references its team through
+//! `Engine.PlayerReplicationInfo:Team`, and car components reference their car through
+//! `TAGame.CarComponent_TA:Vehicle`.
+//!
+//! An `EntityTracker` resolves these links from the latest state of the live actors, so links
+//! stay intact when a car is respawned under a new actor id after a demolition.
+//!
+//! ```
+//! let data = include_bytes!("../assets/replays/good/rumble.replay");
+//! let replay = boxcars::ParserBuilder::new(&data[..])
+//!     .must_parse_network_data()
+//!     .parse()
+//!     .unwrap();
+//!
+//! let mut tracker = boxcars::entities::EntityTracker::new(&replay.objects);
+//! for frame in &replay.network_frames.as_ref().unwrap().frames[..1000] {
+//!     tracker.apply(frame);
+//! }
+//!
+//! for player in tracker.players() {
+//!     let car = player.car.and_then(|x| tracker.car(x));
+//!     println!("{:?} is driving {:?}", player.name, car.map(|x| x.actor_id));
+//! }
+//!
+//! assert!(tracker.ball().is_some());
+//! assert!(tracker.players().iter().any(|x| x.car.is_some() && x.team.is_some()));
+//! ```
+
+use crate::data::PARENT_CLASSES;
+use crate::network::attributes::{ActiveActor, Attribute};
+use crate::network::{ActorId, Frame, ObjectId, UniqueId};
+use crate::world::{ReplayWorld, WorldActor, WorldEvent};
+
+const PAWN_PRI: &str = "Engine.Pawn:PlayerReplicationInfo";
+const PRI_NAME: &str = "Engine.PlayerReplicationInfo:PlayerName";
+const PRI_UNIQUE_ID: &str = "Engine.PlayerReplicationInfo:UniqueId";
+const PRI_TEAM: &str = "Engine.PlayerReplicationInfo:Team";
+const COMPONENT_VEHICLE: &str = "TAGame.CarComponent_TA:Vehicle";
+
+/// A player's replication info actor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Player<'a> {
+    /// The actor id of the player's replication info
+    pub actor_id: ActorId,
+
+    pub name: Option<&'a str>,
+    pub unique_id: Option<&'a UniqueId>,
+
+    /// The actor id of the player's team
+    pub team: Option<ActorId>,
+
+    /// The actor id of the car the player is driving
+    pub car: Option<ActorId>,
+}
+
+/// A car actor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Car {
+    pub actor_id: ActorId,
+
+    /// The actor id of the replication info of the player driving the car
+    pub player: Option<ActorId>,
+}
+
+/// A car component actor, such as boost, jump, and dodge, as well as rumble's special pickups
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CarComponent {
+    pub actor_id: ActorId,
+
+    /// The component's class (eg: `TAGame.CarComponent_Boost_TA`)
+    pub class_name: &'static str,
+
+    /// The actor id of the car the component belongs to
+    pub vehicle: Option<ActorId>,
+}
+
+/// A team actor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Team {
+    pub actor_id: ActorId,
+
+    /// The team number deduced from the team's archetype (eg: `Archetypes.Teams.Team1` is 1)
+    pub index: Option<u32>,
+}
+
+/// A ball actor
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ball<'a> {
+    pub actor_id: ActorId,
+
+    /// The ball's archetype (eg: `Archetypes.Ball.Ball_Default`)
+    pub object_name: &'a str,
+}
+
+/// The object ids of the attributes that link entities together
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LinkIds {
+    pawn_pri: Option<ObjectId>,
+    pri_name: Option<ObjectId>,
+    pri_unique_id: Option<ObjectId>,
+    pri_team: Option<ObjectId>,
+    component_vehicle: Option<ObjectId>,
+}
+
+/// Links the actors of a `ReplayWorld` into players, cars, teams, and balls as frames are applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityTracker<'a> {
+    world: ReplayWorld<'a>,
+    ids: LinkIds,
+}
+
+impl<'a> EntityTracker<'a> {
+    /// Creates a tracker with no actors for a replay's objects
+    pub fn new<S: AsRef<str>>(objects: &'a [S]) -> Self {
+        let world = ReplayWorld::new(objects);
+        let ids = LinkIds {
+            pawn_pri: world.object_id(PAWN_PRI),
+            pri_name: world.object_id(PRI_NAME),
+            pri_unique_id: world.object_id(PRI_UNIQUE_ID),
+            pri_team: world.object_id(PRI_TEAM),
+            component_vehicle: world.object_id(COMPONENT_VEHICLE),
+        };
+
+        EntityTracker { world, ids }
+    }
+
+    /// Applies the next frame and returns the events that occurred in the frame
+    pub fn apply(&mut self, frame: &Frame) -> &[WorldEvent] {
+        self.world.apply(frame)
+    }
+
+    /// The underlying actor state
+    pub fn world(&self) -> &ReplayWorld<'a> {
+        &self.world
+    }
+
+    fn of_class<'b>(&'b self, class_name: &'b str) -> impl Iterator<Item = &'b WorldActor<'a>> {
+        self.world
+            .actors()
+            .filter(move |x| matches!(x.class_name(), Some(c) if is_a(c, class_name)))
+    }
+
+    /// All players with live replication info
+    pub fn players(&self) -> Vec<Player<'_>> {
+        let cars = self.cars();
+        let mut players: Vec<_> = self
+            .of_class("TAGame.PRI_TA")
+            .map(|x| self.to_player(x, &cars))
+            .collect();
+        players.sort_by_key(|x| x.actor_id);
+        players
+    }
+
+    /// The player with the given replication info actor id
+    pub fn player(&self, actor_id: ActorId) -> Option<Player<'_>> {
+        self.world
+            .actor(actor_id)
+            .filter(|x| matches!(x.class_name(), Some(c) if is_a(c, "TAGame.PRI_TA")))
+            .map(|x| self.to_player(x, &self.cars()))
+    }
+
+    fn to_player<'b>(&'b self, actor: &'b WorldActor<'a>, cars: &[Car]) -> Player<'b> {
+        let name = match attribute(actor, self.ids.pri_name) {
+            Some(Attribute::String(name)) => Some(name.as_str()),
+            _ => None,
+        };
+
+        let unique_id = match attribute(actor, self.ids.pri_unique_id) {
+            Some(Attribute::UniqueId(id)) => Some(id.as_ref()),
+            _ => None,
+        };
+
+        let car = cars
+            .iter()
+            .find(|x| x.player == Some(actor.actor_id))
+            .map(|x| x.actor_id);
+
+        Player {
+            actor_id: actor.actor_id,
+            name,
+            unique_id,
+            team: linked_actor(actor, self.ids.pri_team),
+            car,
+        }
+    }
+
+    /// All live cars
+    pub fn cars(&self) -> Vec<Car> {
+        let mut cars: Vec<_> = self
+            .of_class("TAGame.Car_TA")
+            .map(|x| self.to_car(x))
+            .collect();
+        cars.sort_by_key(|x| x.actor_id);
+        cars
+    }
+
+    /// The car with the given actor id
+    pub fn car(&self, actor_id: ActorId) -> Option<Car> {
+        self.world
+            .actor(actor_id)
+            .filter(|x| matches!(x.class_name(), Some(c) if is_a(c, "TAGame.Car_TA")))
+            .map(|x| self.to_car(x))
+    }
+
+    fn to_car(&self, actor: &WorldActor<'_>) -> Car {
+        Car {
+            actor_id: actor.actor_id,
+            player: linked_actor(actor, self.ids.pawn_pri),
+        }
+    }
+
+    /// All live car components
+    pub fn car_components(&self) -> Vec<CarComponent> {
+        let mut components: Vec<_> = self
+            .world
+            .actors()
+            .filter_map(|actor| {
+                let class_name = actor
+                    .class_name()
+                    .filter(|c| is_a(c, "TAGame.CarComponent_TA"))?;
+                Some(CarComponent {
+                    actor_id: actor.actor_id,
+                    class_name,
+                    vehicle: linked_actor(actor, self.ids.component_vehicle),
+                })
+            })
+            .collect();
+        components.sort_by_key(|x| x.actor_id);
+        components
+    }
+
+    /// The live components that belong to the car with the given actor id
+    pub fn components_of(&self, car: ActorId) -> Vec<CarComponent> {
+        let mut components = self.car_components();
+        components.retain(|x| x.vehicle == Some(car));
+        components
+    }
+
+    /// All live teams
+    pub fn teams(&self) -> Vec<Team> {
+        let mut teams: Vec<_> = self
+            .of_class("TAGame.Team_TA")
+            .map(|x| Team {
+                actor_id: x.actor_id,
+                index: x
+                    .object_name()
+                    .strip_prefix("Archetypes.Teams.Team")
+                    .and_then(|x| x.parse().ok()),
+            })
+            .collect();
+        teams.sort_by_key(|x| x.actor_id);
+        teams
+    }
+
+    /// All live balls. Some game modes have more than one ball.
+    pub fn balls(&self) -> Vec<Ball<'a>> {
+        let mut balls: Vec<_> = self
+            .world
+            .actors()
+            .filter(|x| is_ball(x))
+            .map(|x| Ball {
+                actor_id: x.actor_id,
+                object_name: x.object_name(),
+            })
+            .collect();
+        balls.sort_by_key(|x| x.actor_id);
+        balls
+    }
+
+    /// The live ball with the lowest actor id
+    pub fn ball(&self) -> Option<Ball<'a>> {
+        self.balls().into_iter().next()
+    }
+}
+
+fn attribute<'b>(actor: &'b WorldActor<'_>, id: Option<ObjectId>) -> Option<&'b Attribute> {
+    id.and_then(|id| actor.attribute_by_id(id))
+}
+
+/// Resolves an attribute that references another actor
+fn linked_actor(actor: &WorldActor<'_>, id: Option<ObjectId>) -> Option<ActorId> {
+    match attribute(actor, id) {
+        Some(Attribute::ActiveActor(ActiveActor {
+            active: true,
+            actor,
+        })) => Some(*actor),
+        _ => None,
+    }
+}
+
+fn is_ball(actor: &WorldActor<'_>) -> bool {
+    actor
+        .class_name()
+        .map_or(actor.object_name().starts_with("Archetypes.Ball."), |c| {
+            c.starts_with("TAGame.Ball_")
+        })
+}
+
+/// Returns if the class is the given class or inherits from it
+fn is_a(class_name: &str, ancestor: &str) -> bool {
+    let mut class_name = class_name;
+    loop {
+        if class_name == ancestor {
+            return true;
+        }
+
+        match PARENT_CLASSES.get(class_name) {
+            Some(parent) => class_name = parent,
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::UpdatedAttribute;
+    use crate::test_utils::{frame, new_actor, update};
+
+    fn objects() -> Vec<String> {
+        crate::test_utils::objects(&[
+            "Archetypes.Car.Car_Default",
+            "TAGame.Default__PRI_TA",
+            "Archetypes.Teams.Team1",
+            "Archetypes.Ball.Ball_Default",
+            "Archetypes.CarComponents.CarComponent_Boost",
+            PAWN_PRI,
+            PRI_NAME,
+            PRI_TEAM,
+            COMPONENT_VEHICLE,
+        ])
+    }
+
+    fn link(actor_id: i32, object_id: i32, actor: i32) -> UpdatedAttribute {
+        let attribute = Attribute::ActiveActor(ActiveActor {
+            active: true,
+            actor: ActorId(actor),
+        });
+        update(actor_id, object_id, attribute)
+    }
+
+    #[test]
+    fn test_link_entities() {
+        let objects = objects();
+        let mut tracker = EntityTracker::new(&objects);
+        tracker.apply(&frame(
+            vec![
+                new_actor(1, 2),
+                new_actor(2, 1),
+                new_actor(3, 0),
+                new_actor(4, 4),
+                new_actor(5, 3),
+            ],
+            vec![],
+            vec![
+                update(2, 6, Attribute::String(String::from("comagoosie"))),
+                link(2, 7, 1),
+                link(3, 5, 2),
+                link(4, 8, 3),
+            ],
+        ));
+
+        assert_eq!(
+            tracker.players(),
+            vec![Player {
+                actor_id: ActorId(2),
+                name: Some("comagoosie"),
+                unique_id: None,
+                team: Some(ActorId(1)),
+                car: Some(ActorId(3)),
+            }]
+        );
+
+        assert_eq!(
+            tracker.teams(),
+            vec![Team {
+                actor_id: ActorId(1),
+                index: Some(1),
+            }]
+        );
+
+        assert_eq!(
+            tracker.components_of(ActorId(3)),
+            vec![CarComponent {
+                actor_id: ActorId(4),
+                class_name: "TAGame.CarComponent_Boost_TA",
+                vehicle: Some(ActorId(3)),
+            }]
+        );
+
+        assert_eq!(tracker.ball().map(|x| x.actor_id), Some(ActorId(5)));
+
+        // A demolished car is respawned as a new actor that is linked to the same player
+        tracker.apply(&frame(vec![], vec![3], vec![]));
+        assert_eq!(tracker.player(ActorId(2)).unwrap().car, None);
+
+        tracker.apply(&frame(vec![new_actor(6, 0)], vec![], vec![link(6, 5, 2)]));
+        assert_eq!(tracker.player(ActorId(2)).unwrap().car, Some(ActorId(6)));
+        assert_eq!(tracker.car(ActorId(6)).unwrap().player, Some(ActorId(2)));
+        assert_eq!(tracker.car(ActorId(2)), None);
+    }
+
+    #[test]
+    fn test_is_a() {
+        assert!(is_a(
+            "TAGame.CarComponent_Boost_TA",
+            "TAGame.CarComponent_TA"
+        ));
+        assert!(is_a("TAGame.PRI_TA", "TAGame.PRI_TA"));
+        assert!(!is_a("TAGame.Car_TA", "TAGame.PRI_TA"));
+    }
+}
@@ -1,9 +1,13 @@
 use crate::data::ATTRIBUTES;
-use crate::network::{ActorId, Frame, NewActor, ObjectId, StreamId, UpdatedAttribute};
+use crate::models::Replay;
+use crate::network::{
+    self, ActorId, Frame, NetworkTrace, NewActor, ObjectId, StreamId, UpdatedAttribute,
+};
 use fnv::FnvHashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::io;
 use std::ops::Deref;
 use std::str;
 
@@ -18,7 +22,25 @@ pub enum ParseError {
     CrcMismatch(u32, u32),
     CorruptReplay(String, Box<ParseError>),
     ListTooLarge(usize),
-    NetworkError(Box<NetworkError>),
+
+    /// The network data failed to decode. The replay holds the rest of the parsed data. When the
+    /// failure was in a frame, `into_partial_replay` moves the frames that decoded before it into
+    /// the replay.
+    NetworkError(Box<NetworkError>, Box<Replay>),
+}
+
+impl ParseError {
+    /// Recovers the replay from a network error, along with the frames that decoded prior to a
+    /// frame that failed to decode, which are marked as truncated
+    pub fn into_partial_replay(self) -> Option<Replay> {
+        match self {
+            ParseError::NetworkError(error, mut replay) => {
+                replay.network_frames = network::truncated_frames(*error).ok();
+                Some(*replay)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for ParseError {
@@ -53,7 +75,7 @@ impl Display for ParseError {
                 "Could not decode replay {} at offset ({}): {}",
                 section, bytes_read, parse_error
             ),
-            ParseError::NetworkError(network_error) => write!(f, "{}", network_error),
+            ParseError::NetworkError(network_error, _) => write!(f, "{}", network_error),
         }
     }
 }
@@ -64,7 +86,7 @@ impl Error for ParseError {
             ParseError::Utf8Error(utf8_error) => Some(utf8_error),
             ParseError::CorruptReplay(_, error) => Some(error),
             ParseError::ParseError(_, _, error) => Some(error),
-            ParseError::NetworkError(error) => Some(error),
+            ParseError::NetworkError(error, _) => Some(error),
             _ => None,
         }
     }
@@ -82,6 +104,8 @@ pub enum AttributeError {
     UnrecognizedRemoteId(u8),
     Unimplemented,
     TooBigString(i32),
+    UnexpectedAttribute,
+    ValueOutOfRange(&'static str),
 }
 
 impl Error for AttributeError {
@@ -101,26 +125,32 @@ impl Display for AttributeError {
             }
             AttributeError::Unimplemented => write!(f, "Does not have an attribute implementation"),
             AttributeError::TooBigString(size) => write!(f, "Unexpected size for string: {}", size),
+            AttributeError::UnexpectedAttribute => {
+                write!(
+                    f,
+                    "Attribute does not match the attribute type of the stream"
+                )
+            }
+            AttributeError::ValueOutOfRange(message) => {
+                write!(f, "Value out of range to encode attribute {}", message)
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ContextObjectAttribute {
-    obj_id: ObjectId,
-    obj_name: String,
-    prop_id: ObjectId,
-    prop_name: String,
-}
-
 #[derive(PartialEq, Debug, Clone)]
 pub struct FrameContext {
+    /// Bit offset into the network data where the error occurred
+    pub bit_offset: usize,
     pub objects: Vec<String>,
     pub object_attributes: FnvHashMap<ObjectId, FnvHashMap<StreamId, ObjectId>>,
     pub frames: Vec<Frame>,
     pub actors: FnvHashMap<ActorId, ObjectId>,
     pub new_actors: Vec<NewActor>,
     pub updated_actors: Vec<UpdatedAttribute>,
+
+    /// The elements decoded leading up to the error when the network data is traced
+    pub trace: Option<NetworkTrace>,
 }
 
 impl FrameContext {
@@ -223,6 +253,7 @@ impl fmt::Display for FrameContext {
 #[derive(PartialEq, Debug, Clone)]
 pub enum FrameError {
     NotEnoughDataFor(&'static str),
+    ValueOutOfRange(&'static str),
     TimeOutOfRange {
         time: f32,
     },
@@ -384,6 +415,7 @@ impl Display for FrameError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             FrameError::NotEnoughDataFor(message) => write!(f, "not enough data to decode {}", message),
+            FrameError::ValueOutOfRange(message) => write!(f, "value out of range to encode {}", message),
             FrameError::TimeOutOfRange {time} => write!(f, "time is out of range: {}", time),
             FrameError::DeltaOutOfRange {delta} => write!(f, "delta is out of range: {}", delta),
             FrameError::ObjectIdOutOfRange {obj} => write!(f, "new actor object id out of range: {}", obj),
@@ -401,9 +433,12 @@ pub enum NetworkError {
     ObjectIdOutOfRange(ObjectId),
     StreamTooLargeIndex(i32, i32),
     MissingParentClass(String, String),
+    ParentClassCycle(String),
     ParentHasNoAttributes(ObjectId, ObjectId),
     FrameError(FrameError, Box<FrameContext>),
     TooManyFrames(i32),
+    KeyFrameOutOfRange(usize),
+    FrameOutOfRange(usize),
 }
 
 impl Error for NetworkError {
@@ -432,12 +467,21 @@ impl Display for NetworkError {
                 "Replay contained object: {} but not the parent class: {}",
                 obj, parent
             ),
+            NetworkError::ParentClassCycle(class) => {
+                write!(f, "Class hierarchy contains a cycle at class: {}", class)
+            }
             NetworkError::ParentHasNoAttributes(parent_id, object_id) => write!(
                 f,
                 "Parent id of {} for object id of {} was not recognized to have attributes",
                 parent_id, object_id
             ),
             NetworkError::TooManyFrames(size) => write!(f, "Too many frames to decode: {}", size),
+            NetworkError::KeyFrameOutOfRange(index) => {
+                write!(f, "Keyframe {} does not point into the network data", index)
+            }
+            NetworkError::FrameOutOfRange(frame) => {
+                write!(f, "Frame {} is not in the network data", frame)
+            }
             NetworkError::FrameError(err, context) => {
                 write!(f, "Error decoding frame: {}. ", err)?;
                 err.contextualize(f, context)?;
@@ -446,3 +490,107 @@ impl Display for NetworkError {
         }
     }
 }
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum WriteError {
+    TextTooLarge(String),
+    ListTooLarge(usize),
+    NetworkError(Box<NetworkError>),
+
+    /// The network frames are missing the given number of frames that failed to decode
+    IncompleteFrames(usize),
+}
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WriteError::NetworkError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            WriteError::TextTooLarge(text) => {
+                write!(f, "Text is too large to encode: {} bytes", text.len())
+            }
+            WriteError::ListTooLarge(size) => write!(f, "list of size {} is too large", size),
+            WriteError::NetworkError(network_error) => write!(f, "{}", network_error),
+            WriteError::IncompleteFrames(failures) => write!(
+                f,
+                "Network frames are incomplete as {} failed to decode",
+                failures
+            ),
+        }
+    }
+}
+
+/// An error from reading a replay or parsing what was read
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(error) => Some(error),
+            ReadError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(error) => write!(f, "Unable to read replay: {}", error),
+            ReadError::Parse(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> Self {
+        ReadError::Io(error)
+    }
+}
+
+impl From<ParseError> for ReadError {
+    fn from(error: ParseError) -> Self {
+        ReadError::Parse(error)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ClipError {
+    MissingNetworkData,
+    FrameRange {
+        start: usize,
+        end: usize,
+        frames: usize,
+    },
+}
+
+impl Error for ClipError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+impl Display for ClipError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ClipError::MissingNetworkData => {
+                write!(f, "Network frames are required to clip a replay")
+            }
+            ClipError::FrameRange { start, end, frames } => write!(
+                f,
+                "Frame range {}..{} is not valid for a replay of {} frames",
+                start, end, frames
+            ),
+        }
+    }
+}
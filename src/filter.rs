@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+/// Restricts the network data that is decoded to the actors and attributes of interest.
+///
+/// The network data must be read in full to decode any of it, so attributes that aren't wanted
+/// are still read, but they are skipped without being allocated into the frames. Actors and
+/// attributes are selected by:
+///
+/// - Class (eg: "TAGame.Car_TA"): only actors that are an instance of the class, or of a class
+///   derived from it, are kept. New actors, deleted actors, and attribute updates of all other
+///   actors are dropped from the frames.
+/// - Attribute (eg: "TAGame.RBActor_TA:ReplicatedRBState"): only updates to the attribute are
+///   kept.
+///
+/// When both are given, only the given attributes of actors of the given classes are kept. When
+/// no classes are given, all actors are kept, and when no attributes are given, all attributes
+/// of the kept actors are kept.
+///
+/// ```
+/// use boxcars::{Attribute, NetworkFilter, ParserBuilder};
+///
+/// let mut filter = NetworkFilter::new();
+/// filter
+///     .add_class("TAGame.Ball_TA")
+///     .add_class("TAGame.Car_TA")
+///     .add_attribute("TAGame.RBActor_TA:ReplicatedRBState");
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = ParserBuilder::new(&data[..])
+///     .network_filter(&filter)
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = replay.network_frames.unwrap().frames;
+/// assert!(frames
+///     .iter()
+///     .flat_map(|x| x.updated_actors.iter())
+///     .all(|x| matches!(x.attribute, Attribute::RigidBody(_))));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NetworkFilter {
+    classes: HashSet<String>,
+    attributes: HashSet<String>,
+}
+
+impl NetworkFilter {
+    /// Creates a filter that keeps everything
+    pub fn new() -> Self {
+        NetworkFilter::default()
+    }
+
+    /// Keeps the actors of the class and its derived classes
+    pub fn add_class(&mut self, class: &str) -> &mut Self {
+        self.classes.insert(String::from(class));
+        self
+    }
+
+    /// Keeps the updates to the attribute
+    pub fn add_attribute(&mut self, attribute: &str) -> &mut Self {
+        self.attributes.insert(String::from(attribute));
+        self
+    }
+
+    /// Returns if the filter restricts actors by their class
+    pub(crate) fn filters_classes(&self) -> bool {
+        !self.classes.is_empty()
+    }
+
+    /// Returns if the filter restricts attributes by their name
+    pub(crate) fn filters_attributes(&self) -> bool {
+        !self.attributes.is_empty()
+    }
+
+    pub(crate) fn has_class(&self, class: &str) -> bool {
+        self.classes.contains(class)
+    }
+
+    pub(crate) fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes.contains(attribute)
+    }
+}
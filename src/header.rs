@@ -1,49 +1,211 @@
 use crate::core_parser::CoreParser;
-use crate::errors::ParseError;
-use crate::models::HeaderProp;
+use crate::core_writer::CoreWriter;
+use crate::errors::{ParseError, WriteError};
+use crate::models::{
+    into_owned_props, BorrowedHeaderProp, HeaderGoal, HeaderHighlight, HeaderPlayerStats,
+    HeaderProp, ReplayHeader,
+};
 use crate::parsing_utils::{le_f32, le_u64};
+use std::borrow::Cow;
 
-/// Intermediate parsing structure for the header
+/// Intermediate parsing structure for the header. The header is decoded with its text borrowed
+/// from the replay data, while a header that is written out is owned.
 #[derive(Debug, PartialEq)]
-pub struct Header {
+pub struct Header<S = String, P = HeaderProp> {
     pub major_version: i32,
     pub minor_version: i32,
     pub net_version: Option<i32>,
-    pub game_type: String,
-    pub properties: Vec<(String, HeaderProp)>,
+    pub game_type: S,
+    pub properties: Vec<(S, P)>,
 }
 
-impl Header {
+pub type BorrowedHeader<'a> = Header<Cow<'a, str>, BorrowedHeaderProp<'a>>;
+
+impl<'a> BorrowedHeader<'a> {
+    pub fn into_owned(self) -> Header {
+        Header {
+            major_version: self.major_version,
+            minor_version: self.minor_version,
+            net_version: self.net_version,
+            game_type: self.game_type.into_owned(),
+            properties: into_owned_props(self.properties),
+        }
+    }
+}
+
+/// The property values that decoding the network data looks up, for both owned and borrowed
+/// header properties
+pub trait PropValue {
+    fn as_i32(&self) -> Option<i32>;
+    fn as_string(&self) -> Option<&str>;
+}
+
+impl PropValue for HeaderProp {
+    fn as_i32(&self) -> Option<i32> {
+        HeaderProp::as_i32(self)
+    }
+
+    fn as_string(&self) -> Option<&str> {
+        HeaderProp::as_string(self)
+    }
+}
+
+impl<'a> PropValue for BorrowedHeaderProp<'a> {
+    fn as_i32(&self) -> Option<i32> {
+        BorrowedHeaderProp::as_i32(self)
+    }
+
+    fn as_string(&self) -> Option<&str> {
+        BorrowedHeaderProp::as_string(self)
+    }
+}
+
+impl<S: AsRef<str>, P: PropValue> Header<S, P> {
     pub fn num_frames(&self) -> Option<i32> {
         self.properties
             .iter()
-            .find(|&(key, _)| key == "NumFrames")
-            .and_then(|&(_, ref prop)| prop.as_i32())
+            .find(|(key, _)| key.as_ref() == "NumFrames")
+            .and_then(|(_, prop)| prop.as_i32())
     }
 
     pub fn max_channels(&self) -> Option<i32> {
         self.properties
             .iter()
-            .find(|&(key, _)| key == "MaxChannels")
-            .and_then(|&(_, ref prop)| prop.as_i32())
+            .find(|(key, _)| key.as_ref() == "MaxChannels")
+            .and_then(|(_, prop)| prop.as_i32())
     }
 
     pub fn match_type(&self) -> Option<&str> {
         self.properties
             .iter()
-            .find(|&(key, _)| key == "MatchType")
-            .and_then(|&(_, ref prop)| prop.as_string())
+            .find(|(key, _)| key.as_ref() == "MatchType")
+            .and_then(|(_, prop)| prop.as_string())
     }
 
     pub fn build_version(&self) -> Option<&str> {
         self.properties
             .iter()
-            .find(|&(key, _)| key == "BuildVersion")
-            .and_then(|&(_, ref prop)| prop.as_string())
+            .find(|(key, _)| key.as_ref() == "BuildVersion")
+            .and_then(|(_, prop)| prop.as_string())
+    }
+}
+
+impl ReplayHeader {
+    /// Extracts the well-known properties. When a key is repeated, the first property is used
+    /// and the rest are kept in `extra`.
+    pub fn from_properties(properties: &[(String, HeaderProp)]) -> ReplayHeader {
+        let mut header = ReplayHeader::default();
+        for (key, prop) in properties {
+            let known = match key.as_str() {
+                "TeamSize" => set(&mut header.team_size, prop.as_i32()),
+                "Team0Score" => set(&mut header.team0_score, prop.as_i32()),
+                "Team1Score" => set(&mut header.team1_score, prop.as_i32()),
+                "Goals" => set_list(&mut header.goals, prop, header_goal),
+                "PlayerStats" => set_list(&mut header.player_stats, prop, header_player_stats),
+                "Date" => set(&mut header.date, prop.as_string().map(String::from)),
+                "MapName" => set(&mut header.map_name, prop.as_string().map(String::from)),
+                "Id" => set(&mut header.id, prop.as_string().map(String::from)),
+                "ReplayName" => set(&mut header.replay_name, prop.as_string().map(String::from)),
+                "MatchType" => set(&mut header.match_type, prop.as_string().map(String::from)),
+                "BuildVersion" => set(
+                    &mut header.build_version,
+                    prop.as_string().map(String::from),
+                ),
+                "NumFrames" => set(&mut header.num_frames, prop.as_i32()),
+                "RecordFPS" => set(&mut header.record_fps, prop.as_float()),
+                "HighLights" => set_list(&mut header.highlights, prop, header_highlight),
+                _ => false,
+            };
+
+            if !known {
+                header
+                    .extra
+                    .entry(key.clone())
+                    .or_insert_with(|| prop.clone());
+            }
+        }
+
+        header
+    }
+}
+
+/// Assigns the value to an unassigned field and returns if it was assigned
+fn set<T>(field: &mut Option<T>, value: Option<T>) -> bool {
+    if field.is_some() || value.is_none() {
+        return false;
+    }
+
+    *field = value;
+    true
+}
+
+/// Assigns an array property to an unassigned field when every entry converts successfully
+fn set_list<T, F>(field: &mut Vec<T>, prop: &HeaderProp, f: F) -> bool
+where
+    F: Fn(&[(String, HeaderProp)]) -> Option<T>,
+{
+    if !field.is_empty() {
+        return false;
+    }
+
+    let list = prop
+        .as_array()
+        .and_then(|arr| arr.iter().map(|x| f(x)).collect::<Option<Vec<_>>>());
+
+    match list {
+        Some(list) => {
+            *field = list;
+            true
+        }
+        None => false,
     }
 }
 
-pub fn parse_header(rlp: &mut CoreParser) -> Result<Header, ParseError> {
+fn entry<'a>(props: &'a [(String, HeaderProp)], key: &str) -> Option<&'a HeaderProp> {
+    props.iter().find(|(k, _)| k == key).map(|(_, prop)| prop)
+}
+
+fn header_goal(props: &[(String, HeaderProp)]) -> Option<HeaderGoal> {
+    Some(HeaderGoal {
+        frame: entry(props, "frame")?.as_i32()?,
+        player_name: String::from(entry(props, "PlayerName")?.as_string()?),
+        player_team: entry(props, "PlayerTeam")?.as_i32()?,
+    })
+}
+
+fn header_player_stats(props: &[(String, HeaderProp)]) -> Option<HeaderPlayerStats> {
+    // Older replays store the platform as the kind of the byte property without a value
+    let platform = match entry(props, "Platform") {
+        Some(HeaderProp::Byte { kind, value }) => Some(value.as_ref().unwrap_or(kind).clone()),
+        _ => None,
+    };
+
+    Some(HeaderPlayerStats {
+        name: String::from(entry(props, "Name")?.as_string()?),
+        platform,
+        online_id: entry(props, "OnlineID").and_then(|x| x.as_u64()),
+        team: entry(props, "Team")?.as_i32()?,
+        score: entry(props, "Score")?.as_i32()?,
+        goals: entry(props, "Goals")?.as_i32()?,
+        assists: entry(props, "Assists")?.as_i32()?,
+        saves: entry(props, "Saves")?.as_i32()?,
+        shots: entry(props, "Shots")?.as_i32()?,
+        bot: entry(props, "bBot")?.as_bool()?,
+    })
+}
+
+fn header_highlight(props: &[(String, HeaderProp)]) -> Option<HeaderHighlight> {
+    Some(HeaderHighlight {
+        frame: entry(props, "frame")?.as_i32()?,
+        car_name: String::from(entry(props, "CarName")?.as_string()?),
+        ball_name: String::from(entry(props, "BallName")?.as_string()?),
+        goal_actor_name: entry(props, "GoalActorName")
+            .and_then(|x| x.as_string())
+            .map(String::from),
+    })
+}
+
+pub fn parse_header<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeader<'a>, ParseError> {
     let major_version = rlp.take_i32("major version")?;
     let minor_version = rlp.take_i32("minor version")?;
     let net_version = if major_version > 865 && minor_version > 17 {
@@ -53,7 +215,7 @@ pub fn parse_header(rlp: &mut CoreParser) -> Result<Header, ParseError> {
     };
 
     let game_type = rlp
-        .parse_text()
+        .parse_text_borrowed()
         .map_err(|e| ParseError::ParseError("game type", rlp.bytes_read(), Box::new(e)))?;
 
     let properties = parse_rdict(rlp)
@@ -68,7 +230,21 @@ pub fn parse_header(rlp: &mut CoreParser) -> Result<Header, ParseError> {
     })
 }
 
-fn parse_rdict(rlp: &mut CoreParser) -> Result<Vec<(String, HeaderProp)>, ParseError> {
+/// Encodes a header in the same format that `parse_header` decodes
+pub fn write_header(w: &mut CoreWriter, header: &Header) -> Result<(), WriteError> {
+    w.write_i32(header.major_version);
+    w.write_i32(header.minor_version);
+    if let Some(net_version) = header.net_version {
+        w.write_i32(net_version);
+    }
+
+    w.write_text(&header.game_type)?;
+    write_rdict(w, &header.properties)
+}
+
+type Props<'a> = Vec<(Cow<'a, str>, BorrowedHeaderProp<'a>)>;
+
+fn parse_rdict<'a>(rlp: &mut CoreParser<'a>) -> Result<Props<'a>, ParseError> {
     // Other the actual network data, the header property associative array is the hardest to parse.
     // The format is to:
     // - Read string
@@ -97,7 +273,7 @@ fn parse_rdict(rlp: &mut CoreParser) -> Result<Vec<(String, HeaderProp)>, ParseE
             x => Err(ParseError::UnexpectedProperty(String::from(x))),
         }?;
 
-        res.push((String::from(key), val));
+        res.push((Cow::Borrowed(key), val));
     }
 
     Ok(res)
@@ -109,54 +285,143 @@ fn parse_rdict(rlp: &mut CoreParser) -> Result<Vec<(String, HeaderProp)>, ParseE
 // 32bits unknown. Doesn't matter to us, we throw it out anyways. The rest of the bytes are
 // decoded property type specific.
 
-fn decode_prop<F, T>(rlp: &mut CoreParser, mut f: F) -> Result<T, ParseError>
+fn decode_prop<'a, F, T>(rlp: &mut CoreParser<'a>, mut f: F) -> Result<T, ParseError>
 where
-    F: FnMut(&mut CoreParser) -> Result<T, ParseError>,
+    F: FnMut(&mut CoreParser<'a>) -> Result<T, ParseError>,
 {
     rlp.skip(8)?;
     f(rlp)
 }
 
-fn byte_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
+fn byte_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
     let kind = rlp.parse_str()?;
     let value = match kind {
         "OnlinePlatform_Steam" | "OnlinePlatform_PS4" => Ok(None),
         _ => rlp.parse_str().map(Some),
     }?;
-    Ok(HeaderProp::Byte {
-        kind: String::from(kind),
-        value: value.map(String::from),
+    Ok(BorrowedHeaderProp::Byte {
+        kind: Cow::Borrowed(kind),
+        value: value.map(Cow::Borrowed),
     })
 }
 
-fn str_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    Ok(HeaderProp::Str(rlp.parse_text()?))
+fn str_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    Ok(BorrowedHeaderProp::Str(rlp.parse_text_borrowed()?))
 }
 
-fn name_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    Ok(HeaderProp::Name(rlp.parse_text()?))
+fn name_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    Ok(BorrowedHeaderProp::Name(rlp.parse_text_borrowed()?))
 }
 
-fn int_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    rlp.take_i32("int property").map(HeaderProp::Int)
+fn int_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    rlp.take_i32("int property").map(BorrowedHeaderProp::Int)
 }
 
-fn bool_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    rlp.take(1, |d| HeaderProp::Bool(d[0] == 1))
+fn bool_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    rlp.take(1, |d| BorrowedHeaderProp::Bool(d[0] == 1))
 }
 
-fn float_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    rlp.take(4, |d| HeaderProp::Float(le_f32(d)))
+fn float_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    rlp.take(4, |d| BorrowedHeaderProp::Float(le_f32(d)))
 }
 
-fn qword_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
-    rlp.take(8, |d| HeaderProp::QWord(le_u64(d)))
+fn qword_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
+    rlp.take(8, |d| BorrowedHeaderProp::QWord(le_u64(d)))
 }
 
-fn array_property(rlp: &mut CoreParser) -> Result<HeaderProp, ParseError> {
+fn array_property<'a>(rlp: &mut CoreParser<'a>) -> Result<BorrowedHeaderProp<'a>, ParseError> {
     let size = rlp.take_i32("array property size")?;
     let arr = CoreParser::repeat(size as usize, || parse_rdict(rlp))?;
-    Ok(HeaderProp::Array(arr))
+    Ok(BorrowedHeaderProp::Array(arr))
+}
+
+/// Encodes properties in the same format that `parse_rdict` decodes, including the trailing
+/// "None" key.
+pub fn write_rdict(w: &mut CoreWriter, props: &[(String, HeaderProp)]) -> Result<(), WriteError> {
+    for (key, prop) in props {
+        w.write_str(key)?;
+        match prop {
+            HeaderProp::Array(arr) => {
+                w.write_str("ArrayProperty")?;
+                encode_prop(w, |w| {
+                    w.list_of(arr, |w, x| write_rdict(w, x))?;
+                    Ok(None)
+                })?
+            }
+            HeaderProp::Bool(x) => {
+                w.write_str("BoolProperty")?;
+
+                // The size of a bool property is always recorded as zero even though a byte of
+                // data follows
+                encode_prop(w, |w| {
+                    w.write_data(&[u8::from(*x)]);
+                    Ok(Some(0))
+                })?
+            }
+            HeaderProp::Byte { kind, value } => {
+                w.write_str("ByteProperty")?;
+
+                // Only the last string written is included in the size
+                encode_prop(w, |w| {
+                    w.write_str(kind)?;
+                    let start = w.bytes_written();
+                    if let Some(value) = value {
+                        w.write_str(value)?;
+                        Ok(Some(w.bytes_written() - start))
+                    } else {
+                        Ok(Some(kind.len() + 5))
+                    }
+                })?
+            }
+            HeaderProp::Float(x) => {
+                w.write_str("FloatProperty")?;
+                encode_prop(w, |w| {
+                    w.write_f32(*x);
+                    Ok(None)
+                })?
+            }
+            HeaderProp::Int(x) => {
+                w.write_str("IntProperty")?;
+                encode_prop(w, |w| {
+                    w.write_i32(*x);
+                    Ok(None)
+                })?
+            }
+            HeaderProp::Name(x) => {
+                w.write_str("NameProperty")?;
+                encode_prop(w, |w| w.write_text(x).map(|_| None))?
+            }
+            HeaderProp::QWord(x) => {
+                w.write_str("QWordProperty")?;
+                encode_prop(w, |w| {
+                    w.write_u64(*x);
+                    Ok(None)
+                })?
+            }
+            HeaderProp::Str(x) => {
+                w.write_str("StrProperty")?;
+                encode_prop(w, |w| w.write_text(x).map(|_| None))?
+            }
+        }
+    }
+
+    w.write_str("None")
+}
+
+/// Writes the 64 bits that `decode_prop` skips: the first 32 bits are the size of the property
+/// data and the next 32 bits are always zero. The closure writes the property data and can
+/// return a size to override the number of bytes it wrote.
+fn encode_prop<F>(w: &mut CoreWriter, mut f: F) -> Result<(), WriteError>
+where
+    F: FnMut(&mut CoreWriter) -> Result<Option<usize>, WriteError>,
+{
+    let size_offset = w.bytes_written();
+    w.write_i32(0);
+    w.write_i32(0);
+    let start = w.bytes_written();
+    let size = f(w)?.unwrap_or_else(|| w.bytes_written() - start);
+    w.patch_i32(size_offset, size as i32);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -169,7 +434,7 @@ mod tests {
     fn rdict_no_elements() {
         let data = [0x05, 0x00, 0x00, 0x00, b'N', b'o', b'n', b'e', 0x00];
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(res, Vec::new());
     }
 
@@ -178,7 +443,7 @@ mod tests {
         // dd skip=$((0x1269)) count=$((0x12a8 - 0x1269)) if=rumble.replay of=rdict_one.replay bs=1
         let data = include_bytes!("../assets/replays/partial/rdict_one.replay");
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(
             res,
             vec![(
@@ -193,7 +458,7 @@ mod tests {
         // dd skip=$((0x250)) count=$((0x284 - 0x250)) if=rumble.replay of=rdict_int.replay bs=1
         let data = include_bytes!("../assets/replays/partial/rdict_int.replay");
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(res, vec![(String::from("PlayerTeam"), HeaderProp::Int(0))]);
     }
 
@@ -202,7 +467,7 @@ mod tests {
         // dd skip=$((0xa0f)) count=$((0xa3b - 0xa0f)) if=rumble.replay of=rdict_bool.replay bs=1
         let data = include_bytes!("../assets/replays/partial/rdict_bool.replay");
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(res, vec![(String::from("bBot"), HeaderProp::Bool(false))]);
     }
 
@@ -221,7 +486,7 @@ mod tests {
             "../assets/replays/partial/rdict_name.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(
             res,
             vec![(
@@ -238,7 +503,7 @@ mod tests {
             "../assets/replays/partial/rdict_float.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(
             res,
             vec![(String::from("RecordFPS"), HeaderProp::Float(30.0))]
@@ -252,7 +517,7 @@ mod tests {
             "../assets/replays/partial/rdict_qword.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(
             res,
             vec![(
@@ -269,7 +534,7 @@ mod tests {
             "../assets/replays/partial/rdict_array.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         let expected = vec![
             vec![
                 (String::from("frame"), HeaderProp::Int(441)),
@@ -341,7 +606,7 @@ mod tests {
             "../assets/replays/partial/rdict_byte.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(
             res,
             vec![(
@@ -368,14 +633,101 @@ mod tests {
         );
     }
 
+    fn write_props(props: &[(String, HeaderProp)]) -> Vec<u8> {
+        let mut writer = CoreWriter::new();
+        write_rdict(&mut writer, props).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn write_rdict_round_trip() {
+        let files: [&[u8]; 4] = [
+            include_bytes!("../assets/replays/partial/rdict_one.replay"),
+            include_bytes!("../assets/replays/partial/rdict_int.replay"),
+            include_bytes!("../assets/replays/partial/rdict_bool.replay"),
+            &append_none(include_bytes!(
+                "../assets/replays/partial/rdict_qword.replay"
+            )),
+        ];
+
+        for data in files.iter() {
+            let mut parser = CoreParser::new(data);
+            let res = into_owned_props(parse_rdict(&mut parser).unwrap());
+            assert_eq!(&write_props(&res)[..], *data);
+        }
+    }
+
+    #[test]
+    fn write_rdict_array_and_byte() {
+        let array = append_none(include_bytes!(
+            "../assets/replays/partial/rdict_array.replay"
+        ));
+        let byte = append_none(include_bytes!(
+            "../assets/replays/partial/rdict_byte.replay"
+        ));
+
+        for data in [array, byte].iter() {
+            let mut parser = CoreParser::new(&data[..]);
+            let res = into_owned_props(parse_rdict(&mut parser).unwrap());
+            assert_eq!(&write_props(&res)[..], &data[..]);
+        }
+    }
+
     #[test]
     fn rdict_ps4_online_id() {
         let data = append_none(include_bytes!(
             "../assets/replays/partial/rdict_ps4_online_id.replay"
         ));
         let mut parser = CoreParser::new(&data[..]);
-        let res = parse_rdict(&mut parser).unwrap();
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
         assert_eq!(res.len(), 14);
         assert_eq!(res[0], (String::from("TeamSize"), HeaderProp::Int(3)));
     }
+
+    #[test]
+    fn replay_header_array() {
+        let data = append_none(include_bytes!(
+            "../assets/replays/partial/rdict_array.replay"
+        ));
+        let mut parser = CoreParser::new(&data[..]);
+        let res = into_owned_props(parse_rdict(&mut parser).unwrap());
+        let header = ReplayHeader::from_properties(&res);
+        assert_eq!(header.goals.len(), 7);
+        assert_eq!(
+            header.goals[0],
+            HeaderGoal {
+                frame: 441,
+                player_name: String::from("Cakeboss"),
+                player_team: 1,
+            }
+        );
+        assert!(header.extra.is_empty());
+    }
+
+    #[test]
+    fn replay_header_extra() {
+        let props = vec![
+            (String::from("NumFrames"), HeaderProp::Int(10)),
+            (String::from("NumFrames"), HeaderProp::Int(20)),
+            (String::from("TeamSize"), HeaderProp::Str(String::from("3"))),
+            (String::from("MaxChannels"), HeaderProp::Int(1023)),
+            (
+                String::from("PlayerStats"),
+                HeaderProp::Array(vec![vec![(
+                    String::from("Name"),
+                    HeaderProp::Str(String::from("comagoosie")),
+                )]]),
+            ),
+        ];
+
+        let header = ReplayHeader::from_properties(&props);
+        assert_eq!(header.num_frames, Some(10));
+        assert_eq!(header.team_size, None);
+        assert!(header.player_stats.is_empty());
+        assert_eq!(
+            header.extra.keys().collect::<Vec<_>>(),
+            vec!["MaxChannels", "NumFrames", "PlayerStats", "TeamSize"]
+        );
+        assert_eq!(header.extra["NumFrames"], HeaderProp::Int(20));
+    }
 }
@@ -5,7 +5,7 @@
 //!
 //! ## Features
 //!
-//! - ✔ Safe: Stable Rust with no unsafe
+//! - ✔ Safe: Stable Rust with no unsafe (outside of the opt-in `mmap` feature)
 //! - ✔ Fast: Parse a hundred replays per second per CPU core
 //! - ✔ Fuzzed: Extensively fuzzed against potential malicious input
 //! - ✔ Ergonomic: Serialization support is provided through [serde](https://github.com/serde-rs/serde)
@@ -52,35 +52,76 @@
 //! ## Variations
 //!
 //! If you're only interested the header (where tidbits like goals and scores are stored) then you
-//! can achieve an 1000x speedup by directing boxcars to only parse the header.
+//! can achieve an 1000x speedup by directing boxcars to only parse the header. `scan_header` goes
+//! further and reads only the header section from a file, leaving the rest of it unread.
 //!
 //! - By skipping network data one can parse and aggregate thousands of replays in
 //!   under a second to provide an immediate response to the user. Then a full
 //!   parsing of the replay data can provide additional insights when given time.
 //! - By ignoring network data errors, boxcars can still provide details about
-//!   newly patched replays based on the header.
+//!   newly patched replays based on the header and the frames decoded prior to
+//!   the error.
 //!
 //! Boxcars will also check for replay corruption on error, but this can be configured to always
 //! check for corruption or never check.
+//!
+//! Enabling the `rayon` feature decodes the network data between keyframes in parallel. The
+//! decoded frames are identical to those of a serial decode.
+//!
+//! A `NetworkVisitor` receives the network data as it is decoded by `FrameIterator::visit`, so
+//! analyses can run without the decoded frames ever being collected.
+//!
+//! A `NetworkFilter` restricts the decoded network data to the actors and attributes of
+//! interest, which cuts down on the memory and output size of the network frames. Filtered
+//! network data is always decoded serially.
+//!
+//! `ReplayData` reads a replay from any `std::io::Read`, stopping at the end of the content
+//! section. Enabling the `mmap` feature allows `ReplayData` to memory map a replay file instead.
+//!
+//! When the network data of a new patch fails to decode, `ParserBuilder::trace_network` records
+//! every element decoded leading up to the failure alongside the bits it was decoded from, which
+//! `NetworkTrace::listing` renders for inspection. `FrameIterator::guess_attribute` goes a step
+//! further and reports the attribute layouts that let the rest of the network data decode.
 
 #[macro_use]
 extern crate serde;
 
 #[macro_use]
 mod macros;
-pub use self::errors::{AttributeError, FrameContext, FrameError, NetworkError, ParseError};
+pub use self::errors::{
+    AttributeError, ClipError, FrameContext, FrameError, NetworkError, ParseError, ReadError,
+    WriteError,
+};
+pub use self::filter::NetworkFilter;
 pub use self::models::*;
-pub use self::network::attributes::Attribute;
+pub use self::network::attributes::{Attribute, AttributeDecode, AttributeTag};
 pub use self::network::*;
-pub use self::parser::{CrcCheck, NetworkParse, ParserBuilder};
+pub use self::parser::{scan_header, CrcCheck, NetworkParse, ParserBuilder};
+pub use self::reader::ReplayData;
+pub use self::registry::Registry;
+pub use self::world::{ReplayWorld, WorldActor, WorldEvent};
+pub use self::writer::{HeaderEditor, ReplayWriter};
+pub mod anonymize;
 mod bits;
+pub mod clip;
 mod core_parser;
+mod core_writer;
 pub mod crc;
 mod data;
+pub mod entities;
 mod errors;
+mod filter;
 mod header;
 mod models;
 mod network;
 mod parser;
 mod parsing_utils;
+pub mod physics;
+mod reader;
+mod registry;
 mod serde_utils;
+#[cfg(test)]
+mod test_utils;
+pub mod timeline;
+mod world;
+mod writer;
@@ -8,10 +8,12 @@
 /// numeric/string types). Asking "why JSON" would be next logical step, and that's due to other
 /// rocket league replay parsers (like Octane) using JSON; however, the output of this library is
 /// not compatible with that of other rocket league replay parsers.
+use crate::errors::FrameError;
 use crate::network::Frame;
 use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct};
 use serde::{Serialize, Serializer};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 /// The structure that a rocket league replay is parsed into.
 #[derive(Serialize, PartialEq, Debug, Clone)]
@@ -41,10 +43,157 @@ pub struct Replay {
     pub net_cache: Vec<ClassNetCache>,
 }
 
+impl Replay {
+    /// Returns a typed view of the header properties
+    ///
+    /// ```
+    /// let data = include_bytes!("../assets/replays/good/rumble.replay");
+    /// let replay = boxcars::ParserBuilder::new(&data[..])
+    ///     .never_parse_network_data()
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let header = replay.header();
+    /// assert_eq!(header.num_frames, Some(7744));
+    /// assert_eq!(header.goals.len(), 7);
+    /// assert_eq!(header.goals[0].player_name, "Cakeboss");
+    /// ```
+    pub fn header(&self) -> ReplayHeader {
+        ReplayHeader::from_properties(&self.properties)
+    }
+}
+
+/// The header section of a replay, which is all that is parsed by `ParserBuilder::header_only`
+/// and `scan_header`. The fields are the same as the corresponding fields of a `Replay`.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct HeaderSection {
+    pub header_size: i32,
+    pub header_crc: u32,
+    pub major_version: i32,
+    pub minor_version: i32,
+    pub net_version: Option<i32>,
+    pub game_type: String,
+
+    #[serde(serialize_with = "pair_vec")]
+    pub properties: Vec<(String, HeaderProp)>,
+}
+
+impl HeaderSection {
+    /// Returns a typed view of the header properties
+    pub fn header(&self) -> ReplayHeader {
+        ReplayHeader::from_properties(&self.properties)
+    }
+}
+
+/// A replay whose text is borrowed from the replay data where possible, which is returned by
+/// `ParserBuilder::parse_borrowed`. Text that is ASCII, which is nearly all the header keys,
+/// levels, packages, objects, names, and class indices, is borrowed instead of allocated. The
+/// fields are otherwise the same as the corresponding fields of a `Replay`.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct BorrowedReplay<'a> {
+    pub header_size: i32,
+    pub header_crc: u32,
+    pub major_version: i32,
+    pub minor_version: i32,
+    pub net_version: Option<i32>,
+    pub game_type: Cow<'a, str>,
+
+    #[serde(serialize_with = "pair_vec")]
+    pub properties: Vec<(Cow<'a, str>, BorrowedHeaderProp<'a>)>,
+    pub content_size: i32,
+    pub content_crc: u32,
+    pub network_frames: Option<NetworkFrames>,
+    pub levels: Vec<Cow<'a, str>>,
+    pub keyframes: Vec<KeyFrame>,
+    pub debug_info: Vec<DebugInfo>,
+    pub tick_marks: Vec<TickMark>,
+    pub packages: Vec<Cow<'a, str>>,
+    pub objects: Vec<Cow<'a, str>>,
+    pub names: Vec<Cow<'a, str>>,
+    pub class_indices: Vec<BorrowedClassIndex<'a>>,
+    pub net_cache: Vec<ClassNetCache>,
+}
+
+impl<'a> BorrowedReplay<'a> {
+    /// Returns a typed view of the header properties
+    pub fn header(&self) -> ReplayHeader {
+        ReplayHeader::from_properties(&into_owned_props(self.properties.clone()))
+    }
+
+    /// Copies the borrowed text so that the replay no longer borrows from the replay data
+    pub fn into_owned(self) -> Replay {
+        Replay {
+            header_size: self.header_size,
+            header_crc: self.header_crc,
+            major_version: self.major_version,
+            minor_version: self.minor_version,
+            net_version: self.net_version,
+            game_type: self.game_type.into_owned(),
+            properties: into_owned_props(self.properties),
+            content_size: self.content_size,
+            content_crc: self.content_crc,
+            network_frames: self.network_frames,
+            levels: into_owned_text(self.levels),
+            keyframes: self.keyframes,
+            debug_info: self.debug_info,
+            tick_marks: self.tick_marks,
+            packages: into_owned_text(self.packages),
+            objects: into_owned_text(self.objects),
+            names: into_owned_text(self.names),
+            class_indices: self
+                .class_indices
+                .into_iter()
+                .map(BorrowedClassIndex::into_owned)
+                .collect(),
+            net_cache: self.net_cache,
+        }
+    }
+}
+
+fn into_owned_text(text: Vec<Cow<'_, str>>) -> Vec<String> {
+    text.into_iter().map(Cow::into_owned).collect()
+}
+
+pub(crate) fn into_owned_props(
+    props: Vec<(Cow<'_, str>, BorrowedHeaderProp<'_>)>,
+) -> Vec<(String, HeaderProp)> {
+    props
+        .into_iter()
+        .map(|(key, prop)| (key.into_owned(), prop.into_owned()))
+        .collect()
+}
+
 /// The frames decoded from the network data
 #[derive(Serialize, PartialEq, Debug, Clone)]
 pub struct NetworkFrames {
     pub frames: Vec<Frame>,
+
+    /// The frames that failed to decode. Only populated when parsing with
+    /// `NetworkParse::BestEffort` or `NetworkParse::IgnoreOnError`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<NetworkFailure>,
+
+    /// Whether a frame failed to decode and none of the frames after it are present
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// A frame that failed to decode. With `NetworkParse::BestEffort`, the frames between the failed
+/// frame and the frame that decoding resumed at are empty placeholders with times spread evenly
+/// up to the time of the keyframe that decoding resumed at.
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct NetworkFailure {
+    /// The index of the frame that failed to decode
+    pub frame: usize,
+
+    /// The bit offset into the network data where decoding failed
+    pub bit_offset: usize,
+
+    #[serde(serialize_with = "crate::serde_utils::display_it")]
+    pub error: FrameError,
+
+    /// The index of the keyframe's frame where decoding resumed, if any
+    pub resumed_at: Option<usize>,
 }
 
 /// In Rocket league replays, there are tickmarks that typically represent a significant event in
@@ -212,6 +361,118 @@ impl HeaderProp {
     }
 }
 
+/// A `HeaderProp` with its text borrowed from the replay data where possible
+#[derive(PartialEq, Debug, Clone)]
+pub enum BorrowedHeaderProp<'a> {
+    Array(Vec<Vec<(Cow<'a, str>, BorrowedHeaderProp<'a>)>>),
+    Bool(bool),
+    Byte {
+        kind: Cow<'a, str>,
+        value: Option<Cow<'a, str>>,
+    },
+    Float(f32),
+    Int(i32),
+    Name(Cow<'a, str>),
+    QWord(u64),
+    Str(Cow<'a, str>),
+}
+
+impl<'a> BorrowedHeaderProp<'a> {
+    /// If the `BorrowedHeaderProp` is an int, returns the value
+    pub fn as_i32(&self) -> Option<i32> {
+        if let BorrowedHeaderProp::Int(val) = self {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+
+    /// If the `BorrowedHeaderProp` is an string, returns the value
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            BorrowedHeaderProp::Name(val) => Some(val.as_ref()),
+            BorrowedHeaderProp::Str(val) => Some(val.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Copies the borrowed text so that the property no longer borrows from the replay data
+    pub fn into_owned(self) -> HeaderProp {
+        match self {
+            BorrowedHeaderProp::Array(arr) => {
+                HeaderProp::Array(arr.into_iter().map(into_owned_props).collect())
+            }
+            BorrowedHeaderProp::Bool(x) => HeaderProp::Bool(x),
+            BorrowedHeaderProp::Byte { kind, value } => HeaderProp::Byte {
+                kind: kind.into_owned(),
+                value: value.map(Cow::into_owned),
+            },
+            BorrowedHeaderProp::Float(x) => HeaderProp::Float(x),
+            BorrowedHeaderProp::Int(x) => HeaderProp::Int(x),
+            BorrowedHeaderProp::Name(x) => HeaderProp::Name(x.into_owned()),
+            BorrowedHeaderProp::QWord(x) => HeaderProp::QWord(x),
+            BorrowedHeaderProp::Str(x) => HeaderProp::Str(x.into_owned()),
+        }
+    }
+}
+
+/// Typed view of the well-known header properties. Properties with an unknown key, or with a
+/// known key but an unexpected shape, are kept in `extra`.
+#[derive(Serialize, PartialEq, Debug, Clone, Default)]
+pub struct ReplayHeader {
+    pub team_size: Option<i32>,
+    pub team0_score: Option<i32>,
+    pub team1_score: Option<i32>,
+    pub goals: Vec<HeaderGoal>,
+    pub player_stats: Vec<HeaderPlayerStats>,
+    pub date: Option<String>,
+    pub map_name: Option<String>,
+    pub id: Option<String>,
+    pub replay_name: Option<String>,
+    pub match_type: Option<String>,
+    pub build_version: Option<String>,
+    pub num_frames: Option<i32>,
+    pub record_fps: Option<f32>,
+    pub highlights: Vec<HeaderHighlight>,
+    pub extra: BTreeMap<String, HeaderProp>,
+}
+
+/// An entry of the `Goals` header property
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct HeaderGoal {
+    pub frame: i32,
+    pub player_name: String,
+    pub player_team: i32,
+}
+
+/// An entry of the `PlayerStats` header property
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct HeaderPlayerStats {
+    pub name: String,
+
+    /// The platform, such as `OnlinePlatform_Steam`
+    pub platform: Option<String>,
+    pub online_id: Option<u64>,
+    pub team: i32,
+    pub score: i32,
+    pub goals: i32,
+    pub assists: i32,
+    pub saves: i32,
+    pub shots: i32,
+    pub bot: bool,
+}
+
+/// An entry of the `HighLights` header property
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct HeaderHighlight {
+    pub frame: i32,
+    pub car_name: String,
+    pub ball_name: String,
+
+    /// Only present in newer replays, where it is often `None`
+    pub goal_actor_name: Option<String>,
+}
+
 /// Debugging info stored in the replay if debugging is enabled.
 #[derive(Serialize, PartialEq, Debug, Clone)]
 pub struct DebugInfo {
@@ -230,6 +491,25 @@ pub struct ClassIndex {
     pub index: i32,
 }
 
+/// A `ClassIndex` with the class borrowed from the replay data
+#[derive(Serialize, PartialEq, Debug, Clone)]
+pub struct BorrowedClassIndex<'a> {
+    /// Should be equivalent to `BorrowedReplay::objects(self.index)`
+    pub class: Cow<'a, str>,
+
+    /// The index that the object appears in the `BorrowedReplay::objects`
+    pub index: i32,
+}
+
+impl<'a> BorrowedClassIndex<'a> {
+    pub fn into_owned(self) -> ClassIndex {
+        ClassIndex {
+            class: self.class.into_owned(),
+            index: self.index,
+        }
+    }
+}
+
 /// A mapping between an object (that's an attribute)'s index and what its id will be when encoded
 /// in the network data
 #[derive(Serialize, PartialEq, Debug, Clone, Copy)]
@@ -271,7 +551,7 @@ where
     S: Serializer,
 {
     let mut state = serializer.serialize_map(Some(inp.len()))?;
-    for &(ref key, ref val) in inp.iter() {
+    for (key, val) in inp.iter() {
         state.serialize_key(key)?;
         state.serialize_value(val)?;
     }
@@ -318,6 +598,44 @@ impl Serialize for HeaderProp {
     }
 }
 
+/// Serializes the same as a `HeaderProp`
+impl<'a> Serialize for BorrowedHeaderProp<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            BorrowedHeaderProp::Array(ref x) => {
+                let mut state = serializer.serialize_seq(Some(x.len()))?;
+                for inner in x {
+                    let mut els = HashMap::new();
+                    for (key, val) in inner.iter() {
+                        els.insert(key, val);
+                    }
+                    state.serialize_element(&els)?;
+                }
+                state.end()
+            }
+            BorrowedHeaderProp::Bool(ref x) => serializer.serialize_bool(*x),
+            BorrowedHeaderProp::Byte {
+                ref kind,
+                ref value,
+            } => {
+                let mut byte = serializer.serialize_struct("Byte", 2)?;
+                byte.serialize_field("kind", kind)?;
+                byte.serialize_field("value", value)?;
+                byte.end()
+            }
+            BorrowedHeaderProp::Float(ref x) => serializer.serialize_f32(*x),
+            BorrowedHeaderProp::Int(ref x) => serializer.serialize_i32(*x),
+            BorrowedHeaderProp::QWord(ref x) => serializer.collect_str(x),
+            BorrowedHeaderProp::Name(ref x) | BorrowedHeaderProp::Str(ref x) => {
+                serializer.serialize_str(x)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,13 +1,17 @@
-use crate::bits::RlBits;
+use crate::bits::{BitWriter, RlBits};
 use crate::errors::AttributeError;
 use crate::network::{ActorId, ObjectId, Quaternion, Rotation, Vector3f, VersionTriplet};
 use crate::parsing_utils::{decode_utf16, decode_windows1252};
 use bitter::{BitReader, LittleEndianReader};
 use encoding_rs::WINDOWS_1252;
+use std::cmp;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum AttributeTag {
+/// Determines how an attribute is decoded from the network data. Each tag corresponds to an
+/// `Attribute` variant of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AttributeTag {
     Boolean,
     Byte,
     AppliedDamage,
@@ -51,6 +55,52 @@ pub(crate) enum AttributeTag {
     PickupInfo,
 }
 
+impl AttributeTag {
+    /// Every tag that decodes an attribute (ie: all but `NotImplemented`)
+    pub(crate) const DECODABLE: [AttributeTag; 40] = [
+        AttributeTag::Boolean,
+        AttributeTag::Byte,
+        AttributeTag::AppliedDamage,
+        AttributeTag::DamageState,
+        AttributeTag::CamSettings,
+        AttributeTag::ClubColors,
+        AttributeTag::Demolish,
+        AttributeTag::DemolishFx,
+        AttributeTag::Enum,
+        AttributeTag::Explosion,
+        AttributeTag::ExtendedExplosion,
+        AttributeTag::FlaggedByte,
+        AttributeTag::ActiveActor,
+        AttributeTag::Float,
+        AttributeTag::GameMode,
+        AttributeTag::Int,
+        AttributeTag::Int64,
+        AttributeTag::Loadout,
+        AttributeTag::TeamLoadout,
+        AttributeTag::Location,
+        AttributeTag::MusicStinger,
+        AttributeTag::Pickup,
+        AttributeTag::PickupNew,
+        AttributeTag::PlayerHistoryKey,
+        AttributeTag::QWordString,
+        AttributeTag::Welded,
+        AttributeTag::RigidBody,
+        AttributeTag::Title,
+        AttributeTag::TeamPaint,
+        AttributeTag::String,
+        AttributeTag::UniqueId,
+        AttributeTag::Reservation,
+        AttributeTag::PartyLeader,
+        AttributeTag::PrivateMatchSettings,
+        AttributeTag::LoadoutOnline,
+        AttributeTag::LoadoutsOnline,
+        AttributeTag::StatEvent,
+        AttributeTag::RotationTag,
+        AttributeTag::RepStatTitle,
+        AttributeTag::PickupInfo,
+    ];
+}
+
 /// The attributes for updated actors in the network data.
 ///
 /// The vast majority of attributes in the network data are rigid bodies. As a performance
@@ -102,6 +152,7 @@ pub enum Attribute {
     Rotation(Rotation),
     RepStatTitle(RepStatTitle),
     PickupInfo(PickupInfo),
+    Raw(RawAttribute),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -382,6 +433,116 @@ pub struct PickupInfo {
     pub unknown2: bool,
 }
 
+/// The undecoded bits of an attribute. Meant for an `AttributeDecode` implementation that knows
+/// how many bits an attribute spans but not what they represent. The bits are written back as is
+/// when the replay is encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RawAttribute {
+    pub bit_len: u32,
+    pub data: Vec<u8>,
+}
+
+impl RawAttribute {
+    /// Reads the next `bit_len` bits
+    pub fn read(bits: &mut LittleEndianReader<'_>, bit_len: u32) -> Option<RawAttribute> {
+        let remainder = bit_len % 8;
+        let mut data = Vec::with_capacity(bit_len as usize / 8 + 1);
+        for _ in 0..bit_len / 8 {
+            data.push(bits.read_u8()?);
+        }
+
+        if remainder > 0 {
+            data.push(bits.read_bits(remainder)? as u8);
+        }
+
+        Some(RawAttribute { bit_len, data })
+    }
+
+    fn encode(&self, bits: &mut BitWriter) -> Result<(), AttributeError> {
+        // Every byte but the last must be filled
+        let data_bits = self.data.len() as u64 * 8;
+        let bit_len = u64::from(self.bit_len);
+        if data_bits < bit_len || data_bits >= bit_len + 8 {
+            return Err(AttributeError::ValueOutOfRange("Raw attribute length"));
+        }
+
+        for (i, byte) in self.data.iter().enumerate() {
+            let len = cmp::min(8, self.bit_len - i as u32 * 8);
+            bits.write_bits(len, u64::from(*byte));
+        }
+
+        Ok(())
+    }
+}
+
+/// Details about the attribute being decoded by an `AttributeDecode` implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeContext<'a> {
+    /// The name of the attribute (eg: "TAGame.Car_TA:TeamPaint")
+    pub attribute: &'a str,
+
+    /// The actor that the attribute is updated on
+    pub actor: ActorId,
+
+    /// The object of the actor
+    pub actor_object: ObjectId,
+
+    pub major_version: i32,
+    pub minor_version: i32,
+    pub net_version: i32,
+}
+
+/// Decodes an attribute with a bit layout that boxcars doesn't know about. An implementation is
+/// registered for an attribute name with `Registry::add_decoder`, and takes precedence over the
+/// attribute tag of the attribute.
+///
+/// The returned attribute can be any variant. When the replay is written back out, a `Raw`
+/// attribute is written as is, while other variants are encoded according to the attribute tag
+/// registered for the attribute.
+///
+/// ```
+/// use boxcars::attributes::{AttributeContext, AttributeDecode, RawAttribute};
+/// use boxcars::{Attribute, AttributeError, ParserBuilder, Registry};
+/// use bitter::LittleEndianReader;
+///
+/// struct TeamPaintBits;
+///
+/// impl AttributeDecode for TeamPaintBits {
+///     fn decode(
+///         &self,
+///         bits: &mut LittleEndianReader<'_>,
+///         _ctx: &AttributeContext<'_>,
+///     ) -> Result<Attribute, AttributeError> {
+///         RawAttribute::read(bits, 88)
+///             .map(Attribute::Raw)
+///             .ok_or(AttributeError::NotEnoughDataFor("Team Paint"))
+///     }
+/// }
+///
+/// let mut registry = Registry::new();
+/// registry.add_decoder("TAGame.Car_TA:TeamPaint", TeamPaintBits);
+///
+/// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+/// let replay = ParserBuilder::new(&data[..])
+///     .with_registry(&registry)
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = replay.network_frames.unwrap().frames;
+/// assert!(frames
+///     .iter()
+///     .flat_map(|x| x.updated_actors.iter())
+///     .any(|x| matches!(x.attribute, Attribute::Raw(_))));
+/// ```
+pub trait AttributeDecode: Send + Sync {
+    fn decode(
+        &self,
+        bits: &mut LittleEndianReader<'_>,
+        ctx: &AttributeContext<'_>,
+    ) -> Result<Attribute, AttributeError>;
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct ProductValueDecoder {
     version: VersionTriplet,
@@ -405,7 +566,7 @@ impl ProductValueDecoder {
         let title_ind = name_obj_ind
             .get("TAGame.ProductAttribute_TitleID_TA")
             .map(|x| usize::from(x[0]) as u32)
-            .unwrap_or(0) as u32;
+            .unwrap_or(0);
         let special_edition_ind = name_obj_ind
             .get("TAGame.ProductAttribute_SpecialEdition_TA")
             .map(|x| usize::from(x[0]) as u32)
@@ -472,6 +633,91 @@ impl ProductValueDecoder {
             Some(ProductValue::Absent)
         }
     }
+
+    /// Reads past the product value like `decode` without allocating the title
+    pub fn skip(
+        &self,
+        bits: &mut LittleEndianReader<'_>,
+        obj_ind: u32,
+        buf: &mut [u8],
+    ) -> Option<()> {
+        if obj_ind == self.title_ind {
+            skip_text(bits, buf).ok()
+        } else {
+            self.decode(bits, obj_ind, buf).map(drop)
+        }
+    }
+
+    /// The inverse of `decode`. The product value must be the variant that the object index
+    /// decodes to.
+    pub fn encode(
+        &self,
+        bits: &mut BitWriter,
+        obj_ind: u32,
+        value: &ProductValue,
+    ) -> Result<(), AttributeError> {
+        let new_color = self.version >= VersionTriplet(868, 23, 8);
+        let new_paint = self.version >= VersionTriplet(868, 18, 0);
+        match value {
+            ProductValue::NewColor(x) if obj_ind == self.color_ind && new_color => {
+                bits.write_i32(*x)
+            }
+            ProductValue::OldColor(x) if obj_ind == self.color_ind && !new_color => {
+                if *x >= 1 << 31 {
+                    return Err(AttributeError::ValueOutOfRange("Product color"));
+                }
+                bits.write_bit(true);
+                bits.write_bits(31, u64::from(*x));
+            }
+            ProductValue::NoColor if obj_ind == self.color_ind && !new_color => {
+                bits.write_bit(false)
+            }
+            ProductValue::NewPaint(x) if obj_ind == self.painted_ind && new_paint => {
+                encode_product_31(bits, *x)?
+            }
+            ProductValue::OldPaint(x) if obj_ind == self.painted_ind && !new_paint => {
+                encode_product_14(bits, *x)?
+            }
+            ProductValue::Title(x) if obj_ind == self.title_ind => encode_text(bits, x)?,
+            ProductValue::SpecialEdition(x) if obj_ind == self.special_edition_ind => {
+                encode_product_31(bits, *x)?
+            }
+            ProductValue::NewTeamEdition(x) if obj_ind == self.team_edition_ind && new_paint => {
+                encode_product_31(bits, *x)?
+            }
+            ProductValue::OldTeamEdition(x) if obj_ind == self.team_edition_ind && !new_paint => {
+                encode_product_14(bits, *x)?
+            }
+            ProductValue::Absent
+                if ![
+                    self.color_ind,
+                    self.painted_ind,
+                    self.title_ind,
+                    self.special_edition_ind,
+                    self.team_edition_ind,
+                ]
+                .contains(&obj_ind) => {}
+            _ => return Err(AttributeError::UnexpectedAttribute),
+        }
+
+        Ok(())
+    }
+}
+
+fn encode_product_31(bits: &mut BitWriter, val: u32) -> Result<(), AttributeError> {
+    if val >= 1 << 31 {
+        return Err(AttributeError::ValueOutOfRange("Product value"));
+    }
+    bits.write_bits(31, u64::from(val));
+    Ok(())
+}
+
+fn encode_product_14(bits: &mut BitWriter, val: u32) -> Result<(), AttributeError> {
+    if val >= 14 {
+        return Err(AttributeError::ValueOutOfRange("Product value"));
+    }
+    bits.write_bits_max_computed(3, 14, u64::from(val));
+    Ok(())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -533,6 +779,106 @@ impl AttributeDecoder {
         }
     }
 
+    /// Reads past the attribute like `decode`, but without allocating the attribute. Text is
+    /// read without being decoded, so text with invalid characters is skipped instead of
+    /// returning an error.
+    pub fn skip(
+        &self,
+        tag: AttributeTag,
+        bits: &mut LittleEndianReader<'_>,
+        buf: &mut [u8],
+    ) -> Result<(), AttributeError> {
+        let net_version = self.version.net_version();
+        match tag {
+            AttributeTag::CamSettings => self
+                ._decode_cam_settings(bits)
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("Cam Settings")),
+            AttributeTag::Demolish => self
+                ._decode_demolish(bits)
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("Demolish")),
+            AttributeTag::DemolishFx => self
+                ._decode_demolish_fx(bits)
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("DemolishFx")),
+            AttributeTag::Loadout => decode_loadout(bits)
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("Loadout")),
+            AttributeTag::TeamLoadout => decode_loadout(bits)
+                .and_then(|_| decode_loadout(bits))
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("Team Loadout")),
+            AttributeTag::QWordString if self.is_rl_223 => skip_text(bits, buf),
+            AttributeTag::String => skip_text(bits, buf),
+            AttributeTag::UniqueId => skip_unique_id(bits, net_version, buf).map(drop),
+            AttributeTag::Reservation => {
+                let component = "Reservation";
+                get_or!(bits.read_bits(3), component)?;
+                if skip_unique_id(bits, net_version, buf)? != 0 {
+                    skip_text(bits, buf)?;
+                }
+
+                get_or!(bits.read_bits(2), component)?;
+                if self.version >= VersionTriplet(868, 12, 0) {
+                    get_or!(bits.read_bits(6), component)?;
+                }
+                Ok(())
+            }
+            AttributeTag::PartyLeader => {
+                let system_id = bits
+                    .read_u8()
+                    .ok_or(AttributeError::NotEnoughDataFor("Party Leader"))?;
+                if system_id != 0 {
+                    skip_unique_id_with_system_id(bits, net_version, system_id, buf)?;
+                }
+                Ok(())
+            }
+            AttributeTag::PrivateMatchSettings => {
+                let component = "Private Match";
+                skip_text(bits, buf)?;
+                get_or!(bits.read_u64(), component)?;
+                skip_text(bits, buf)?;
+                skip_text(bits, buf)?;
+                get_or!(bits.read_bit(), component)?;
+                Ok(())
+            }
+            AttributeTag::LoadoutOnline => self
+                .skip_online_loadout(bits, buf)
+                .ok_or(AttributeError::NotEnoughDataFor("Loadout Online")),
+            AttributeTag::LoadoutsOnline => self
+                .skip_online_loadout(bits, buf)
+                .and_then(|_| self.skip_online_loadout(bits, buf))
+                .and_then(|_| bits.read_bits(2))
+                .map(drop)
+                .ok_or(AttributeError::NotEnoughDataFor("Loadouts online")),
+            AttributeTag::RepStatTitle => {
+                let component = "RepStatTitle";
+                get_or!(bits.read_bit(), component)?;
+                skip_text(bits, buf)?;
+                get_or!(bits.read_bit(), component)?;
+                get_or!(bits.read_u64(), component)?;
+                Ok(())
+            }
+
+            // The remaining attributes are decoded without allocating
+            _ => self.decode(tag, bits, buf).map(drop),
+        }
+    }
+
+    fn skip_online_loadout(&self, bits: &mut LittleEndianReader<'_>, buf: &mut [u8]) -> Option<()> {
+        let size = bits.read_u8()?;
+        for _ in 0..size {
+            let attribute_size = bits.read_u8()?;
+            for _ in 0..attribute_size {
+                bits.read_bit()?;
+                let obj_ind = bits.read_u32()?;
+                self.product_decoder.skip(bits, obj_ind, buf)?;
+            }
+        }
+        Some(())
+    }
+
     pub fn decode_byte(
         &self,
         bits: &mut LittleEndianReader<'_>,
@@ -1314,6 +1660,476 @@ impl AttributeDecoder {
     }
 }
 
+/// The inverse of the `AttributeDecoder`: writes an attribute so that decoding it with the same
+/// attribute tag returns an equivalent attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AttributeEncoder {
+    pub(crate) version: VersionTriplet,
+    pub(crate) product_decoder: ProductValueDecoder,
+    pub(crate) is_rl_223: bool,
+}
+
+impl AttributeEncoder {
+    pub fn encode(
+        &self,
+        tag: AttributeTag,
+        attr: &Attribute,
+        bits: &mut BitWriter,
+    ) -> Result<(), AttributeError> {
+        let net_version = self.version.net_version();
+        match (tag, attr) {
+            (_, Attribute::Raw(x)) => x.encode(bits)?,
+            (AttributeTag::Boolean, Attribute::Boolean(x)) => bits.write_bit(*x),
+            (AttributeTag::Byte, Attribute::Byte(x)) => bits.write_u8(*x),
+            (AttributeTag::AppliedDamage, Attribute::AppliedDamage(x)) => {
+                bits.write_u8(x.id);
+                encode_vector(bits, &x.position, net_version)?;
+                bits.write_i32(x.damage_index);
+                bits.write_i32(x.total_damage);
+            }
+            (AttributeTag::DamageState, Attribute::DamageState(x)) => {
+                bits.write_u8(x.tile_state);
+                bits.write_bit(x.damaged);
+                bits.write_i32(x.offender.0);
+                encode_vector(bits, &x.ball_position, net_version)?;
+                bits.write_bit(x.direct_hit);
+                bits.write_bit(x.unknown1);
+            }
+            (AttributeTag::CamSettings, Attribute::CamSettings(x)) => {
+                bits.write_f32(x.fov);
+                bits.write_f32(x.height);
+                bits.write_f32(x.angle);
+                bits.write_f32(x.distance);
+                bits.write_f32(x.stiffness);
+                bits.write_f32(x.swivel);
+                if self.version >= VersionTriplet(868, 20, 0) {
+                    bits.write_f32(x.transition.unwrap_or(0.0));
+                }
+            }
+            (AttributeTag::ClubColors, Attribute::ClubColors(x)) => {
+                bits.write_bit(x.blue_flag);
+                bits.write_u8(x.blue_color);
+                bits.write_bit(x.orange_flag);
+                bits.write_u8(x.orange_color);
+            }
+            (AttributeTag::Demolish, Attribute::Demolish(x)) => {
+                bits.write_bit(x.attacker_flag);
+                bits.write_i32(x.attacker.0);
+                bits.write_bit(x.victim_flag);
+                bits.write_i32(x.victim.0);
+                encode_vector(bits, &x.attack_velocity, net_version)?;
+                encode_vector(bits, &x.victim_velocity, net_version)?;
+            }
+            (AttributeTag::DemolishFx, Attribute::DemolishFx(x)) => {
+                bits.write_bit(x.custom_demo_flag);
+                bits.write_i32(x.custom_demo_id);
+                bits.write_bit(x.attacker_flag);
+                bits.write_i32(x.attacker.0);
+                bits.write_bit(x.victim_flag);
+                bits.write_i32(x.victim.0);
+                encode_vector(bits, &x.attack_velocity, net_version)?;
+                encode_vector(bits, &x.victim_velocity, net_version)?;
+            }
+            (AttributeTag::Enum, Attribute::Enum(x)) => {
+                encode_bits(bits, 11, u64::from(*x), "Enum")?
+            }
+            (AttributeTag::Explosion, Attribute::Explosion(x)) => {
+                encode_explosion(bits, x, net_version)?
+            }
+            (AttributeTag::ExtendedExplosion, Attribute::ExtendedExplosion(x)) => {
+                encode_explosion(bits, &x.explosion, net_version)?;
+                bits.write_bit(x.unknown1);
+                bits.write_i32(x.secondary_actor.0);
+            }
+            (AttributeTag::ActiveActor, Attribute::ActiveActor(x)) => {
+                bits.write_bit(x.active);
+                bits.write_i32(x.actor.0);
+            }
+            (AttributeTag::FlaggedByte, Attribute::FlaggedByte(flag, x)) => {
+                bits.write_bit(*flag);
+                bits.write_u8(*x);
+            }
+            (AttributeTag::Float, Attribute::Float(x)) => bits.write_f32(*x),
+            (AttributeTag::GameMode, Attribute::GameMode(_, x)) => {
+                let init = if self.version < VersionTriplet(868, 12, 0) {
+                    2
+                } else {
+                    8
+                };
+                encode_bits(bits, init, u64::from(*x), "Game Mode")?
+            }
+            (AttributeTag::Int, Attribute::Int(x)) => bits.write_i32(*x),
+            (AttributeTag::Int64, Attribute::Int64(x)) => bits.write_i64(*x),
+            (AttributeTag::Loadout, Attribute::Loadout(x)) => encode_loadout(bits, x),
+            (AttributeTag::TeamLoadout, Attribute::TeamLoadout(x)) => {
+                encode_loadout(bits, &x.blue);
+                encode_loadout(bits, &x.orange);
+            }
+            (AttributeTag::Location, Attribute::Location(x)) => {
+                encode_vector(bits, x, net_version)?
+            }
+            (AttributeTag::MusicStinger, Attribute::MusicStinger(x)) => {
+                bits.write_bit(x.flag);
+                bits.write_u32(x.cue);
+                bits.write_u8(x.trigger);
+            }
+            (AttributeTag::Pickup, Attribute::Pickup(x)) => {
+                encode_instigator(bits, x.instigator);
+                bits.write_bit(x.picked_up);
+            }
+            (AttributeTag::PickupNew, Attribute::PickupNew(x)) => {
+                encode_instigator(bits, x.instigator);
+                bits.write_u8(x.picked_up);
+            }
+            (AttributeTag::PlayerHistoryKey, Attribute::PlayerHistoryKey(x)) => {
+                encode_bits(bits, 14, u64::from(*x), "PlayerHistoryKey")?
+            }
+            (AttributeTag::QWordString, Attribute::String(x)) if self.is_rl_223 => {
+                encode_text(bits, x)?
+            }
+            (AttributeTag::QWordString, Attribute::QWord(x)) if !self.is_rl_223 => {
+                bits.write_u64(*x)
+            }
+            (AttributeTag::Welded, Attribute::Welded(x)) => {
+                bits.write_bit(x.active);
+                bits.write_i32(x.actor.0);
+                encode_vector(bits, &x.offset, net_version)?;
+                bits.write_f32(x.mass);
+                x.rotation.encode(bits);
+            }
+            (AttributeTag::RigidBody, Attribute::RigidBody(x)) => {
+                self.encode_rigid_body(bits, x)?
+            }
+            (
+                AttributeTag::Title,
+                Attribute::Title(
+                    unknown1,
+                    unknown2,
+                    unknown3,
+                    unknown4,
+                    unknown5,
+                    unknown6,
+                    unknown7,
+                    unknown8,
+                ),
+            ) => {
+                bits.write_bit(*unknown1);
+                bits.write_bit(*unknown2);
+                bits.write_u32(*unknown3);
+                bits.write_u32(*unknown4);
+                bits.write_u32(*unknown5);
+                bits.write_u32(*unknown6);
+                bits.write_u32(*unknown7);
+                bits.write_bit(*unknown8);
+            }
+            (AttributeTag::TeamPaint, Attribute::TeamPaint(x)) => {
+                bits.write_u8(x.team);
+                bits.write_u8(x.primary_color);
+                bits.write_u8(x.accent_color);
+                bits.write_u32(x.primary_finish);
+                bits.write_u32(x.accent_finish);
+            }
+            (AttributeTag::NotImplemented, _) => return Err(AttributeError::Unimplemented),
+            (AttributeTag::String, Attribute::String(x)) => encode_text(bits, x)?,
+            (AttributeTag::UniqueId, Attribute::UniqueId(x)) => {
+                encode_unique_id(bits, x, net_version)?
+            }
+            (AttributeTag::Reservation, Attribute::Reservation(x)) => {
+                self.encode_reservation(bits, x)?
+            }
+            (AttributeTag::PartyLeader, Attribute::PartyLeader(None)) => bits.write_u8(0),
+            (AttributeTag::PartyLeader, Attribute::PartyLeader(Some(x))) => {
+                if x.system_id == 0 {
+                    return Err(AttributeError::ValueOutOfRange("Party Leader"));
+                }
+                encode_unique_id(bits, x, net_version)?
+            }
+            (AttributeTag::PrivateMatchSettings, Attribute::PrivateMatch(x)) => {
+                encode_text(bits, &x.mutators)?;
+                bits.write_u32(x.joinable_by);
+                bits.write_u32(x.max_players);
+                encode_text(bits, &x.game_name)?;
+                encode_text(bits, &x.password)?;
+                bits.write_bit(x.flag);
+            }
+            (AttributeTag::LoadoutOnline, Attribute::LoadoutOnline(x)) => {
+                self.encode_online_loadout(bits, x)?
+            }
+            (AttributeTag::LoadoutsOnline, Attribute::LoadoutsOnline(x)) => {
+                self.encode_online_loadout(bits, &x.blue)?;
+                self.encode_online_loadout(bits, &x.orange)?;
+                bits.write_bit(x.unknown1);
+                bits.write_bit(x.unknown2);
+            }
+            (AttributeTag::StatEvent, Attribute::StatEvent(x)) => {
+                bits.write_bit(x.unknown1);
+                bits.write_i32(x.object_id);
+            }
+            (AttributeTag::RotationTag, Attribute::Rotation(x)) => x.encode(bits),
+            (AttributeTag::RepStatTitle, Attribute::RepStatTitle(x)) => {
+                bits.write_bit(x.unknown);
+                encode_text(bits, &x.name)?;
+                bits.write_bit(x.unknown2);
+                bits.write_u32(x.index);
+                bits.write_u32(x.value);
+            }
+            (AttributeTag::PickupInfo, Attribute::PickupInfo(x)) => {
+                bits.write_bit(x.active);
+                bits.write_i32(x.actor.0);
+                bits.write_bit(x.items_are_preview);
+                bits.write_bit(x.unknown);
+                bits.write_bit(x.unknown2);
+            }
+            _ => return Err(AttributeError::UnexpectedAttribute),
+        }
+
+        Ok(())
+    }
+
+    fn encode_rigid_body(&self, bits: &mut BitWriter, x: &RigidBody) -> Result<(), AttributeError> {
+        let net_version = self.version.net_version();
+        bits.write_bit(x.sleeping);
+        encode_vector(bits, &x.location, net_version)?;
+        if net_version >= 7 {
+            x.rotation.encode(bits);
+        } else {
+            x.rotation.encode_compressed(bits);
+        }
+
+        if !x.sleeping {
+            let zero = Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            encode_vector(bits, &x.linear_velocity.unwrap_or(zero), net_version)?;
+            encode_vector(bits, &x.angular_velocity.unwrap_or(zero), net_version)?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_reservation(
+        &self,
+        bits: &mut BitWriter,
+        x: &Reservation,
+    ) -> Result<(), AttributeError> {
+        encode_bits(bits, 3, u64::from(x.number), "Reservation")?;
+        encode_unique_id(bits, &x.unique_id, self.version.net_version())?;
+        if x.unique_id.system_id != 0 {
+            encode_text(bits, x.name.as_deref().unwrap_or(""))?;
+        }
+
+        bits.write_bit(x.unknown1);
+        bits.write_bit(x.unknown2);
+        if self.version >= VersionTriplet(868, 12, 0) {
+            let unknown3 = u64::from(x.unknown3.unwrap_or(0));
+            encode_bits(bits, 6, unknown3, "Reservation")?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_online_loadout(
+        &self,
+        bits: &mut BitWriter,
+        loadout: &[Vec<Product>],
+    ) -> Result<(), AttributeError> {
+        let size = u8::try_from(loadout.len())
+            .map_err(|_| AttributeError::ValueOutOfRange("Loadout Online"))?;
+        bits.write_u8(size);
+        for products in loadout {
+            let size = u8::try_from(products.len())
+                .map_err(|_| AttributeError::ValueOutOfRange("Loadout Online"))?;
+            bits.write_u8(size);
+            for product in products {
+                bits.write_bit(product.unknown);
+                bits.write_u32(product.object_ind);
+                self.product_decoder
+                    .encode(bits, product.object_ind, &product.value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a value in the given number of bits, rejecting values that don't fit
+fn encode_bits(
+    bits: &mut BitWriter,
+    width: u32,
+    val: u64,
+    component: &'static str,
+) -> Result<(), AttributeError> {
+    if val >= 1 << width {
+        return Err(AttributeError::ValueOutOfRange(component));
+    }
+    bits.write_bits(width, val);
+    Ok(())
+}
+
+fn encode_vector(
+    bits: &mut BitWriter,
+    vec: &Vector3f,
+    net_version: i32,
+) -> Result<(), AttributeError> {
+    vec.encode(bits, net_version)
+        .ok_or(AttributeError::ValueOutOfRange("Vector"))
+}
+
+fn encode_instigator(bits: &mut BitWriter, instigator: Option<ActorId>) {
+    bits.write_bit(instigator.is_some());
+    if let Some(actor) = instigator {
+        bits.write_i32(actor.0);
+    }
+}
+
+fn encode_explosion(
+    bits: &mut BitWriter,
+    explosion: &Explosion,
+    net_version: i32,
+) -> Result<(), AttributeError> {
+    bits.write_bit(explosion.flag);
+    bits.write_i32(explosion.actor.0);
+    encode_vector(bits, &explosion.location, net_version)
+}
+
+/// The inverse of `decode_text`. Text is written as windows-1252 when possible, else as UTF-16.
+/// Text that exceeds the size that the decoder accepts is rejected.
+fn encode_text(bits: &mut BitWriter, text: &str) -> Result<(), AttributeError> {
+    if text.is_empty() {
+        bits.write_i32(0);
+        return Ok(());
+    }
+
+    let (encoded, _, had_errors) = WINDOWS_1252.encode(text);
+    if !had_errors {
+        let size = encoded.len() + 1;
+        if size > 1024 {
+            return Err(AttributeError::TooBigString(size as i32));
+        }
+
+        bits.write_i32(size as i32);
+        bits.write_bytes(&encoded);
+        bits.write_u8(0);
+    } else {
+        let characters = text.encode_utf16().count() + 1;
+        if characters * 2 > 1024 {
+            return Err(AttributeError::TooBigString(-(characters as i32)));
+        }
+
+        bits.write_i32(-(characters as i32));
+        for c in text.encode_utf16().chain(std::iter::once(0)) {
+            bits.write_bytes(&c.to_le_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+fn encode_loadout(bits: &mut BitWriter, loadout: &Loadout) {
+    let version = loadout.version;
+    bits.write_u8(version);
+    bits.write_u32(loadout.body);
+    bits.write_u32(loadout.decal);
+    bits.write_u32(loadout.wheels);
+    bits.write_u32(loadout.rocket_trail);
+    bits.write_u32(loadout.antenna);
+    bits.write_u32(loadout.topper);
+    bits.write_u32(loadout.unknown1);
+    if version > 10 {
+        bits.write_u32(loadout.unknown2.unwrap_or(0));
+    }
+
+    if version >= 16 {
+        bits.write_u32(loadout.engine_audio.unwrap_or(0));
+        bits.write_u32(loadout.trail.unwrap_or(0));
+        bits.write_u32(loadout.goal_explosion.unwrap_or(0));
+    }
+
+    if version >= 17 {
+        bits.write_u32(loadout.banner.unwrap_or(0));
+    }
+
+    if version >= 19 {
+        bits.write_u32(loadout.product_id.unwrap_or(0));
+    }
+
+    // The decoder discards these fields
+    if version >= 22 {
+        bits.write_u32(0);
+        bits.write_u32(0);
+        bits.write_u32(0);
+    }
+}
+
+/// The system id that a remote id is decoded from
+fn remote_system_id(remote_id: &RemoteId) -> u8 {
+    match remote_id {
+        RemoteId::SplitScreen(_) => 0,
+        RemoteId::Steam(_) => 1,
+        RemoteId::PlayStation(_) => 2,
+        RemoteId::Xbox(_) => 4,
+        RemoteId::QQ(_) => 5,
+        RemoteId::Switch(_) => 6,
+        RemoteId::PsyNet(_) => 7,
+        RemoteId::Epic(_) => 11,
+    }
+}
+
+/// Writes a fixed number of bytes, padding with zeros or truncating as necessary
+fn encode_fixed_bytes(bits: &mut BitWriter, data: &[u8], size: usize) {
+    let len = data.len().min(size);
+    bits.write_bytes(&data[..len]);
+    for _ in len..size {
+        bits.write_u8(0);
+    }
+}
+
+/// The inverse of `decode_unique_id`. Fixed size fields of unknown purpose are padded with zeros
+/// or truncated to the size that the decoder expects.
+fn encode_unique_id(
+    bits: &mut BitWriter,
+    id: &UniqueId,
+    net_version: i32,
+) -> Result<(), AttributeError> {
+    if remote_system_id(&id.remote_id) != id.system_id {
+        return Err(AttributeError::ValueOutOfRange("System id"));
+    }
+
+    bits.write_u8(id.system_id);
+    match &id.remote_id {
+        RemoteId::SplitScreen(x) => encode_bits(bits, 24, u64::from(*x), "SplitScreen")?,
+        RemoteId::Steam(x) | RemoteId::Xbox(x) | RemoteId::QQ(x) | RemoteId::Epic(x) => {
+            bits.write_u64(*x)
+        }
+        RemoteId::PlayStation(x) => {
+            let (name, _, had_errors) = WINDOWS_1252.encode(&x.name);
+            if had_errors || name.len() > 16 || name.contains(&0) {
+                return Err(AttributeError::ValueOutOfRange("PS4 Name"));
+            }
+
+            encode_fixed_bytes(bits, &name, 16);
+            let to_write = if net_version >= 1 { 16 } else { 8 };
+            encode_fixed_bytes(bits, &x.unknown1, to_write);
+            bits.write_u64(x.online_id);
+        }
+        RemoteId::Switch(x) => {
+            bits.write_u64(x.online_id);
+            encode_fixed_bytes(bits, &x.unknown1, 24);
+        }
+        RemoteId::PsyNet(x) => {
+            bits.write_u64(x.online_id);
+            if net_version < 10 {
+                encode_fixed_bytes(bits, &x.unknown1, 24);
+            }
+        }
+    }
+
+    bits.write_u8(id.local_id);
+    Ok(())
+}
+
 fn decode_explosion(bits: &mut LittleEndianReader<'_>, net_version: i32) -> Option<Explosion> {
     let flag = bits.read_bit()?;
     let actor = bits.read_i32().map(ActorId)?;
@@ -1364,6 +2180,26 @@ fn decode_text(
     }
 }
 
+/// Reads past text like `decode_text` without decoding it
+fn skip_text(bits: &mut LittleEndianReader<'_>, buf: &mut [u8]) -> Result<(), AttributeError> {
+    let size = bits
+        .read_i32()
+        .ok_or(AttributeError::NotEnoughDataFor("text string"))?;
+
+    let bytes = if size < 0 {
+        size.checked_mul(-2)
+            .ok_or(AttributeError::TooBigString(size))? as usize
+    } else {
+        size as usize
+    };
+
+    if bytes > buf.len() || !bits.read_bytes(&mut buf[..bytes]) {
+        Err(AttributeError::TooBigString(size))
+    } else {
+        Ok(())
+    }
+}
+
 fn decode_loadout_specials(
     bits: &mut LittleEndianReader<'_>,
 ) -> Option<(Option<u32>, Option<u32>, Option<u32>)> {
@@ -1441,6 +2277,43 @@ fn decode_unique_id(
     decode_unique_id_with_system_id(bits, net_version, system_id, buf)
 }
 
+/// Reads past a unique id like `decode_unique_id` without allocating. Returns the system id.
+fn skip_unique_id(
+    bits: &mut LittleEndianReader<'_>,
+    net_version: i32,
+    buf: &mut [u8],
+) -> Result<u8, AttributeError> {
+    let system_id = bits
+        .read_u8()
+        .ok_or(AttributeError::NotEnoughDataFor("System id"))?;
+    skip_unique_id_with_system_id(bits, net_version, system_id, buf)?;
+    Ok(system_id)
+}
+
+fn skip_unique_id_with_system_id(
+    bits: &mut LittleEndianReader<'_>,
+    net_version: i32,
+    system_id: u8,
+    buf: &mut [u8],
+) -> Result<(), AttributeError> {
+    // Only the remote ids with a name or unknown bytes allocate when decoded
+    let (bytes, component) = match system_id {
+        2 if net_version >= 1 => (16 + 16 + 8, "PS4 Name"),
+        2 => (16 + 8 + 8, "PS4 Name"),
+        6 => (8 + 24, "Switch ID"),
+        7 if net_version < 10 => (8 + 24, "PsyNet ID"),
+        _ => return decode_unique_id_with_system_id(bits, net_version, system_id, buf).map(drop),
+    };
+
+    if !bits.read_bytes(&mut buf[..bytes]) {
+        return Err(AttributeError::NotEnoughDataFor(component));
+    }
+
+    bits.read_u8()
+        .map(drop)
+        .ok_or(AttributeError::NotEnoughDataFor("UniqueId local_id"))
+}
+
 fn decode_unique_id_with_system_id(
     bits: &mut LittleEndianReader<'_>,
     net_version: i32,
@@ -1534,9 +2407,9 @@ fn decode_unique_id_with_system_id(
             }
         }
         11 => bits
-             .read_u64()
-             .ok_or(AttributeError::NotEnoughDataFor("Epic ID"))
-             .map(RemoteId::Epic),
+            .read_u64()
+            .ok_or(AttributeError::NotEnoughDataFor("Epic ID"))
+            .map(RemoteId::Epic),
         x => Err(AttributeError::UnrecognizedRemoteId(x)),
     }?;
 
@@ -1554,6 +2427,9 @@ fn decode_unique_id_with_system_id(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::strategy::{BoxedStrategy, Union};
 
     #[test]
     fn test_size_of_rigid_body() {
@@ -1567,4 +2443,843 @@ mod tests {
                 <= ::std::mem::size_of::<RigidBody>() + ::std::mem::size_of::<usize>()
         );
     }
+
+    /// Object indices of the product attributes that the encoder and decoder under test are
+    /// created with
+    const COLOR_IND: u32 = 1;
+    const PAINTED_IND: u32 = 2;
+    const TITLE_IND: u32 = 3;
+    const SPECIAL_EDITION_IND: u32 = 4;
+    const TEAM_EDITION_IND: u32 = 5;
+
+    /// Versions that toggle each of the version dependent encodings
+    const VERSIONS: [(VersionTriplet, bool); 4] = [
+        (VersionTriplet(868, 1, 0), false),
+        (VersionTriplet(868, 10, 5), false),
+        (VersionTriplet(868, 20, 9), false),
+        (VersionTriplet(868, 32, 10), true),
+    ];
+
+    fn product_decoder(version: VersionTriplet) -> ProductValueDecoder {
+        let names = [
+            "TAGame.ProductAttribute_UserColor_TA",
+            "TAGame.ProductAttribute_Painted_TA",
+            "TAGame.ProductAttribute_TitleID_TA",
+            "TAGame.ProductAttribute_SpecialEdition_TA",
+            "TAGame.ProductAttribute_TeamEdition_TA",
+        ];
+
+        let name_obj_ind = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (*name, vec![ObjectId(i as i32 + 1)]))
+            .collect();
+        ProductValueDecoder::create(version, &name_obj_ind)
+    }
+
+    fn round_trip(
+        version: VersionTriplet,
+        is_rl_223: bool,
+        tag: AttributeTag,
+        attr: &Attribute,
+    ) -> Result<Attribute, AttributeError> {
+        let product_decoder = product_decoder(version);
+        let encoder = AttributeEncoder {
+            version,
+            product_decoder,
+            is_rl_223,
+        };
+        let decoder = AttributeDecoder {
+            version,
+            product_decoder,
+            is_rl_223,
+        };
+
+        let mut writer = BitWriter::new();
+        encoder.encode(tag, attr, &mut writer)?;
+        let bits_written = writer.bits_written();
+        let data = writer.into_inner();
+        let mut bits = LittleEndianReader::new(&data);
+        let mut buf = [0u8; 1024];
+        let decoded = decoder.decode(tag, &mut bits, &mut buf)?;
+        assert_eq!(
+            data.len() * 8 - bits.bits_remaining().unwrap(),
+            bits_written
+        );
+        Ok(decoded)
+    }
+
+    fn float() -> impl Strategy<Value = f32> {
+        use proptest::num::f32::*;
+        NORMAL | SUBNORMAL | ZERO | INFINITE | POSITIVE | NEGATIVE
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        prop_oneof!["[a-zA-Z0-9 \u{b3}\u{20ac}]{0,40}", "\\PC{0,40}"]
+    }
+
+    fn actor() -> impl Strategy<Value = ActorId> {
+        any::<i32>().prop_map(ActorId)
+    }
+
+    fn vector(version: VersionTriplet) -> BoxedStrategy<Vector3f> {
+        let max = if version.net_version() >= 7 {
+            1 << 22
+        } else {
+            1 << 20
+        };
+        (-max..max, -max..max, -max..max)
+            .prop_map(|(x, y, z)| Vector3f {
+                x: (x as f32) / 100.0,
+                y: (y as f32) / 100.0,
+                z: (z as f32) / 100.0,
+            })
+            .boxed()
+    }
+
+    fn rotation() -> impl Strategy<Value = Rotation> {
+        (
+            any::<Option<i8>>(),
+            any::<Option<i8>>(),
+            any::<Option<i8>>(),
+        )
+            .prop_map(|(yaw, pitch, roll)| Rotation { yaw, pitch, roll })
+    }
+
+    /// Quaternions are generated from their encoded form, as only a decoded quaternion can
+    /// survive being encoded
+    fn quaternion(version: VersionTriplet) -> BoxedStrategy<Quaternion> {
+        if version.net_version() >= 7 {
+            (0..4u64, vec(0..(1u64 << 18), 3))
+                .prop_map(|(largest, comps)| {
+                    let mut writer = BitWriter::new();
+                    writer.write_bits(2, largest);
+                    for x in comps {
+                        writer.write_bits(18, x);
+                    }
+                    let data = writer.into_inner();
+                    Quaternion::decode(&mut LittleEndianReader::new(&data)).unwrap()
+                })
+                .boxed()
+        } else {
+            vec(any::<u16>(), 3)
+                .prop_map(|comps| {
+                    let mut writer = BitWriter::new();
+                    for x in comps {
+                        writer.write_bits(16, u64::from(x));
+                    }
+                    let data = writer.into_inner();
+                    Quaternion::decode_compressed(&mut LittleEndianReader::new(&data)).unwrap()
+                })
+                .boxed()
+        }
+    }
+
+    fn unique_id(version: VersionTriplet) -> BoxedStrategy<UniqueId> {
+        let net_version = version.net_version();
+        let ps4_unknown = if net_version >= 1 { 16 } else { 8 };
+        let psynet_unknown = if net_version < 10 { 24 } else { 0 };
+        let remote_id = prop_oneof![
+            (0..(1u32 << 24)).prop_map(RemoteId::SplitScreen),
+            any::<u64>().prop_map(RemoteId::Steam),
+            (
+                any::<u64>(),
+                "[a-zA-Z0-9 ]{0,16}",
+                vec(any::<u8>(), ps4_unknown)
+            )
+                .prop_map(|(online_id, name, unknown1)| RemoteId::PlayStation(Ps4Id {
+                    online_id,
+                    name,
+                    unknown1,
+                })),
+            any::<u64>().prop_map(RemoteId::Xbox),
+            any::<u64>().prop_map(RemoteId::QQ),
+            (any::<u64>(), vec(any::<u8>(), 24)).prop_map(
+                |(online_id, unknown1)| RemoteId::Switch(SwitchId {
+                    online_id,
+                    unknown1
+                })
+            ),
+            (any::<u64>(), vec(any::<u8>(), psynet_unknown)).prop_map(|(online_id, unknown1)| {
+                RemoteId::PsyNet(PsyNetId {
+                    online_id,
+                    unknown1,
+                })
+            }),
+            any::<u64>().prop_map(RemoteId::Epic),
+        ];
+
+        (remote_id, any::<u8>())
+            .prop_map(|(remote_id, local_id)| UniqueId {
+                system_id: remote_system_id(&remote_id),
+                remote_id,
+                local_id,
+            })
+            .boxed()
+    }
+
+    fn loadout() -> impl Strategy<Value = Loadout> {
+        (any::<u8>(), vec(any::<u32>(), 13)).prop_map(|(version, x)| {
+            let opt = |cond: bool, val: u32| if cond { Some(val) } else { None };
+            Loadout {
+                version,
+                body: x[0],
+                decal: x[1],
+                wheels: x[2],
+                rocket_trail: x[3],
+                antenna: x[4],
+                topper: x[5],
+                unknown1: x[6],
+                unknown2: opt(version > 10, x[7]),
+                engine_audio: opt(version >= 16, x[8]),
+                trail: opt(version >= 16, x[9]),
+                goal_explosion: opt(version >= 16, x[10]),
+                banner: opt(version >= 17, x[11]),
+                product_id: opt(version >= 19, x[12]),
+            }
+        })
+    }
+
+    fn product(version: VersionTriplet) -> BoxedStrategy<Product> {
+        let value = if version >= VersionTriplet(868, 23, 8) {
+            any::<i32>().prop_map(ProductValue::NewColor).boxed()
+        } else {
+            prop_oneof![
+                Just(ProductValue::NoColor),
+                (0..(1u32 << 31)).prop_map(ProductValue::OldColor)
+            ]
+            .boxed()
+        };
+        let color = value.prop_map(|x| (COLOR_IND, x));
+
+        let new_paint = version >= VersionTriplet(868, 18, 0);
+        let (paint, team_edition) = if new_paint {
+            (
+                (0..(1u32 << 31)).prop_map(ProductValue::NewPaint).boxed(),
+                (0..(1u32 << 31))
+                    .prop_map(ProductValue::NewTeamEdition)
+                    .boxed(),
+            )
+        } else {
+            (
+                (0..14u32).prop_map(ProductValue::OldPaint).boxed(),
+                (0..14u32).prop_map(ProductValue::OldTeamEdition).boxed(),
+            )
+        };
+
+        let value = prop_oneof![
+            color,
+            paint.prop_map(|x| (PAINTED_IND, x)),
+            text().prop_map(|x| (TITLE_IND, ProductValue::Title(x))),
+            (0..(1u32 << 31)).prop_map(|x| (SPECIAL_EDITION_IND, ProductValue::SpecialEdition(x))),
+            team_edition.prop_map(|x| (TEAM_EDITION_IND, x)),
+            (6..u32::MAX).prop_map(|x| (x, ProductValue::Absent)),
+        ];
+
+        (any::<bool>(), value)
+            .prop_map(|(unknown, (object_ind, value))| Product {
+                unknown,
+                object_ind,
+                value,
+            })
+            .boxed()
+    }
+
+    fn online_loadout(version: VersionTriplet) -> impl Strategy<Value = Vec<Vec<Product>>> {
+        vec(vec(product(version), 0..4), 0..4)
+    }
+
+    /// An attribute for each tag that the encoder is expected to round trip
+    fn attribute(
+        version: VersionTriplet,
+        is_rl_223: bool,
+    ) -> Union<BoxedStrategy<(AttributeTag, Attribute)>> {
+        use AttributeTag as Tag;
+        let vec3 = vector(version);
+        let game_mode_bits = if version < VersionTriplet(868, 12, 0) {
+            2
+        } else {
+            8
+        };
+        let qword_string = if is_rl_223 {
+            text().prop_map(Attribute::String).boxed()
+        } else {
+            any::<u64>().prop_map(Attribute::QWord).boxed()
+        };
+
+        let strategies: Vec<(Tag, BoxedStrategy<Attribute>)> = vec![
+            (
+                Tag::Boolean,
+                any::<bool>().prop_map(Attribute::Boolean).boxed(),
+            ),
+            (Tag::Byte, any::<u8>().prop_map(Attribute::Byte).boxed()),
+            (
+                Tag::AppliedDamage,
+                (any::<u8>(), vec3.clone(), any::<i32>(), any::<i32>())
+                    .prop_map(|(id, position, damage_index, total_damage)| {
+                        Attribute::AppliedDamage(AppliedDamage {
+                            id,
+                            position,
+                            damage_index,
+                            total_damage,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::DamageState,
+                (
+                    any::<u8>(),
+                    any::<bool>(),
+                    actor(),
+                    vec3.clone(),
+                    any::<(bool, bool)>(),
+                )
+                    .prop_map(
+                        |(tile_state, damaged, offender, ball_position, (direct_hit, unknown1))| {
+                            Attribute::DamageState(DamageState {
+                                tile_state,
+                                damaged,
+                                offender,
+                                ball_position,
+                                direct_hit,
+                                unknown1,
+                            })
+                        },
+                    )
+                    .boxed(),
+            ),
+            (
+                Tag::CamSettings,
+                (vec(float(), 7))
+                    .prop_map(move |x| {
+                        Attribute::CamSettings(Box::new(CamSettings {
+                            fov: x[0],
+                            height: x[1],
+                            angle: x[2],
+                            distance: x[3],
+                            stiffness: x[4],
+                            swivel: x[5],
+                            transition: Some(x[6])
+                                .filter(|_| version >= VersionTriplet(868, 20, 0)),
+                        }))
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::ClubColors,
+                any::<(bool, u8, bool, u8)>()
+                    .prop_map(|(blue_flag, blue_color, orange_flag, orange_color)| {
+                        Attribute::ClubColors(ClubColors {
+                            blue_flag,
+                            blue_color,
+                            orange_flag,
+                            orange_color,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::Demolish,
+                (
+                    any::<(bool, bool)>(),
+                    actor(),
+                    actor(),
+                    vec3.clone(),
+                    vec3.clone(),
+                )
+                    .prop_map(
+                        |(
+                            (attacker_flag, victim_flag),
+                            attacker,
+                            victim,
+                            attack_velocity,
+                            victim_velocity,
+                        )| {
+                            Attribute::Demolish(Box::new(Demolish {
+                                attacker_flag,
+                                attacker,
+                                victim_flag,
+                                victim,
+                                attack_velocity,
+                                victim_velocity,
+                            }))
+                        },
+                    )
+                    .boxed(),
+            ),
+            (
+                Tag::DemolishFx,
+                (
+                    any::<(bool, i32, bool, bool)>(),
+                    actor(),
+                    actor(),
+                    vec3.clone(),
+                    vec3.clone(),
+                )
+                    .prop_map(
+                        |(
+                            (custom_demo_flag, custom_demo_id, attacker_flag, victim_flag),
+                            attacker,
+                            victim,
+                            attack_velocity,
+                            victim_velocity,
+                        )| {
+                            Attribute::DemolishFx(Box::new(DemolishFx {
+                                custom_demo_flag,
+                                custom_demo_id,
+                                attacker_flag,
+                                attacker,
+                                victim_flag,
+                                victim,
+                                attack_velocity,
+                                victim_velocity,
+                            }))
+                        },
+                    )
+                    .boxed(),
+            ),
+            (
+                Tag::Enum,
+                (0..(1u16 << 11)).prop_map(Attribute::Enum).boxed(),
+            ),
+            (
+                Tag::Explosion,
+                (any::<bool>(), actor(), vec3.clone())
+                    .prop_map(|(flag, actor, location)| {
+                        Attribute::Explosion(Explosion {
+                            flag,
+                            actor,
+                            location,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::ExtendedExplosion,
+                (any::<(bool, bool)>(), actor(), vec3.clone(), actor())
+                    .prop_map(|((flag, unknown1), actor, location, secondary_actor)| {
+                        Attribute::ExtendedExplosion(ExtendedExplosion {
+                            explosion: Explosion {
+                                flag,
+                                actor,
+                                location,
+                            },
+                            unknown1,
+                            secondary_actor,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::FlaggedByte,
+                any::<(bool, u8)>()
+                    .prop_map(|(flag, x)| Attribute::FlaggedByte(flag, x))
+                    .boxed(),
+            ),
+            (
+                Tag::ActiveActor,
+                (any::<bool>(), actor())
+                    .prop_map(|(active, actor)| {
+                        Attribute::ActiveActor(ActiveActor { active, actor })
+                    })
+                    .boxed(),
+            ),
+            (Tag::Float, float().prop_map(Attribute::Float).boxed()),
+            (
+                Tag::GameMode,
+                (0..(1u16 << game_mode_bits))
+                    .prop_map(move |x| Attribute::GameMode(game_mode_bits, x as u8))
+                    .boxed(),
+            ),
+            (Tag::Int, any::<i32>().prop_map(Attribute::Int).boxed()),
+            (Tag::Int64, any::<i64>().prop_map(Attribute::Int64).boxed()),
+            (
+                Tag::Loadout,
+                loadout()
+                    .prop_map(|x| Attribute::Loadout(Box::new(x)))
+                    .boxed(),
+            ),
+            (
+                Tag::TeamLoadout,
+                (loadout(), loadout())
+                    .prop_map(|(blue, orange)| {
+                        Attribute::TeamLoadout(Box::new(TeamLoadout { blue, orange }))
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::Location,
+                vec3.clone().prop_map(Attribute::Location).boxed(),
+            ),
+            (
+                Tag::MusicStinger,
+                any::<(bool, u32, u8)>()
+                    .prop_map(|(flag, cue, trigger)| {
+                        Attribute::MusicStinger(MusicStinger { flag, cue, trigger })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::Pickup,
+                (proptest::option::of(actor()), any::<bool>())
+                    .prop_map(|(instigator, picked_up)| {
+                        Attribute::Pickup(Pickup {
+                            instigator,
+                            picked_up,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::PickupNew,
+                (proptest::option::of(actor()), any::<u8>())
+                    .prop_map(|(instigator, picked_up)| {
+                        Attribute::PickupNew(PickupNew {
+                            instigator,
+                            picked_up,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::PlayerHistoryKey,
+                (0..(1u16 << 14))
+                    .prop_map(Attribute::PlayerHistoryKey)
+                    .boxed(),
+            ),
+            (Tag::QWordString, qword_string),
+            (
+                Tag::Welded,
+                (any::<bool>(), actor(), vec3.clone(), float(), rotation())
+                    .prop_map(|(active, actor, offset, mass, rotation)| {
+                        Attribute::Welded(Welded {
+                            active,
+                            actor,
+                            offset,
+                            mass,
+                            rotation,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::RigidBody,
+                (
+                    any::<bool>(),
+                    vec3.clone(),
+                    quaternion(version),
+                    vec3.clone(),
+                    vec3.clone(),
+                )
+                    .prop_map(|(sleeping, location, rotation, linear, angular)| {
+                        Attribute::RigidBody(RigidBody {
+                            sleeping,
+                            location,
+                            rotation,
+                            linear_velocity: Some(linear).filter(|_| !sleeping),
+                            angular_velocity: Some(angular).filter(|_| !sleeping),
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::Title,
+                (any::<(bool, bool, bool)>(), vec(any::<u32>(), 5))
+                    .prop_map(|((a, b, c), x)| {
+                        Attribute::Title(a, b, x[0], x[1], x[2], x[3], x[4], c)
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::TeamPaint,
+                any::<(u8, u8, u8, u32, u32)>()
+                    .prop_map(
+                        |(team, primary_color, accent_color, primary_finish, accent_finish)| {
+                            Attribute::TeamPaint(TeamPaint {
+                                team,
+                                primary_color,
+                                accent_color,
+                                primary_finish,
+                                accent_finish,
+                            })
+                        },
+                    )
+                    .boxed(),
+            ),
+            (Tag::String, text().prop_map(Attribute::String).boxed()),
+            (
+                Tag::UniqueId,
+                unique_id(version)
+                    .prop_map(|x| Attribute::UniqueId(Box::new(x)))
+                    .boxed(),
+            ),
+            (
+                Tag::Reservation,
+                (
+                    0..8u32,
+                    unique_id(version),
+                    text(),
+                    any::<(bool, bool)>(),
+                    0..64u8,
+                )
+                    .prop_map(
+                        move |(number, unique_id, name, (unknown1, unknown2), unknown3)| {
+                            Attribute::Reservation(Box::new(Reservation {
+                                number,
+                                name: Some(name).filter(|_| unique_id.system_id != 0),
+                                unique_id,
+                                unknown1,
+                                unknown2,
+                                unknown3: Some(unknown3)
+                                    .filter(|_| version >= VersionTriplet(868, 12, 0)),
+                            }))
+                        },
+                    )
+                    .boxed(),
+            ),
+            (
+                Tag::PartyLeader,
+                proptest::option::of(
+                    unique_id(version).prop_filter("split screen", |x| x.system_id != 0),
+                )
+                .prop_map(|x| Attribute::PartyLeader(x.map(Box::new)))
+                .boxed(),
+            ),
+            (
+                Tag::PrivateMatchSettings,
+                (text(), any::<(u32, u32)>(), text(), text(), any::<bool>())
+                    .prop_map(
+                        |(mutators, (joinable_by, max_players), game_name, password, flag)| {
+                            Attribute::PrivateMatch(Box::new(PrivateMatchSettings {
+                                mutators,
+                                joinable_by,
+                                max_players,
+                                game_name,
+                                password,
+                                flag,
+                            }))
+                        },
+                    )
+                    .boxed(),
+            ),
+            (
+                Tag::LoadoutOnline,
+                online_loadout(version)
+                    .prop_map(Attribute::LoadoutOnline)
+                    .boxed(),
+            ),
+            (
+                Tag::LoadoutsOnline,
+                (
+                    online_loadout(version),
+                    online_loadout(version),
+                    any::<(bool, bool)>(),
+                )
+                    .prop_map(|(blue, orange, (unknown1, unknown2))| {
+                        Attribute::LoadoutsOnline(LoadoutsOnline {
+                            blue,
+                            orange,
+                            unknown1,
+                            unknown2,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::StatEvent,
+                any::<(bool, i32)>()
+                    .prop_map(|(unknown1, object_id)| {
+                        Attribute::StatEvent(StatEvent {
+                            unknown1,
+                            object_id,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::RotationTag,
+                rotation().prop_map(Attribute::Rotation).boxed(),
+            ),
+            (
+                Tag::RepStatTitle,
+                (any::<(bool, bool, u32, u32)>(), text())
+                    .prop_map(|((unknown, unknown2, index, value), name)| {
+                        Attribute::RepStatTitle(RepStatTitle {
+                            unknown,
+                            name,
+                            unknown2,
+                            index,
+                            value,
+                        })
+                    })
+                    .boxed(),
+            ),
+            (
+                Tag::PickupInfo,
+                (any::<(bool, bool, bool, bool)>(), actor())
+                    .prop_map(|((active, items_are_preview, unknown, unknown2), actor)| {
+                        Attribute::PickupInfo(PickupInfo {
+                            active,
+                            actor,
+                            items_are_preview,
+                            unknown,
+                            unknown2,
+                        })
+                    })
+                    .boxed(),
+            ),
+        ];
+
+        Union::new(
+            strategies
+                .into_iter()
+                .map(|(tag, strategy)| strategy.prop_map(move |x| (tag, x)).boxed()),
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(4096))]
+
+        #[test]
+        fn test_attribute_round_trip(
+            ((version, is_rl_223), (tag, attr)) in (0..VERSIONS.len()).prop_flat_map(|i| {
+                let (version, is_rl_223) = VERSIONS[i];
+                (Just((version, is_rl_223)), attribute(version, is_rl_223))
+            })
+        ) {
+            // A recomputed quaternion component can be NaN, which never compares equal
+            let decoded = round_trip(version, is_rl_223, tag, &attr).unwrap();
+            prop_assert_eq!(format!("{:?}", decoded), format!("{:?}", attr));
+        }
+    }
+
+    #[test]
+    fn test_encode_mismatched_attribute() {
+        let (version, is_rl_223) = VERSIONS[3];
+        let err = round_trip(version, is_rl_223, AttributeTag::Int, &Attribute::Byte(1));
+        assert_eq!(err, Err(AttributeError::UnexpectedAttribute));
+
+        let err = round_trip(
+            version,
+            is_rl_223,
+            AttributeTag::QWordString,
+            &Attribute::QWord(1),
+        );
+        assert_eq!(err, Err(AttributeError::UnexpectedAttribute));
+
+        let attr = Attribute::Int(1);
+        let err = round_trip(version, is_rl_223, AttributeTag::NotImplemented, &attr);
+        assert_eq!(err, Err(AttributeError::Unimplemented));
+    }
+
+    #[test]
+    fn test_encode_out_of_range() {
+        let (version, is_rl_223) = VERSIONS[3];
+        let err = round_trip(
+            version,
+            is_rl_223,
+            AttributeTag::Enum,
+            &Attribute::Enum(2048),
+        );
+        assert_eq!(err, Err(AttributeError::ValueOutOfRange("Enum")));
+
+        let location = Attribute::Location(Vector3f {
+            x: 50000.0,
+            y: 0.0,
+            z: 0.0,
+        });
+        let err = round_trip(version, is_rl_223, AttributeTag::Location, &location);
+        assert_eq!(err, Err(AttributeError::ValueOutOfRange("Vector")));
+
+        let text = Attribute::String("a".repeat(1024));
+        let err = round_trip(version, is_rl_223, AttributeTag::String, &text);
+        assert_eq!(err, Err(AttributeError::TooBigString(1025)));
+
+        let id = Attribute::UniqueId(Box::new(UniqueId {
+            system_id: 2,
+            remote_id: RemoteId::Steam(1),
+            local_id: 0,
+        }));
+        let err = round_trip(version, is_rl_223, AttributeTag::UniqueId, &id);
+        assert_eq!(err, Err(AttributeError::ValueOutOfRange("System id")));
+
+        let product = Attribute::LoadoutOnline(vec![vec![Product {
+            unknown: false,
+            object_ind: COLOR_IND,
+            value: ProductValue::Absent,
+        }]]);
+        let err = round_trip(version, is_rl_223, AttributeTag::LoadoutOnline, &product);
+        assert_eq!(err, Err(AttributeError::UnexpectedAttribute));
+    }
+
+    #[test]
+    fn test_encode_loadout_discarded_fields() {
+        let (version, is_rl_223) = VERSIONS[3];
+        let loadout = Loadout {
+            version: 22,
+            body: 1,
+            decal: 2,
+            wheels: 3,
+            rocket_trail: 4,
+            antenna: 5,
+            topper: 6,
+            unknown1: 7,
+            unknown2: None,
+            engine_audio: Some(8),
+            trail: None,
+            goal_explosion: Some(9),
+            banner: Some(10),
+            product_id: Some(11),
+        };
+
+        // Fields absent from the loadout's version are written as zero
+        let attr = Attribute::Loadout(Box::new(loadout));
+        let decoded = round_trip(version, is_rl_223, AttributeTag::Loadout, &attr).unwrap();
+        let expected = Loadout {
+            unknown2: Some(0),
+            trail: Some(0),
+            ..loadout
+        };
+        assert_eq!(decoded, Attribute::Loadout(Box::new(expected)));
+    }
+
+    #[test]
+    fn test_raw_attribute_round_trip() {
+        let raw = RawAttribute {
+            bit_len: 13,
+            data: vec![0xab, 0x1c],
+        };
+
+        let (version, is_rl_223) = VERSIONS[3];
+        let encoder = AttributeEncoder {
+            version,
+            product_decoder: product_decoder(version),
+            is_rl_223,
+        };
+        let mut writer = BitWriter::new();
+        encoder
+            .encode(
+                AttributeTag::NotImplemented,
+                &Attribute::Raw(raw.clone()),
+                &mut writer,
+            )
+            .unwrap();
+        assert_eq!(writer.bits_written(), 13);
+
+        let data = writer.into_inner();
+        let mut bits = LittleEndianReader::new(&data);
+        assert_eq!(RawAttribute::read(&mut bits, 13), Some(raw));
+        assert_eq!(RawAttribute::read(&mut bits, 13), None);
+
+        let mismatched = Attribute::Raw(RawAttribute {
+            bit_len: 17,
+            data: vec![0xab, 0x1c],
+        });
+        let err = encoder.encode(AttributeTag::Int, &mismatched, &mut BitWriter::new());
+        assert_eq!(
+            err,
+            Err(AttributeError::ValueOutOfRange("Raw attribute length"))
+        );
+    }
 }
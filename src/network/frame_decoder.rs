@@ -1,35 +1,221 @@
 use bitter::{BitReader, LittleEndianReader};
 use fnv::FnvHashMap;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::iter::FusedIterator;
+use std::sync::Arc;
 
 use crate::bits::RlBits;
 use crate::errors::{AttributeError, FrameContext, FrameError, NetworkError};
-use crate::network::attributes::{AttributeDecoder, ProductValueDecoder};
+use crate::models::{KeyFrame, NetworkFailure};
+use crate::network::attributes::{
+    AttributeContext, AttributeDecode, AttributeDecoder, ProductValueDecoder,
+};
+use crate::network::guess::{AttributeGuess, AttributeLayout, GuessSink, LayoutDecoder};
 use crate::network::models::{
     ActorId, Frame, NewActor, ObjectId, SpawnTrajectory, StreamId, Trajectory, UpdatedAttribute,
 };
+use crate::network::trace::{NetworkTrace, TraceEntry};
+use crate::network::visitor::{FrameSink, NetworkVisitor, VisitorSink};
 use crate::network::{CacheInfo, VersionTriplet};
-use crate::parser::ReplayBody;
 
-pub(crate) struct FrameDecoder<'a, 'b: 'a> {
+#[derive(Clone)]
+pub(crate) struct FrameDecoder<'a> {
     pub frames_len: usize,
     pub product_decoder: ProductValueDecoder,
     pub max_channels: u32,
     pub channel_bits: u32,
-    pub body: &'a ReplayBody<'b>,
-    pub spawns: &'a Vec<SpawnTrajectory>,
-    pub object_ind_attributes: FnvHashMap<ObjectId, CacheInfo<'a>>,
+    pub objects: Vec<Cow<'a, str>>,
+    pub spawns: Vec<SpawnTrajectory>,
+    pub object_ind_attributes: FnvHashMap<ObjectId, CacheInfo>,
+    pub custom_decoders: FnvHashMap<ObjectId, Arc<dyn AttributeDecode>>,
+
+    /// Layouts being guessed for unknown stream ids, keyed by the object of the actor and the
+    /// stream id
+    pub layouts: FnvHashMap<(ObjectId, StreamId), LayoutDecoder>,
+
+    /// Indexed by object id, whether actors of the object are kept. All are kept when absent.
+    pub actor_filter: Option<Vec<bool>>,
+
+    /// Indexed by object id, whether updates to the attribute are kept. All are kept when absent.
+    pub attribute_filter: Option<Vec<bool>>,
     pub version: VersionTriplet,
     pub is_lan: bool,
     pub is_rl_223: bool,
+
+    /// Whether the decoded elements are traced for the context of errors
+    pub trace: bool,
+}
+
+/// Where an attribute failed to decode
+#[derive(Debug, Clone, Copy)]
+struct AttributeFailure {
+    bit_offset: usize,
+    time: f32,
+    actor: ActorId,
+    actor_object: ObjectId,
+    stream: StreamId,
+}
+
+impl AttributeFailure {
+    fn error(&self, e: AttributeError) -> FrameError {
+        match e {
+            AttributeError::Unimplemented => FrameError::MissingAttribute {
+                actor: self.actor,
+                actor_object: self.actor_object,
+                attribute_stream: self.stream,
+            },
+            e => FrameError::AttributeError {
+                actor: self.actor,
+                actor_object: self.actor_object,
+                attribute_stream: self.stream,
+                error: e,
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
 enum DecodedFrame {
     EndFrame,
-    Frame(Frame),
+    Frame,
+}
+
+/// Collects the decoded data of a frame, keeping the capacity of its buffers between frames
+#[derive(Debug, Default)]
+struct FrameBuffers {
+    time: f32,
+    delta: f32,
+    new_actors: Vec<NewActor>,
+    deleted_actors: Vec<ActorId>,
+    updated_actors: Vec<UpdatedAttribute>,
+}
+
+impl FrameBuffers {
+    fn clear(&mut self) {
+        self.new_actors.clear();
+        self.deleted_actors.clear();
+        self.updated_actors.clear();
+    }
+
+    /// Move the decoded data out of our buffers while keeping their capacity for the next frame
+    fn take_frame(&mut self) -> Frame {
+        let mut new_actors = Vec::with_capacity(self.new_actors.len());
+        new_actors.append(&mut self.new_actors);
+        let mut deleted_actors = Vec::with_capacity(self.deleted_actors.len());
+        deleted_actors.append(&mut self.deleted_actors);
+        let mut updated_actors = Vec::with_capacity(self.updated_actors.len());
+        updated_actors.append(&mut self.updated_actors);
+
+        Frame {
+            time: self.time,
+            delta: self.delta,
+            new_actors,
+            deleted_actors,
+            updated_actors,
+        }
+    }
+}
+
+impl FrameSink for FrameBuffers {
+    fn frame_start(&mut self, time: f32, delta: f32) {
+        self.time = time;
+        self.delta = delta;
+    }
+
+    fn new_actor(&mut self, actor: NewActor) {
+        self.new_actors.push(actor)
+    }
+
+    fn actor_deleted(&mut self, actor: ActorId) {
+        self.deleted_actors.push(actor)
+    }
+
+    fn attribute(&mut self, attribute: UpdatedAttribute) {
+        self.updated_actors.push(attribute)
+    }
+
+    fn frame_end(&mut self) {}
 }
 
-impl<'a, 'b> FrameDecoder<'a, 'b> {
+/// Records the elements decoded in the current and prior frame when tracing is enabled
+#[derive(Debug)]
+struct Tracer {
+    enabled: bool,
+    data_bits: usize,
+    frame: usize,
+    entries: Vec<TraceEntry>,
+
+    /// Index of the first entry of the current frame
+    frame_start: usize,
+}
+
+impl Tracer {
+    fn new(enabled: bool, data: &[u8]) -> Self {
+        Tracer {
+            enabled,
+            data_bits: data.len() * 8,
+            frame: 0,
+            entries: Vec::new(),
+            frame_start: 0,
+        }
+    }
+
+    /// Bit offset into the network data where the reader is at
+    fn offset(&self, bits: &LittleEndianReader<'_>) -> usize {
+        self.data_bits - bits.bits_remaining().unwrap_or(0)
+    }
+
+    /// Starts a frame, discarding the entries of all but the prior frame
+    fn start_frame(&mut self, frame: usize) {
+        self.entries.drain(..self.frame_start);
+        self.frame_start = self.entries.len();
+        self.frame = frame;
+    }
+
+    /// Records the element that was read from the start offset up to the reader. The value is
+    /// only formatted when tracing.
+    fn record<F: FnOnce() -> String>(
+        &mut self,
+        start: usize,
+        bits: &LittleEndianReader<'_>,
+        component: &str,
+        value: F,
+    ) {
+        if self.enabled {
+            let end = self.offset(bits);
+            self.entries.push(TraceEntry {
+                frame: self.frame,
+                bit_offset: start,
+                bit_len: end - start,
+                component: String::from(component),
+                value: value(),
+            });
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.frame_start = 0;
+    }
+
+    fn trace(&self, data: &[u8], bit_offset: usize) -> Option<NetworkTrace> {
+        if !self.enabled {
+            return None;
+        }
+
+        let first = self.entries.first().map_or(bit_offset, |x| x.bit_offset);
+        let start = (first / 8).min(data.len());
+        let end = ((bit_offset + NetworkTrace::TRAILING_BITS) / 8 + 1).min(data.len());
+        Some(NetworkTrace {
+            entries: self.entries.clone(),
+            data_offset: start * 8,
+            data: data[start..end.max(start)].to_vec(),
+        })
+    }
+}
+
+impl<'a> FrameDecoder<'a> {
     fn parse_new_actor(
         &self,
         bits: &mut LittleEndianReader<'_>,
@@ -68,27 +254,581 @@ impl<'a, 'b> FrameDecoder<'a, 'b> {
         })
     }
 
-    fn decode_frame(
+    fn wants_actor(&self, object_id: ObjectId) -> bool {
+        match &self.actor_filter {
+            Some(wanted) => wanted.get(usize::from(object_id)) == Some(&true),
+            None => true,
+        }
+    }
+
+    fn wants_attribute(&self, object_id: ObjectId) -> bool {
+        match &self.attribute_filter {
+            Some(wanted) => wanted.get(usize::from(object_id)) == Some(&true),
+            None => true,
+        }
+    }
+
+    fn object_name(&self, object_id: ObjectId) -> &str {
+        self.objects
+            .get(usize::from(object_id))
+            .map_or("", |x| x.as_ref())
+    }
+
+    fn attribute_decoder(&self) -> AttributeDecoder {
+        AttributeDecoder {
+            version: self.version,
+            product_decoder: self.product_decoder,
+            is_rl_223: self.is_rl_223,
+        }
+    }
+
+    #[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+    pub fn decode_frames(
+        self,
+        data: &[u8],
+        keyframes: &[KeyFrame],
+    ) -> Result<Vec<Frame>, NetworkError> {
+        let decoder = Arc::new(self);
+
+        // Segments are decoded without the context of prior frames, so a trace needs to be
+        // decoded sequentially. So does a filter, as a segment can't tell if an actor deleted in
+        // it was spawned in a prior segment as an actor that is filtered out.
+        #[cfg(feature = "rayon")]
+        if !decoder.trace && decoder.actor_filter.is_none() && decoder.attribute_filter.is_none() {
+            if let Some(frames) = decode_segments(&decoder, data, keyframes) {
+                return Ok(frames);
+            }
+        }
+
+        let mut frames: Vec<Frame> = Vec::with_capacity(decoder.frames_len);
+        for frame in FrameIterator::new(decoder, data) {
+            match frame {
+                Ok(frame) => frames.push(frame),
+
+                // The iterator doesn't hold onto previously decoded frames, so hand them to the
+                // error so that it can backtrack to the last frame with data
+                Err(NetworkError::FrameError(e, mut context)) => {
+                    context.frames = frames;
+                    return Err(NetworkError::FrameError(e, context));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// Decodes the frames like `decode_frames`, but a frame that fails to decode is recorded
+    /// instead of ending the decoding. Decoding resumes at the first keyframe after the failed
+    /// frame, and the skipped frames are filled with empty frames so that frames can still be
+    /// looked up by their index. The times of the empty frames are spread evenly up to the time
+    /// of the keyframe.
+    pub fn decode_frames_best_effort(
+        self,
+        data: &[u8],
+        keyframes: &[KeyFrame],
+    ) -> (Vec<Frame>, Vec<NetworkFailure>) {
+        let mut frames: Vec<Frame> = Vec::with_capacity(self.frames_len);
+        let mut failures = Vec::new();
+        let mut iter = FrameIterator::new(Arc::new(self), data).with_keyframes(keyframes);
+        while let Some(frame) = iter.next_frame() {
+            let (error, bit_offset) = match frame {
+                Ok(frame) => {
+                    frames.push(frame);
+                    continue;
+                }
+                Err(e) => e,
+            };
+
+            let frame = iter.frame_index();
+            let mut resumable: Vec<(usize, usize)> = keyframes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, x)| usize::try_from(x.frame).ok().map(|frame| (frame, i)))
+                .filter(|&(x, _)| x > frame)
+                .collect();
+            resumable.sort_unstable();
+
+            let resumed_at = resumable.iter().find_map(|&(_, i)| {
+                iter.seek_to_keyframe(i)
+                    .ok()
+                    .map(|frame| (frame, keyframes[i].time))
+            });
+
+            failures.push(NetworkFailure {
+                frame,
+                bit_offset,
+                error,
+                resumed_at: resumed_at.map(|(frame, _)| frame),
+            });
+
+            match resumed_at {
+                Some((resume, time)) => {
+                    let start = frames.last().map_or(time, |x| x.time);
+                    let skipped = resume.saturating_sub(frames.len());
+                    let delta = (time - start) / (skipped + 1) as f32;
+                    for i in 1..=skipped {
+                        frames.push(Frame {
+                            time: start + delta * i as f32,
+                            delta,
+                            new_actors: Vec::new(),
+                            deleted_actors: Vec::new(),
+                            updated_actors: Vec::new(),
+                        });
+                    }
+                }
+                None => break,
+            }
+        }
+
+        (frames, failures)
+    }
+}
+
+/// Splits the network data at the keyframes and decodes each segment on the rayon thread pool.
+/// Every live actor is replicated again at a keyframe, so segments can be decoded independently.
+///
+/// Returns `None` when the keyframes can't be trusted to split the data or a segment fails to
+/// decode, so that the caller can decode the data sequentially and report errors with the full
+/// context of the prior frames.
+#[cfg(feature = "rayon")]
+fn decode_segments(
+    decoder: &Arc<FrameDecoder<'_>>,
+    data: &[u8],
+    keyframes: &[KeyFrame],
+) -> Option<Vec<Frame>> {
+    use rayon::prelude::*;
+
+    // The (frame, bit position) where each segment starts
+    let mut starts = vec![(0, 0)];
+    for keyframe in keyframes.iter().filter(|x| x.frame != 0) {
+        let frame = usize::try_from(keyframe.frame).ok()?;
+        let position = usize::try_from(keyframe.position).ok()?;
+        let &(last_frame, last_position) = starts.last()?;
+        if frame <= last_frame || position <= last_position || frame >= decoder.frames_len {
+            return None;
+        }
+
+        starts.push((frame, position));
+    }
+
+    if starts.len() < 2 || starts.last()?.1 >= data.len() * 8 {
+        return None;
+    }
+
+    let segments = (0..starts.len())
+        .into_par_iter()
+        .map(|i| {
+            let (frame, position) = starts[i];
+            let end = starts.get(i + 1).copied();
+            let len = end.map_or(decoder.frames_len, |(x, _)| x) - frame;
+
+            let mut iter = FrameIterator::new(Arc::clone(decoder), data);
+            iter.reset(position, frame);
+            let frames = iter
+                .by_ref()
+                .take(len)
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?;
+
+            // A segment must end exactly where the next one starts
+            match end {
+                Some((_, next)) if frames.len() != len || iter.position != next => None,
+                _ => Some(frames),
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(segments.into_iter().flatten().collect())
+}
+
+/// Lazily decodes the network data one frame at a time.
+///
+/// Decoding a frame depends on the actors seen in all prior frames, so the iterator must be
+/// driven in order, but only the actor lookup is retained between frames. Once a frame fails to
+/// decode, the error is yielded and the iterator is exhausted.
+///
+/// Rocket league replicates every live actor again at a keyframe, so decoding can also start at a
+/// keyframe with `seek_to_keyframe` or `decode_from`, which is useful to scrub through a replay
+/// without decoding all the frames that came before.
+pub struct FrameIterator<'a> {
+    decoder: Arc<FrameDecoder<'a>>,
+    attr_decoder: AttributeDecoder,
+    data: &'a [u8],
+    keyframes: Vec<KeyFrame>,
+
+    /// Bit offset into the network data where the next frame starts
+    position: usize,
+    frames_decoded: usize,
+    finished: bool,
+    actors: FnvHashMap<ActorId, ObjectId>,
+    buffers: FrameBuffers,
+    tracer: Tracer,
+
+    /// Time of the frame being decoded
+    time: f32,
+    failure: Option<AttributeFailure>,
+    buf: [u8; 1024],
+}
+
+impl<'a> FrameIterator<'a> {
+    pub(crate) fn new(decoder: Arc<FrameDecoder<'a>>, data: &'a [u8]) -> Self {
+        FrameIterator {
+            attr_decoder: decoder.attribute_decoder(),
+            tracer: Tracer::new(decoder.trace, data),
+            decoder,
+            data,
+            keyframes: Vec::new(),
+            position: 0,
+            frames_decoded: 0,
+            finished: false,
+            actors: FnvHashMap::default(),
+            buffers: FrameBuffers::default(),
+            time: 0.0,
+            failure: None,
+            buf: [0u8; 1024],
+        }
+    }
+
+    pub(crate) fn with_keyframes(mut self, keyframes: &[KeyFrame]) -> Self {
+        self.keyframes = keyframes.to_vec();
+        self
+    }
+
+    /// The index of the next frame to be decoded
+    pub fn frame_index(&self) -> usize {
+        self.frames_decoded
+    }
+
+    pub fn keyframes(&self) -> &[KeyFrame] {
+        &self.keyframes
+    }
+
+    /// Positions the iterator so that the next frame decoded is the frame of the keyframe at the
+    /// given index. Returns the index of that frame.
+    ///
+    /// ```
+    /// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+    /// let (_, mut frames) = boxcars::ParserBuilder::new(&data[..]).frames().unwrap();
+    ///
+    /// let keyframe = frames.keyframes()[3];
+    /// assert_eq!(frames.seek_to_keyframe(3).unwrap(), keyframe.frame as usize);
+    /// assert_eq!(frames.next().unwrap().unwrap().time, keyframe.time);
+    /// ```
+    pub fn seek_to_keyframe(&mut self, index: usize) -> Result<usize, NetworkError> {
+        let keyframe = self
+            .keyframes
+            .get(index)
+            .ok_or(NetworkError::KeyFrameOutOfRange(index))?;
+
+        let position = usize::try_from(keyframe.position)
+            .ok()
+            .filter(|&x| x < self.data.len() * 8)
+            .ok_or(NetworkError::KeyFrameOutOfRange(index))?;
+
+        let frame = usize::try_from(keyframe.frame)
+            .ok()
+            .filter(|&x| x < self.decoder.frames_len)
+            .ok_or(NetworkError::KeyFrameOutOfRange(index))?;
+
+        // The keyframe spawns all the live actors again, so prior actors are not needed
+        self.reset(position, frame);
+        Ok(frame)
+    }
+
+    /// Positions the iterator so that the next frame decoded is the given frame. Decoding starts
+    /// at the nearest keyframe at or before the frame, and the frames in between are decoded and
+    /// discarded.
+    ///
+    /// ```
+    /// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+    /// let replay = boxcars::ParserBuilder::new(&data[..])
+    ///     .must_parse_network_data()
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let (_, mut frames) = boxcars::ParserBuilder::new(&data[..]).frames().unwrap();
+    /// frames.decode_from(3000).unwrap();
+    /// let frame = frames.next().unwrap().unwrap();
+    /// assert_eq!(&frame, &replay.network_frames.unwrap().frames[3000]);
+    /// ```
+    pub fn decode_from(&mut self, frame: usize) -> Result<(), NetworkError> {
+        if frame >= self.decoder.frames_len {
+            return Err(NetworkError::FrameOutOfRange(frame));
+        }
+
+        let keyframe = self
+            .keyframes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| usize::try_from(x.frame).ok().map(|frame| (frame, i)))
+            .filter(|&(x, _)| x <= frame)
+            .max();
+
+        match keyframe {
+            Some((_, index)) => {
+                self.seek_to_keyframe(index)?;
+            }
+            None => self.reset(0, 0),
+        }
+
+        while self.frames_decoded < frame {
+            match self.next() {
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Err(NetworkError::FrameOutOfRange(frame)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self, position: usize, frames_decoded: usize) {
+        self.position = position;
+        self.frames_decoded = frames_decoded;
+        self.finished = false;
+        self.actors.clear();
+
+        // A frame that failed to decode may have left partial data behind
+        self.buffers.clear();
+        self.tracer.clear();
+        self.failure = None;
+    }
+
+    /// Guesses the layout of the attribute that the last frame failed to decode on, which is
+    /// useful to work out how a new or changed attribute is decoded after a patch. Each attribute
+    /// tag and run of raw bits up to 128 bits is tried in place of the attribute, and decoding
+    /// carries on for the given number of updates (new actors, deleted actors, and attribute
+    /// updates). A layout that lets the following data decode is likely to be correct.
+    ///
+    /// Later occurrences of the attribute are decoded with the same layout. When the attribute's
+    /// stream id is unknown, these are the occurrences on actors of the same object. Guesses are
+    /// ordered from most to least likely, with tags ahead of raw bits that are as likely. Returns
+    /// `None` when the iterator didn't fail on an attribute.
+    ///
+    /// ```
+    /// use boxcars::{AttributeLayout, AttributeTag, ParserBuilder, Registry};
+    ///
+    /// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+    /// let mut registry = Registry::new();
+    /// registry.add_attribute("TAGame.Car_TA:TeamPaint", AttributeTag::NotImplemented);
+    /// let (_, mut frames) = ParserBuilder::new(&data[..])
+    ///     .with_registry(&registry)
+    ///     .frames()
+    ///     .unwrap();
+    ///
+    /// assert!(frames.by_ref().any(|x| x.is_err()));
+    /// let guesses = frames.guess_attribute(100).unwrap();
+    ///
+    /// // Layouts that span the same number of bits can't be told apart
+    /// let likely: Vec<_> = guesses
+    ///     .iter()
+    ///     .filter(|x| x.consistent)
+    ///     .map(|x| x.layout)
+    ///     .collect();
+    /// assert!(likely.contains(&AttributeLayout::Tag(AttributeTag::TeamPaint)));
+    /// assert!(likely.contains(&AttributeLayout::Bits(88)));
+    /// ```
+    pub fn guess_attribute(&self, updates: usize) -> Option<Vec<AttributeGuess>> {
+        let failure = self.failure?;
+        let mut guesses: Vec<AttributeGuess> = AttributeLayout::candidates()
+            .filter_map(|layout| self.try_layout(failure, layout, updates))
+            .collect();
+
+        // A stable sort keeps the candidates in order when they are equally likely
+        guesses.sort_by(|a, b| {
+            b.consistent
+                .cmp(&a.consistent)
+                .then(b.updates.cmp(&a.updates))
+        });
+        Some(guesses)
+    }
+
+    /// Decodes the failed attribute with the layout and counts the updates that follow
+    fn try_layout(
         &self,
-        attr_decoder: &AttributeDecoder,
+        failure: AttributeFailure,
+        layout: AttributeLayout,
+        updates: usize,
+    ) -> Option<AttributeGuess> {
+        let layout_decoder = LayoutDecoder {
+            layout,
+            decoder: self.attr_decoder,
+        };
+
+        // Everything is decoded so that the updates of filtered actors are counted too
+        let mut decoder = FrameDecoder::clone(&self.decoder);
+        decoder.actor_filter = None;
+        decoder.attribute_filter = None;
+        decoder.trace = false;
+
+        // A known attribute is decoded with the layout on actors of any object, while an unknown
+        // stream id only identifies the attribute on actors of the failed actor's object
+        let object_id = self
+            .decoder
+            .object_ind_attributes
+            .get(&failure.actor_object)
+            .and_then(|x| x.attributes.get(&failure.stream))
+            .map(|x| x.object_id);
+        match object_id {
+            Some(object_id) => {
+                decoder
+                    .custom_decoders
+                    .insert(object_id, Arc::new(layout_decoder));
+            }
+            None => {
+                decoder
+                    .layouts
+                    .insert((failure.actor_object, failure.stream), layout_decoder);
+            }
+        }
+
+        let mut iter = FrameIterator::new(Arc::new(decoder), self.data);
+        iter.position = failure.bit_offset;
+        iter.frames_decoded = self.frames_decoded;
+        iter.actors = self.actors.clone();
+        iter.time = failure.time;
+
+        let mut bits = iter.reader();
+        let start = iter.tracer.offset(&bits);
+        let attribute = layout_decoder.decode_layout(&mut bits).ok()?;
+        let bit_len = iter.tracer.offset(&bits) - start;
+
+        // Finish the frame the attribute is in before decoding the frames after it
+        let mut sink = GuessSink::new(failure.time, updates);
+        let rest = iter
+            .decode_updates(&mut bits, failure.actor, failure.actor_object, &mut sink)
+            .and_then(|_| iter.decode_actors(&mut bits, &mut sink));
+
+        if rest.is_err() {
+            sink.consistent = false;
+        } else {
+            iter.position = iter.tracer.offset(&bits);
+            iter.frames_decoded += 1;
+        }
+
+        while sink.wants_more() {
+            match iter.decode_next(&mut sink) {
+                Some(Ok(())) => {}
+                Some(Err(_)) => sink.consistent = false,
+
+                // Misread data can look like the end of the network data, so it must end after
+                // the last frame
+                None => {
+                    if iter.frames_decoded < iter.decoder.frames_len {
+                        sink.consistent = false;
+                    }
+                    break;
+                }
+            }
+        }
+
+        Some(AttributeGuess {
+            layout,
+            attribute,
+            bit_len,
+            updates: sink.updates.min(updates),
+            consistent: sink.consistent,
+        })
+    }
+
+    /// Creates a bit reader that is positioned at the start of the next frame
+    fn reader(&self) -> LittleEndianReader<'a> {
+        let data: &'a [u8] = self.data;
+        let mut bits = LittleEndianReader::new(&data[self.position / 8..]);
+        let offset = (self.position % 8) as u32;
+        if offset != 0 {
+            bits.refill_lookahead();
+            bits.consume(offset);
+        }
+        bits
+    }
+
+    /// Decodes the remaining frames into the visitor instead of collecting them into frames.
+    /// Decoding stops at the first frame that fails to decode, after the visitor received the
+    /// data decoded prior to the failure.
+    pub fn visit<V: NetworkVisitor + ?Sized>(
+        &mut self,
+        visitor: &mut V,
+    ) -> Result<(), NetworkError> {
+        let mut sink = VisitorSink(visitor);
+        while let Some(result) = self.decode_next(&mut sink) {
+            if let Err((e, bit_offset)) = result {
+                return Err(NetworkError::FrameError(
+                    e,
+                    Box::new(self.frame_context(bit_offset)),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the next frame. On failure, returns the error with the bit offset where decoding
+    /// failed.
+    fn next_frame(&mut self) -> Option<Result<Frame, (FrameError, usize)>> {
+        // The buffers are moved out while decoding so that they can be borrowed as the sink
+        let mut buffers = std::mem::take(&mut self.buffers);
+        let result = self.decode_next(&mut buffers);
+        self.buffers = buffers;
+        Some(result?.map(|_| self.buffers.take_frame()))
+    }
+
+    /// Decodes the next frame into the sink. On failure, returns the error with the bit offset
+    /// where decoding failed.
+    fn decode_next<S: FrameSink>(
+        &mut self,
+        sink: &mut S,
+    ) -> Option<Result<(), (FrameError, usize)>> {
+        if self.finished
+            || self.frames_decoded >= self.decoder.frames_len
+            || self.position >= self.data.len() * 8
+        {
+            self.finished = true;
+            return None;
+        }
+
+        let mut bits = self.reader();
+        match self.decode_frame(&mut bits, sink) {
+            Ok(DecodedFrame::Frame) => {
+                self.position = self.data.len() * 8 - bits.bits_remaining().unwrap_or(0);
+                self.frames_decoded += 1;
+                Some(Ok(()))
+            }
+            Ok(DecodedFrame::EndFrame) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                let bit_offset = self.data.len() * 8 - bits.bits_remaining().unwrap_or(0);
+                Some(Err((e, bit_offset)))
+            }
+        }
+    }
+
+    fn decode_frame<S: FrameSink>(
+        &mut self,
         bits: &mut LittleEndianReader<'_>,
-        buf: &mut [u8],
-        actors: &mut FnvHashMap<ActorId, ObjectId>,
-        new_actors: &mut Vec<NewActor>,
-        deleted_actors: &mut Vec<ActorId>,
-        updated_actors: &mut Vec<UpdatedAttribute>,
+        sink: &mut S,
     ) -> Result<DecodedFrame, FrameError> {
+        self.tracer.start_frame(self.frames_decoded);
+        let start = self.tracer.offset(bits);
         let time = bits
             .read_f32()
             .ok_or(FrameError::NotEnoughDataFor("Time"))?;
+        self.tracer.record(start, bits, "Time", || time.to_string());
 
         if time < 0.0 || (time > 0.0 && time < 1e-10) {
             return Err(FrameError::TimeOutOfRange { time });
         }
 
+        let start = self.tracer.offset(bits);
         let delta = bits
             .read_f32()
             .ok_or(FrameError::NotEnoughDataFor("Delta"))?;
+        self.tracer
+            .record(start, bits, "Delta", || delta.to_string());
 
         if delta < 0.0 || (delta > 0.0 && delta < 1e-10) {
             return Err(FrameError::DeltaOutOfRange { delta });
@@ -98,183 +838,298 @@ impl<'a, 'b> FrameDecoder<'a, 'b> {
             return Ok(DecodedFrame::EndFrame);
         }
 
-        while bits
-            .read_bit()
-            .ok_or(FrameError::NotEnoughDataFor("Actor data"))?
-        {
+        self.time = time;
+        sink.frame_start(time, delta);
+        self.decode_actors(bits, sink)?;
+        sink.frame_end();
+        Ok(DecodedFrame::Frame)
+    }
+
+    /// Decodes the actors replicated in the rest of the frame
+    fn decode_actors<S: FrameSink>(
+        &mut self,
+        bits: &mut LittleEndianReader<'_>,
+        sink: &mut S,
+    ) -> Result<(), FrameError> {
+        loop {
+            let start = self.tracer.offset(bits);
+            let present = bits
+                .read_bit()
+                .ok_or(FrameError::NotEnoughDataFor("Actor data"))?;
+            self.tracer
+                .record(start, bits, "Actor present", || present.to_string());
+            if !present {
+                return Ok(());
+            }
+
             let len = bits.refill_lookahead();
-            if len < self.channel_bits + 1 + 1 {
+            if len < self.decoder.channel_bits + 1 + 1 {
                 return Err(FrameError::NotEnoughDataFor("Actor Id"));
             }
 
-            let max = u64::from(self.max_channels);
-            let actor_id_raw = bits.peek_bits_max_computed(self.channel_bits, max);
+            let start = self.tracer.offset(bits);
+            let max = u64::from(self.decoder.max_channels);
+            let actor_id_raw = bits.peek_bits_max_computed(self.decoder.channel_bits, max);
             let actor_id = ActorId(actor_id_raw as i32);
+            self.tracer
+                .record(start, bits, "Actor id", || actor_id.to_string());
 
             // alive
-            if bits.peek_and_consume(1) == 1 {
+            let start = self.tracer.offset(bits);
+            let alive = bits.peek_and_consume(1) == 1;
+            self.tracer
+                .record(start, bits, "Alive", || alive.to_string());
+            if alive {
                 // new
-                if bits
+                let start = self.tracer.offset(bits);
+                let is_new = bits
                     .read_bit()
-                    .ok_or(FrameError::NotEnoughDataFor("Is new actor"))?
-                {
-                    let actor = self.parse_new_actor(bits, actor_id)?;
+                    .ok_or(FrameError::NotEnoughDataFor("Is new actor"))?;
+                self.tracer
+                    .record(start, bits, "Is new actor", || is_new.to_string());
+
+                if is_new {
+                    let start = self.tracer.offset(bits);
+                    let actor = self.decoder.parse_new_actor(bits, actor_id)?;
+                    let decoder = &self.decoder;
+                    self.tracer.record(start, bits, "New actor", || {
+                        format!("{} {:?}", decoder.object_name(actor.object_id), actor)
+                    });
 
                     // Insert the new actor so we can keep track of it for attribute
                     // updates. It's common for an actor id to already exist, so we
                     // overwrite it.
-                    actors.insert(actor.actor_id, actor.object_id);
-                    new_actors.push(actor);
+                    self.actors.insert(actor.actor_id, actor.object_id);
+                    if self.decoder.wants_actor(actor.object_id) {
+                        sink.new_actor(actor);
+                    }
                 } else {
                     // We'll be updating an existing actor with some attributes so we need
                     // to track down what the actor's type is
-                    let object_id = actors
+                    let object_id = *self
+                        .actors
                         .get(&actor_id)
                         .ok_or(FrameError::MissingActor { actor: actor_id })?;
 
-                    // Once we have the type we need to look up what attributes are
-                    // available for said type
-                    let cache_info = self.object_ind_attributes.get(object_id).ok_or(
-                        FrameError::MissingCache {
-                            actor: actor_id,
-                            actor_object: *object_id,
-                        },
-                    )?;
-
-                    // While there are more attributes to update for our actor:
-                    while bits
-                        .read_bit()
-                        .ok_or(FrameError::NotEnoughDataFor("Is prop present"))?
-                    {
-                        // We've previously calculated the max the stream id can be for a
-                        // given type and how many bits that it encompasses so use those
-                        // values now
-                        let len = bits.refill_lookahead();
-                        if len < cache_info.prop_id_bits + 1 {
-                            return Err(FrameError::NotEnoughDataFor("Prop id"));
-                        }
-
-                        let stream_id_raw = bits.peek_bits_max_computed(
-                            cache_info.prop_id_bits,
-                            u64::from(cache_info.max_prop_id),
-                        );
-                        let stream_id = StreamId(stream_id_raw as i32);
-
-                        // Look the stream id up and find the corresponding attribute
-                        // decoding function. Experience has told me replays that fail to
-                        // parse, fail to do so here, so a large chunk is dedicated to
-                        // generating an error message with context
-                        let attr = cache_info.attributes.get(&stream_id).ok_or(
-                            FrameError::MissingAttribute {
-                                actor: actor_id,
-                                actor_object: *object_id,
-                                attribute_stream: stream_id,
-                            },
-                        )?;
-
-                        let attribute = attr_decoder.decode(attr.attribute, bits, buf).map_err(
-                            |e| match e {
-                                AttributeError::Unimplemented => FrameError::MissingAttribute {
-                                    actor: actor_id,
-                                    actor_object: *object_id,
-                                    attribute_stream: stream_id,
-                                },
-                                e => FrameError::AttributeError {
-                                    actor: actor_id,
-                                    actor_object: *object_id,
-                                    attribute_stream: stream_id,
-                                    error: e,
-                                },
-                            },
-                        )?;
-
-                        updated_actors.push(UpdatedAttribute {
-                            actor_id,
-                            stream_id,
-                            object_id: attr.object_id,
-                            attribute,
-                        });
-                    }
+                    self.decode_updates(bits, actor_id, object_id, sink)?;
                 }
             } else {
-                deleted_actors.push(actor_id);
-                actors.remove(&actor_id);
+                let wanted = match self.actors.remove(&actor_id) {
+                    Some(object_id) => self.decoder.wants_actor(object_id),
+                    None => true,
+                };
+
+                if wanted {
+                    sink.actor_deleted(actor_id);
+                }
             }
         }
-
-        Ok(DecodedFrame::Frame(Frame {
-            time,
-            delta,
-            new_actors: new_actors.drain(..).collect(),
-            deleted_actors: deleted_actors.drain(..).collect(),
-            updated_actors: updated_actors.drain(..).collect(),
-        }))
     }
 
-    pub fn decode_frames(&self) -> Result<Vec<Frame>, NetworkError> {
-        let attr_decoder = AttributeDecoder {
-            version: self.version,
-            product_decoder: self.product_decoder,
-            is_rl_223: self.is_rl_223,
-        };
-
-        let mut frames: Vec<Frame> = Vec::with_capacity(self.frames_len);
-        let mut actors = FnvHashMap::default();
-        let mut bits = LittleEndianReader::new(self.body.network_data);
-        let mut new_actors = Vec::new();
-        let mut updated_actors = Vec::new();
-        let mut deleted_actors = Vec::new();
-        let mut buf = [0u8; 1024];
-
-        while !bits.is_empty() && frames.len() < self.frames_len {
-            let frame = self
-                .decode_frame(
-                    &attr_decoder,
-                    &mut bits,
-                    &mut buf,
-                    &mut actors,
-                    &mut new_actors,
-                    &mut deleted_actors,
-                    &mut updated_actors,
-                )
-                .map_err(|e| {
-                    NetworkError::FrameError(
-                        e,
-                        Box::new(FrameContext {
-                            objects: self.body.objects.clone(),
-                            object_attributes: self
-                                .object_ind_attributes
-                                .iter()
-                                .map(|(key, value)| {
-                                    (
-                                        *key,
-                                        value
-                                            .attributes
-                                            .iter()
-                                            .map(|(key2, value)| (*key2, value.object_id))
-                                            .collect(),
-                                    )
-                                })
-                                .collect(),
-                            frames: frames.clone(),
-                            actors: actors.clone(),
-                            new_actors: new_actors.clone(),
-                            updated_actors: updated_actors.clone(),
-                        }),
-                    )
+    /// Decodes the rest of the attribute updates of an existing actor
+    fn decode_updates<S: FrameSink>(
+        &mut self,
+        bits: &mut LittleEndianReader<'_>,
+        actor_id: ActorId,
+        object_id: ObjectId,
+        sink: &mut S,
+    ) -> Result<(), FrameError> {
+        // Once we have the type we need to look up what attributes are
+        // available for said type
+        let cache_info =
+            self.decoder
+                .object_ind_attributes
+                .get(&object_id)
+                .ok_or(FrameError::MissingCache {
+                    actor: actor_id,
+                    actor_object: object_id,
                 })?;
 
-            match frame {
-                DecodedFrame::EndFrame => break,
-                DecodedFrame::Frame(frame) => frames.push(frame),
+        // Attributes of unwanted actors are read past without being kept
+        let wants_actor = self.decoder.wants_actor(object_id);
+
+        // While there are more attributes to update for our actor:
+        loop {
+            let start = self.tracer.offset(bits);
+            let present = bits
+                .read_bit()
+                .ok_or(FrameError::NotEnoughDataFor("Is prop present"))?;
+            self.tracer
+                .record(start, bits, "Prop present", || present.to_string());
+            if !present {
+                return Ok(());
             }
+
+            // We've previously calculated the max the stream id can be for a
+            // given type and how many bits that it encompasses so use those
+            // values now
+            let len = bits.refill_lookahead();
+            if len < cache_info.prop_id_bits + 1 {
+                return Err(FrameError::NotEnoughDataFor("Prop id"));
+            }
+
+            let start = self.tracer.offset(bits);
+            let stream_id_raw = bits
+                .peek_bits_max_computed(cache_info.prop_id_bits, u64::from(cache_info.max_prop_id));
+            let stream_id = StreamId(stream_id_raw as i32);
+            let decoder = &self.decoder;
+            self.tracer.record(start, bits, "Stream id", || {
+                let attribute = cache_info
+                    .attributes
+                    .get(&stream_id)
+                    .map_or("unknown", |x| decoder.object_name(x.object_id));
+                format!("{} ({})", stream_id, attribute)
+            });
+
+            // Where the attribute starts, so that its layout can be guessed if it fails to
+            // decode
+            let start = self.tracer.offset(bits);
+            let failure = AttributeFailure {
+                bit_offset: start,
+                time: self.time,
+                actor: actor_id,
+                actor_object: object_id,
+                stream: stream_id,
+            };
+
+            // A layout being guessed takes precedence, as its stream id may be unknown
+            if let Some(layout) = self.decoder.layouts.get(&(object_id, stream_id)) {
+                let attribute = match layout.decode_layout(bits) {
+                    Ok(attribute) => attribute,
+                    Err(e) => {
+                        self.failure = Some(failure);
+                        return Err(failure.error(e));
+                    }
+                };
+
+                if let Some(attr) = cache_info.attributes.get(&stream_id) {
+                    sink.attribute(UpdatedAttribute {
+                        actor_id,
+                        stream_id,
+                        object_id: attr.object_id,
+                        attribute,
+                    });
+                }
+                continue;
+            }
+
+            // Look the stream id up and find the corresponding attribute
+            // decoding function. Experience has told me replays that fail to
+            // parse, fail to do so here, so a large chunk is dedicated to
+            // generating an error message with context
+            let attr = match cache_info.attributes.get(&stream_id) {
+                Some(attr) => attr,
+                None => {
+                    self.failure = Some(failure);
+                    return Err(failure.error(AttributeError::Unimplemented));
+                }
+            };
+
+            let wanted = wants_actor && self.decoder.wants_attribute(attr.object_id);
+            let result = match self.decoder.custom_decoders.get(&attr.object_id) {
+                Some(custom) => {
+                    let ctx = AttributeContext {
+                        attribute: self.decoder.object_name(attr.object_id),
+                        actor: actor_id,
+                        actor_object: object_id,
+                        major_version: self.decoder.version.0,
+                        minor_version: self.decoder.version.1,
+                        net_version: self.decoder.version.2,
+                    };
+                    custom.decode(bits, &ctx).map(Some)
+                }
+                None if wanted => self
+                    .attr_decoder
+                    .decode(attr.attribute, bits, &mut self.buf)
+                    .map(Some),
+                None => self
+                    .attr_decoder
+                    .skip(attr.attribute, bits, &mut self.buf)
+                    .map(|_| None),
+            };
+
+            let attribute = match result {
+                Ok(attribute) => attribute,
+                Err(e) => {
+                    self.failure = Some(failure);
+                    return Err(failure.error(e));
+                }
+            };
+
+            self.tracer.record(
+                start,
+                bits,
+                self.decoder.object_name(attr.object_id),
+                || match &attribute {
+                    Some(attribute) => format!("{:?}", attribute),
+                    None => String::from("(skipped)"),
+                },
+            );
+
+            if let Some(attribute) = attribute.filter(|_| wanted) {
+                sink.attribute(UpdatedAttribute {
+                    actor_id,
+                    stream_id,
+                    object_id: attr.object_id,
+                    attribute,
+                });
+            }
+        }
+    }
+
+    fn frame_context(&self, bit_offset: usize) -> FrameContext {
+        FrameContext {
+            bit_offset,
+            objects: self
+                .decoder
+                .objects
+                .iter()
+                .map(|x| String::from(x.as_ref()))
+                .collect(),
+            object_attributes: self
+                .decoder
+                .object_ind_attributes
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        *key,
+                        value
+                            .attributes
+                            .iter()
+                            .map(|(key2, value)| (*key2, value.object_id))
+                            .collect(),
+                    )
+                })
+                .collect(),
+            frames: Vec::new(),
+            actors: self.actors.clone(),
+            new_actors: self.buffers.new_actors.clone(),
+            updated_actors: self.buffers.updated_actors.clone(),
+            trace: self.tracer.trace(self.data, bit_offset),
         }
+    }
+}
 
-        if self.version >= VersionTriplet(868, 24, 10) {
-            // Some qualifying replays are missing trailer (eg: 00bb.replay)
-            let _ = bits.read_u32();
+impl<'a> Iterator for FrameIterator<'a> {
+    type Item = Result<Frame, NetworkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_frame()? {
+            Ok(frame) => Some(Ok(frame)),
+            Err((e, bit_offset)) => Some(Err(NetworkError::FrameError(
+                e,
+                Box::new(self.frame_context(bit_offset)),
+            ))),
         }
+    }
 
-        Ok(frames)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            (0, Some(0))
+        } else {
+            (0, Some(self.decoder.frames_len - self.frames_decoded))
+        }
     }
 }
+
+impl<'a> FusedIterator for FrameIterator<'a> {}
@@ -0,0 +1,259 @@
+use fnv::FnvHashMap;
+use std::convert::TryFrom;
+
+use crate::bits::BitWriter;
+use crate::errors::{AttributeError, FrameContext, FrameError, NetworkError};
+use crate::network::attributes::AttributeEncoder;
+use crate::network::frame_decoder::FrameDecoder;
+use crate::network::models::{ActorId, Frame, NewActor, ObjectId, UpdatedAttribute};
+use crate::network::VersionTriplet;
+
+/// Network data encoded from frames
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EncodedFrames {
+    pub data: Vec<u8>,
+
+    /// The bit offset into the data where each frame starts
+    pub frame_positions: Vec<usize>,
+}
+
+/// The inverse of the `FrameIterator`: writes frames to network data using the same lookup tables
+/// that the decoder uses.
+///
+/// A frame doesn't record how its new, updated, and deleted actors were interleaved, so they are
+/// written in the order that rocket league writes them: deleted actors, then new actors, and then
+/// the actor updates. The exception is an actor that is updated and deleted within a frame, which
+/// is deleted after the updates.
+pub(crate) struct FrameEncoder<'a> {
+    decoder: FrameDecoder<'a>,
+    attr_encoder: AttributeEncoder,
+    actors: FnvHashMap<ActorId, ObjectId>,
+    bits: BitWriter,
+}
+
+impl<'a> FrameEncoder<'a> {
+    pub fn new(decoder: FrameDecoder<'a>) -> Self {
+        FrameEncoder {
+            attr_encoder: AttributeEncoder {
+                version: decoder.version,
+                product_decoder: decoder.product_decoder,
+                is_rl_223: decoder.is_rl_223,
+            },
+            decoder,
+            actors: FnvHashMap::default(),
+            bits: BitWriter::new(),
+        }
+    }
+
+    pub fn encode_frames(mut self, frames: &[Frame]) -> Result<EncodedFrames, NetworkError> {
+        let mut frame_positions = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            frame_positions.push(self.bits.bits_written());
+            if let Err(e) = self.encode_frame(frame) {
+                let context = FrameContext {
+                    bit_offset: self.bits.bits_written(),
+                    objects: self
+                        .decoder
+                        .objects
+                        .iter()
+                        .map(|x| String::from(x.as_ref()))
+                        .collect(),
+                    object_attributes: self
+                        .decoder
+                        .object_ind_attributes
+                        .iter()
+                        .map(|(key, value)| {
+                            (
+                                *key,
+                                value
+                                    .attributes
+                                    .iter()
+                                    .map(|(key2, value)| (*key2, value.object_id))
+                                    .collect(),
+                            )
+                        })
+                        .collect(),
+                    frames: frames[..i].to_vec(),
+                    actors: self.actors,
+                    new_actors: frame.new_actors.clone(),
+                    updated_actors: frame.updated_actors.clone(),
+                    trace: None,
+                };
+                return Err(NetworkError::FrameError(e, Box::new(context)));
+            }
+        }
+
+        Ok(EncodedFrames {
+            data: self.bits.into_inner(),
+            frame_positions,
+        })
+    }
+
+    fn encode_frame(&mut self, frame: &Frame) -> Result<(), FrameError> {
+        let time = frame.time;
+        if time < 0.0 || (time > 0.0 && time < 1e-10) {
+            return Err(FrameError::TimeOutOfRange { time });
+        }
+
+        let delta = frame.delta;
+        if delta < 0.0 || (delta > 0.0 && delta < 1e-10) {
+            return Err(FrameError::DeltaOutOfRange { delta });
+        }
+
+        // A frame without a time or delta marks the end of the network data
+        if time == 0.0 && delta == 0.0 {
+            return Err(FrameError::TimeOutOfRange { time });
+        }
+
+        self.bits.write_f32(time);
+        self.bits.write_f32(delta);
+
+        // An actor that is updated and then deleted in the same frame must be deleted last
+        let (deleted, deleted_last): (Vec<ActorId>, Vec<ActorId>) =
+            frame.deleted_actors.iter().partition(|&id| {
+                frame.new_actors.iter().any(|x| x.actor_id == *id)
+                    || !frame.updated_actors.iter().any(|x| x.actor_id == *id)
+            });
+
+        for actor_id in deleted {
+            self.encode_deleted_actor(actor_id)?;
+        }
+
+        for actor in &frame.new_actors {
+            self.encode_new_actor(actor)?;
+        }
+
+        // Consecutive updates to the same actor are written under a single actor entry
+        let mut updates = &frame.updated_actors[..];
+        while let Some(first) = updates.first() {
+            let len = updates
+                .iter()
+                .position(|x| x.actor_id != first.actor_id)
+                .unwrap_or(updates.len());
+            let (actor_updates, rest) = updates.split_at(len);
+            self.encode_updated_actor(first.actor_id, actor_updates)?;
+            updates = rest;
+        }
+
+        for actor_id in deleted_last {
+            self.encode_deleted_actor(actor_id)?;
+        }
+
+        self.bits.write_bit(false);
+        Ok(())
+    }
+
+    fn encode_actor_id(&mut self, actor_id: ActorId) -> Result<(), FrameError> {
+        let max = u64::from(self.decoder.max_channels);
+        let id = u64::try_from(actor_id.0)
+            .ok()
+            .filter(|&x| x < max)
+            .ok_or(FrameError::ValueOutOfRange("Actor Id"))?;
+
+        self.bits.write_bit(true);
+        self.bits
+            .write_bits_max_computed(self.decoder.channel_bits, max, id);
+        Ok(())
+    }
+
+    fn encode_deleted_actor(&mut self, actor_id: ActorId) -> Result<(), FrameError> {
+        self.encode_actor_id(actor_id)?;
+        self.bits.write_bit(false);
+        self.actors.remove(&actor_id);
+        Ok(())
+    }
+
+    fn encode_new_actor(&mut self, actor: &NewActor) -> Result<(), FrameError> {
+        let spawn = usize::try_from(actor.object_id.0)
+            .ok()
+            .and_then(|x| self.decoder.spawns.get(x).copied())
+            .ok_or(FrameError::ObjectIdOutOfRange {
+                obj: actor.object_id,
+            })?;
+
+        self.encode_actor_id(actor.actor_id)?;
+        self.bits.write_bit(true);
+        self.bits.write_bit(true);
+
+        let version = self.decoder.version;
+        let do_write_name = version >= VersionTriplet(868, 20, 0)
+            || (version >= VersionTriplet(868, 14, 0) && !self.decoder.is_lan);
+        if do_write_name {
+            self.bits.write_i32(actor.name_id.unwrap_or(0));
+        }
+
+        // The decoder ignores this bit, but it has only been seen unset
+        self.bits.write_bit(false);
+        self.bits.write_i32(actor.object_id.0);
+        actor
+            .initial_trajectory
+            .encode(&mut self.bits, spawn, version.net_version())
+            .ok_or(FrameError::ValueOutOfRange("New Actor"))?;
+
+        self.actors.insert(actor.actor_id, actor.object_id);
+        Ok(())
+    }
+
+    fn encode_updated_actor(
+        &mut self,
+        actor_id: ActorId,
+        updates: &[UpdatedAttribute],
+    ) -> Result<(), FrameError> {
+        let object_id = *self
+            .actors
+            .get(&actor_id)
+            .ok_or(FrameError::MissingActor { actor: actor_id })?;
+
+        if !self.decoder.object_ind_attributes.contains_key(&object_id) {
+            return Err(FrameError::MissingCache {
+                actor: actor_id,
+                actor_object: object_id,
+            });
+        }
+
+        self.encode_actor_id(actor_id)?;
+        self.bits.write_bit(true);
+        self.bits.write_bit(false);
+
+        let cache_info = &self.decoder.object_ind_attributes[&object_id];
+
+        for update in updates {
+            let stream_id = update.stream_id;
+            let attr = cache_info
+                .attributes
+                .get(&stream_id)
+                .filter(|x| x.object_id == update.object_id)
+                .ok_or(FrameError::MissingAttribute {
+                    actor: actor_id,
+                    actor_object: object_id,
+                    attribute_stream: stream_id,
+                })?;
+
+            self.bits.write_bit(true);
+            self.bits.write_bits_max_computed(
+                cache_info.prop_id_bits,
+                u64::from(cache_info.max_prop_id),
+                stream_id.0 as u64,
+            );
+
+            self.attr_encoder
+                .encode(attr.attribute, &update.attribute, &mut self.bits)
+                .map_err(|e| match e {
+                    AttributeError::Unimplemented => FrameError::MissingAttribute {
+                        actor: actor_id,
+                        actor_object: object_id,
+                        attribute_stream: stream_id,
+                    },
+                    e => FrameError::AttributeError {
+                        actor: actor_id,
+                        actor_object: object_id,
+                        attribute_stream: stream_id,
+                        error: e,
+                    },
+                })?;
+        }
+
+        self.bits.write_bit(false);
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use bitter::LittleEndianReader;
+
+use crate::errors::AttributeError;
+use crate::network::attributes::{
+    Attribute, AttributeContext, AttributeDecode, AttributeDecoder, AttributeTag, RawAttribute,
+};
+use crate::network::models::{ActorId, NewActor, UpdatedAttribute};
+use crate::network::visitor::FrameSink;
+
+/// The widest run of raw bits that is tried as the layout of an attribute
+const MAX_RAW_BITS: u32 = 128;
+
+/// How a guess decodes an attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeLayout {
+    /// Decoded like the attributes of the tag
+    Tag(AttributeTag),
+
+    /// A fixed number of raw bits
+    Bits(u32),
+}
+
+impl AttributeLayout {
+    /// Every tag that decodes an attribute, followed by runs of raw bits
+    pub(crate) fn candidates() -> impl Iterator<Item = AttributeLayout> {
+        AttributeTag::DECODABLE
+            .iter()
+            .copied()
+            .map(AttributeLayout::Tag)
+            .chain((1..=MAX_RAW_BITS).map(AttributeLayout::Bits))
+    }
+}
+
+/// A layout that an attribute which failed to decode may have, as returned by
+/// `FrameIterator::guess_attribute`. The more of the network data that decodes after the
+/// attribute, the more likely the layout is correct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeGuess {
+    pub layout: AttributeLayout,
+
+    /// The first occurrence of the attribute decoded with the layout
+    pub attribute: Attribute,
+
+    /// The number of bits the first occurrence spans
+    pub bit_len: usize,
+
+    /// The number of new actors, deleted actors, and attribute updates that decoded after the
+    /// attribute, up to the number that was asked for
+    pub updates: usize,
+
+    /// Whether all the updates asked for (or the rest of the frames) decoded without error and
+    /// with frame times that don't go backwards
+    pub consistent: bool,
+}
+
+/// Decodes the attribute being guessed with a candidate layout
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LayoutDecoder {
+    pub layout: AttributeLayout,
+    pub decoder: AttributeDecoder,
+}
+
+impl LayoutDecoder {
+    pub fn decode_layout(
+        &self,
+        bits: &mut LittleEndianReader<'_>,
+    ) -> Result<Attribute, AttributeError> {
+        match self.layout {
+            AttributeLayout::Tag(tag) => {
+                let mut buf = [0u8; 1024];
+                self.decoder.decode(tag, bits, &mut buf)
+            }
+            AttributeLayout::Bits(bit_len) => RawAttribute::read(bits, bit_len)
+                .map(Attribute::Raw)
+                .ok_or(AttributeError::NotEnoughDataFor("Raw bits")),
+        }
+    }
+}
+
+impl AttributeDecode for LayoutDecoder {
+    fn decode(
+        &self,
+        bits: &mut LittleEndianReader<'_>,
+        _ctx: &AttributeContext<'_>,
+    ) -> Result<Attribute, AttributeError> {
+        self.decode_layout(bits)
+    }
+}
+
+/// Counts the data decoded after a guessed attribute until enough has been seen
+#[derive(Debug)]
+pub(crate) struct GuessSink {
+    time: f32,
+    limit: usize,
+    pub updates: usize,
+    pub consistent: bool,
+}
+
+impl GuessSink {
+    pub fn new(time: f32, limit: usize) -> Self {
+        GuessSink {
+            time,
+            limit,
+            updates: 0,
+            consistent: true,
+        }
+    }
+
+    /// Returns if decoding should carry on
+    pub fn wants_more(&self) -> bool {
+        self.consistent && self.updates < self.limit
+    }
+}
+
+impl FrameSink for GuessSink {
+    fn frame_start(&mut self, time: f32, _delta: f32) {
+        if time < self.time {
+            self.consistent = false;
+        }
+        self.time = time;
+    }
+
+    fn new_actor(&mut self, _actor: NewActor) {
+        self.updates += 1;
+    }
+
+    fn actor_deleted(&mut self, _actor: ActorId) {
+        self.updates += 1;
+    }
+
+    fn attribute(&mut self, _attribute: UpdatedAttribute) {
+        self.updates += 1;
+    }
+
+    fn frame_end(&mut self) {}
+}
@@ -1,26 +1,38 @@
 pub(crate) use self::attributes::*;
 pub use self::models::*;
 
+pub use self::frame_decoder::FrameIterator;
+pub use self::guess::{AttributeGuess, AttributeLayout};
+pub use self::trace::{NetworkTrace, TraceEntry};
+pub use self::visitor::NetworkVisitor;
+
 pub mod attributes;
 mod frame_decoder;
+mod frame_encoder;
+mod guess;
 mod models;
+mod trace;
+mod visitor;
 
-use crate::data::{object_classes, ATTRIBUTES, PARENT_CLASSES, SPAWN_STATS};
 use crate::errors::NetworkError;
-use crate::header::Header;
+use crate::filter::NetworkFilter;
+use crate::header::{Header, PropValue};
 use crate::models::*;
 use crate::network::frame_decoder::FrameDecoder;
+use crate::network::frame_encoder::{EncodedFrames, FrameEncoder};
 use crate::parser::ReplayBody;
+use crate::registry::Registry;
 use fnv::FnvHashMap;
+use std::borrow::Cow;
 use std::cmp;
 use std::collections::HashMap;
-use std::ops::Deref;
+use std::sync::Arc;
 
-#[derive(Debug)]
-pub(crate) struct CacheInfo<'a> {
+#[derive(Debug, Clone)]
+pub(crate) struct CacheInfo {
     max_prop_id: u32,
     prop_id_bits: u32,
-    attributes: &'a FnvHashMap<StreamId, ObjectAttribute>,
+    attributes: FnvHashMap<StreamId, ObjectAttribute>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,10 +50,165 @@ impl VersionTriplet {
     }
 }
 
-pub(crate) fn parse<'a>(
-    header: &Header,
-    body: &ReplayBody<'a>,
+/// How the network data is decoded
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct DecodeOptions<'a> {
+    /// Overrides the built-in tables for decoding
+    pub registry: Option<&'a Registry>,
+    pub filter: Option<&'a NetworkFilter>,
+
+    /// Whether to trace the decoded elements for the context of errors
+    pub trace: bool,
+}
+
+pub(crate) fn parse<S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    body: &ReplayBody<'_>,
+    options: DecodeOptions<'_>,
 ) -> Result<NetworkFrames, NetworkError> {
+    let frames =
+        frame_decoder(header, body, options)?.decode_frames(body.network_data, &body.keyframes)?;
+    Ok(NetworkFrames {
+        frames,
+        failures: Vec::new(),
+        truncated: false,
+    })
+}
+
+/// Moves the frames decoded prior to a frame that failed to decode out of the error. Errors that
+/// aren't from a frame are returned as is.
+pub(crate) fn truncated_frames(error: NetworkError) -> Result<NetworkFrames, NetworkError> {
+    match error {
+        NetworkError::FrameError(e, context) => Ok(NetworkFrames {
+            failures: vec![NetworkFailure {
+                frame: context.frames.len(),
+                bit_offset: context.bit_offset,
+                error: e,
+                resumed_at: None,
+            }],
+            frames: context.frames,
+            truncated: true,
+        }),
+        e => Err(e),
+    }
+}
+
+/// Decodes the network data, recording the frames that fail to decode instead of returning an
+/// error. An error is only returned when the lookup tables for decoding can't be constructed.
+pub(crate) fn parse_best_effort<S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    body: &ReplayBody<'_>,
+    options: DecodeOptions<'_>,
+) -> Result<NetworkFrames, NetworkError> {
+    let (frames, failures) = frame_decoder(header, body, options)?
+        .decode_frames_best_effort(body.network_data, &body.keyframes);
+    let truncated = matches!(failures.last(), Some(x) if x.resumed_at.is_none());
+    Ok(NetworkFrames {
+        frames,
+        failures,
+        truncated,
+    })
+}
+
+/// Creates an iterator that lazily decodes the network data one frame at a time
+pub(crate) fn frame_iter<'a, S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    body: &ReplayBody<'a>,
+    options: DecodeOptions<'_>,
+) -> Result<FrameIterator<'a>, NetworkError> {
+    let decoder = frame_decoder(header, body, options)?;
+    Ok(FrameIterator::new(Arc::new(decoder), body.network_data).with_keyframes(&body.keyframes))
+}
+
+/// Encodes the frames into network data that decodes to the same frames. Returns the encoded
+/// data alongside the bit offset where each frame starts.
+pub(crate) fn encode<S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    objects: &[String],
+    net_cache: &[ClassNetCache],
+    frames: &[Frame],
+    registry: &Registry,
+) -> Result<EncodedFrames, NetworkError> {
+    let objects = objects.iter().map(|x| Cow::Borrowed(x.as_str())).collect();
+    let decoder = build_frame_decoder(header, objects, net_cache, registry)?;
+    FrameEncoder::new(decoder).encode_frames(frames)
+}
+
+/// Constructs the lookup tables needed to decode the network data from the header and body
+fn frame_decoder<'a, S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    body: &ReplayBody<'a>,
+    options: DecodeOptions<'_>,
+) -> Result<FrameDecoder<'a>, NetworkError> {
+    let builtin = Registry::new();
+    let registry = options.registry.unwrap_or(&builtin);
+    // The object names are shared with the body, as cloning borrowed text doesn't allocate
+    let objects = body.objects.clone();
+    let mut decoder = build_frame_decoder(header, objects, &body.net_cache, registry)?;
+    if decoder.frames_len > body.network_data.len() {
+        return Err(NetworkError::TooManyFrames(
+            header.num_frames().unwrap_or(0),
+        ));
+    }
+
+    if let Some(filter) = options.filter {
+        apply_filter(&mut decoder, filter, registry);
+    }
+
+    decoder.trace = options.trace;
+
+    Ok(decoder)
+}
+
+/// Resolves which objects the filter keeps, so that the decoder only needs to index into them
+fn apply_filter(decoder: &mut FrameDecoder, filter: &NetworkFilter, registry: &Registry) {
+    if filter.filters_classes() {
+        let object_classes: HashMap<&str, &str> = registry.object_classes().into_iter().collect();
+        let wanted = decoder
+            .objects
+            .iter()
+            .map(|name| {
+                // Walk up from the class of the object through its ancestors
+                let mut class = object_classes
+                    .get(normalize_object(name))
+                    .copied()
+                    .unwrap_or(name);
+                let mut visited: Vec<&str> = Vec::new();
+                loop {
+                    if filter.has_class(class) {
+                        return true;
+                    }
+
+                    match registry.parent_class(class) {
+                        Some(parent) if !visited.contains(&parent) => {
+                            visited.push(parent);
+                            class = parent;
+                        }
+                        _ => return false,
+                    }
+                }
+            })
+            .collect();
+        decoder.actor_filter = Some(wanted);
+    }
+
+    if filter.filters_attributes() {
+        let wanted = decoder
+            .objects
+            .iter()
+            .map(|name| filter.has_attribute(name) || filter.has_attribute(normalize_object(name)))
+            .collect();
+        decoder.attribute_filter = Some(wanted);
+    }
+}
+
+/// Constructs the lookup tables shared by decoding and encoding the network data
+fn build_frame_decoder<'a, S: AsRef<str>, P: PropValue>(
+    header: &Header<S, P>,
+    objects: Vec<Cow<'a, str>>,
+    net_cache: &[ClassNetCache],
+    registry: &Registry,
+) -> Result<FrameDecoder<'a>, NetworkError> {
     let version = VersionTriplet(
         header.major_version,
         header.minor_version,
@@ -49,19 +216,16 @@ pub(crate) fn parse<'a>(
     );
 
     // Create a parallel vector where each object has it's name normalized
-    let normalized_objects: Vec<&str> = body.objects.iter().map(|x| normalize_object(x)).collect();
+    let normalized_objects: Vec<&str> = objects
+        .iter()
+        .map(|x| normalize_object(x.as_ref()))
+        .collect();
 
     // Create a parallel vector where we lookup how to decode an object's initial trajectory
     // when they spawn as a new actor
-    let spawns: Vec<SpawnTrajectory> = body
-        .objects
+    let spawns: Vec<SpawnTrajectory> = objects
         .iter()
-        .map(|x| {
-            SPAWN_STATS
-                .get(x.deref())
-                .cloned()
-                .unwrap_or(SpawnTrajectory::None)
-        })
+        .map(|x| registry.spawn(x.as_ref()).unwrap_or(SpawnTrajectory::None))
         .collect();
 
     // Create a map of an object's normalized name to a list of indices in the object
@@ -71,19 +235,18 @@ pub(crate) fn parse<'a>(
     for (i, name) in normalized_objects.iter().enumerate() {
         normalized_name_obj_ind
             .entry(*name)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(ObjectId(i as i32));
     }
 
     // Map each object's name to it's index
-    let name_obj_ind: HashMap<&str, Vec<ObjectId>> = body
-        .objects
+    let name_obj_ind: HashMap<&str, Vec<ObjectId>> = objects
         .iter()
         .map(|name| {
             (
-                name.deref(),
+                name.as_ref(),
                 normalized_name_obj_ind
-                    .get(name.deref())
+                    .get(name.as_ref())
                     .cloned()
                     .unwrap_or_default(),
             )
@@ -92,7 +255,7 @@ pub(crate) fn parse<'a>(
 
     let mut object_ind_attrs: FnvHashMap<ObjectId, FnvHashMap<StreamId, ObjectAttribute>> =
         Default::default();
-    for cache in &body.net_cache {
+    for cache in net_cache {
         let mut all_props: FnvHashMap<StreamId, ObjectAttribute> = cache
             .properties
             .iter()
@@ -100,9 +263,8 @@ pub(crate) fn parse<'a>(
                 let attr = normalized_objects
                     .get(x.object_ind as usize)
                     .map(|x| {
-                        ATTRIBUTES
-                            .get(x.deref())
-                            .cloned()
+                        registry
+                            .attribute(x)
                             .unwrap_or(AttributeTag::NotImplemented)
                     })
                     .ok_or(NetworkError::StreamTooLargeIndex(x.stream_id, x.object_ind))?;
@@ -121,12 +283,19 @@ pub(crate) fn parse<'a>(
         // We are going to recursively resolve an object's name to find their direct parent.
         // Parents have parents as well (etc), so we repeatedly walk up the chain picking up
         // attributes on parent objects until we reach an object with no parent (`Core.Object`)
-        let mut object_name: &str = &*body
-            .objects
+        let mut object_name: &str = objects
             .get(cache.object_ind as usize)
+            .map(|x| x.as_ref())
             .ok_or(NetworkError::ObjectIdOutOfRange(ObjectId(cache.object_ind)))?;
 
-        while let Some(parent_name) = PARENT_CLASSES.get(object_name) {
+        let mut ancestors: Vec<&str> = Vec::new();
+        while let Some(parent_name) = registry.parent_class(object_name) {
+            // The built-in hierarchy is acyclic, but one extended at runtime may not be
+            if ancestors.contains(&parent_name) {
+                return Err(NetworkError::ParentClassCycle(String::from(parent_name)));
+            }
+            ancestors.push(parent_name);
+
             had_parent = true;
             if let Some(parent_ids) = name_obj_ind.get(parent_name) {
                 for parent_id in parent_ids {
@@ -143,11 +312,7 @@ pub(crate) fn parse<'a>(
         // parent and a parent cache id is set, try and find this parent id and carry down
         // their props.
         if !had_parent && cache.parent_id != 0 {
-            if let Some(parent) = body
-                .net_cache
-                .iter()
-                .find(|x| x.cache_id == cache.parent_id)
-            {
+            if let Some(parent) = net_cache.iter().find(|x| x.cache_id == cache.parent_id) {
                 if let Some(parent_attrs) = object_ind_attrs.get(&ObjectId(parent.object_ind)) {
                     all_props.extend(parent_attrs.iter());
                 }
@@ -157,7 +322,7 @@ pub(crate) fn parse<'a>(
         object_ind_attrs.insert(ObjectId(cache.object_ind), all_props);
     }
 
-    for (obj, parent) in object_classes().iter() {
+    for (obj, parent) in registry.object_classes().iter() {
         // It's ok if an object class doesn't appear in our replay. For instance, basketball
         // objects don't appear in a soccer replay.
         if let Some(object_ids) = normalized_name_obj_ind.get(obj) {
@@ -182,21 +347,31 @@ pub(crate) fn parse<'a>(
     }
 
     let object_ind_attributes: FnvHashMap<ObjectId, CacheInfo> = object_ind_attrs
-        .iter()
+        .into_iter()
         .map(|(obj_id, attrs)| {
-            let id = *obj_id;
             let max = attrs.keys().map(|&x| i32::from(x)).max().unwrap_or(2) + 1;
             let max_bit_width = crate::bits::bit_width(max as u64);
-            Ok((
-                id,
+            (
+                obj_id,
                 CacheInfo {
                     max_prop_id: max as u32,
                     prop_id_bits: cmp::max(max_bit_width, 1) - 1,
                     attributes: attrs,
                 },
-            ))
+            )
+        })
+        .collect();
+
+    // Attributes with a decoder supplied at runtime are decoded by it instead of their tag
+    let custom_decoders = normalized_objects
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| {
+            registry
+                .decoder(x)
+                .map(|decoder| (ObjectId(i as i32), Arc::clone(decoder)))
         })
-        .collect::<Result<FnvHashMap<_, _>, NetworkError>>()?;
+        .collect();
 
     let product_decoder = ProductValueDecoder::create(version, &name_obj_ind);
 
@@ -204,33 +379,29 @@ pub(crate) fn parse<'a>(
     let max_channels = header.max_channels().unwrap_or(1023) as u32;
     let channel_width = crate::bits::bit_width(u64::from(max_channels)) - 1;
     let channel_bits = cmp::max(channel_width, 0);
-    let num_frames = header.num_frames();
     let is_lan = header.match_type().map(|x| x == "Lan").unwrap_or(false);
     let is_rl_223 = matches!(header.build_version(), Some(x) if x >= "221120.42953.406184");
 
-    if let Some(frame_len) = num_frames {
-        if frame_len as usize > body.network_data.len() {
-            return Err(NetworkError::TooManyFrames(frame_len));
-        }
+    // Replays without a frame count don't have any network data for us to decode
+    let frames_len = header.num_frames().map_or(0, |x| x as usize);
 
-        let frame_decoder = FrameDecoder {
-            frames_len: frame_len as usize,
-            product_decoder,
-            max_channels,
-            channel_bits,
-            body,
-            spawns: &spawns,
-            object_ind_attributes,
-            version,
-            is_lan,
-            is_rl_223,
-        };
-        Ok(NetworkFrames {
-            frames: frame_decoder.decode_frames()?,
-        })
-    } else {
-        Ok(NetworkFrames { frames: Vec::new() })
-    }
+    Ok(FrameDecoder {
+        frames_len,
+        product_decoder,
+        max_channels,
+        channel_bits,
+        objects,
+        spawns,
+        object_ind_attributes,
+        custom_decoders,
+        layouts: FnvHashMap::default(),
+        actor_filter: None,
+        attribute_filter: None,
+        trace: false,
+        version,
+        is_lan,
+        is_rl_223,
+    })
 }
 
 #[cfg(test)]
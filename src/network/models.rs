@@ -1,5 +1,9 @@
-use crate::{bits::RlBits, network::attributes::Attribute};
+use crate::{
+    bits::{BitWriter, RlBits},
+    network::attributes::Attribute,
+};
 use bitter::{BitReader, LittleEndianReader};
+use std::cmp::Ordering;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
@@ -17,6 +21,16 @@ impl Vector3f {
             z: (vec.z as f32) / 100.0,
         })
     }
+
+    /// Encodes the vector rounded to the nearest hundredth
+    pub(crate) fn encode(&self, bits: &mut BitWriter, net_version: i32) -> Option<()> {
+        let vec = Vector3i {
+            x: (f64::from(self.x) * 100.0).round() as i32,
+            y: (f64::from(self.y) * 100.0).round() as i32,
+            z: (f64::from(self.z) * 100.0).round() as i32,
+        };
+        vec.encode(bits, net_version)
+    }
 }
 
 /// An object's current vector
@@ -73,6 +87,40 @@ impl Vector3i {
             })
         }
     }
+
+    /// Encodes the vector with the fewest number of bits that can represent each component.
+    /// Returns `None` when a component is too large to be encoded.
+    pub(crate) fn encode(&self, bits: &mut BitWriter, net_version: i32) -> Option<()> {
+        let max_bits = if net_version >= 7 { 22 } else { 20 };
+        let largest = self
+            .x
+            .unsigned_abs()
+            .max(self.y.unsigned_abs())
+            .max(self.z.unsigned_abs());
+        let width = 32 - largest.leading_zeros();
+        let size_bits = width.max(1) - 1;
+
+        // The most negative value of the largest size doesn't fit in the size deduced from the
+        // magnitude
+        let size_bits = if size_bits < max_bits {
+            size_bits
+        } else if [self.x, self.y, self.z]
+            .iter()
+            .all(|&v| v >= -(1 << max_bits) && v < (1 << max_bits))
+        {
+            max_bits - 1
+        } else {
+            return None;
+        };
+
+        let bias = 1i64 << (size_bits + 1);
+        let bit_limit = size_bits + 2;
+        bits.write_bits_max_computed(4, u64::from(max_bits), u64::from(size_bits));
+        for &val in &[self.x, self.y, self.z] {
+            bits.write_bits(bit_limit, (i64::from(val) + bias) as u64);
+        }
+        Some(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
@@ -93,6 +141,27 @@ impl Quaternion {
         range * max_quat
     }
 
+    /// The inverse of `unpack`. Returns the packed value when it unpacks to exactly the given
+    /// component.
+    #[inline]
+    fn pack(val: f32) -> Result<u32, u32> {
+        let max_quat = 1.0 / std::f32::consts::SQRT_2;
+        let max_value = (1 << 18) - 1;
+        let pos_range = (val / max_quat) / 2.0 + 0.5;
+        let guess = (pos_range * (max_value as f32))
+            .round()
+            .clamp(0.0, max_value as f32) as u32;
+        let lo = guess.saturating_sub(1);
+        let hi = (guess + 1).min(max_value);
+        (lo..=hi)
+            .find(|&x| Quaternion::unpack(x) == val)
+            .ok_or(guess)
+    }
+
+    fn components(&self) -> [f32; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+
     #[inline]
     fn compressed_f32(bits: &mut LittleEndianReader<'_>) -> f32 {
         // algorithm from jjbott/RocketLeagueReplayParser.
@@ -109,7 +178,20 @@ impl Quaternion {
         //
         // Bakkes copied jjbott. Rattletrap is more in line here
         let res = bits.peek_and_consume(16) as i32;
-        ((res + i32::from(std::i16::MIN)) as f32) * (std::i16::MAX as f32).recip()
+        ((res + i32::from(i16::MIN)) as f32) * (i16::MAX as f32).recip()
+    }
+
+    #[inline]
+    fn encode_compressed_f32(bits: &mut BitWriter, val: f32) {
+        let res = (val * f32::from(i16::MAX)).round() as i32 - i32::from(i16::MIN);
+        bits.write_bits(16, res.clamp(0, i32::from(u16::MAX)) as u64);
+    }
+
+    /// Encodes the x, y, and z components with 16 bits each. The w component is not encoded.
+    pub(crate) fn encode_compressed(&self, bits: &mut BitWriter) {
+        Quaternion::encode_compressed_f32(bits, self.x);
+        Quaternion::encode_compressed_f32(bits, self.y);
+        Quaternion::encode_compressed_f32(bits, self.z);
     }
 
     pub fn decode_compressed(bits: &mut LittleEndianReader<'_>) -> Option<Self> {
@@ -163,6 +245,54 @@ impl Quaternion {
             _ => unreachable!(),
         }
     }
+
+    /// Encodes the quaternion as the index of the component omitted from the stream and the three
+    /// remaining components with 18 bits each. A decoded quaternion can have any of its components
+    /// omitted, so the component that will be recomputed exactly is preferred, and the largest
+    /// component is used otherwise. A NaN component can only come from being recomputed (the
+    /// remaining components were too large for a unit quaternion), so it is always omitted.
+    pub(crate) fn encode(&self, bits: &mut BitWriter) {
+        let comps = self.components();
+        let mut order = [0usize, 1, 2, 3];
+        order.sort_by(|&a, &b| {
+            comps[b].is_nan().cmp(&comps[a].is_nan()).then_with(|| {
+                comps[b]
+                    .abs()
+                    .partial_cmp(&comps[a].abs())
+                    .unwrap_or(Ordering::Equal)
+            })
+        });
+
+        let exact = order.iter().find_map(|&largest| {
+            let mut packed = [0u32; 3];
+            let mut rest = (0..4).filter(|&i| i != largest);
+            for p in packed.iter_mut() {
+                *p = Quaternion::pack(comps[rest.next()?]).ok()?;
+            }
+
+            let [a, b, c] = packed.map(Quaternion::unpack);
+            let extra = (1.0 - (a * a) - (b * b) - (c * c)).sqrt();
+            let same = extra == comps[largest] || (extra.is_nan() && comps[largest].is_nan());
+            Some((largest, packed)).filter(|_| same)
+        });
+
+        let (largest, packed) = exact.unwrap_or_else(|| {
+            let largest = order[0];
+            let sign = if comps[largest] < 0.0 { -1.0 } else { 1.0 };
+            let mut packed = [0u32; 3];
+            let mut rest = (0..4).filter(|&i| i != largest);
+            for p in packed.iter_mut() {
+                let val = comps[rest.next().unwrap()] * sign;
+                *p = Quaternion::pack(val).unwrap_or_else(|x| x);
+            }
+            (largest, packed)
+        });
+
+        bits.write_bits(2, largest as u64);
+        for p in &packed {
+            bits.write_bits(18, u64::from(*p));
+        }
+    }
 }
 
 /// An object's current rotation
@@ -203,11 +333,20 @@ impl Rotation {
             Some(Rotation { yaw, pitch, roll })
         }
     }
+
+    pub(crate) fn encode(&self, bits: &mut BitWriter) {
+        for val in &[self.yaw, self.pitch, self.roll] {
+            bits.write_bit(val.is_some());
+            if let Some(x) = val {
+                bits.write_i8(*x);
+            }
+        }
+    }
 }
 
 /// When a new actor spawns in rocket league it will either have a location, location and rotation,
 /// or none of the above
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum SpawnTrajectory {
     None,
     Location,
@@ -359,6 +498,32 @@ impl Trajectory {
             }
         }
     }
+
+    /// Encodes the components of the trajectory that the spawn trajectory dictates. Missing
+    /// components are encoded as zero.
+    pub(crate) fn encode(
+        &self,
+        bits: &mut BitWriter,
+        sp: SpawnTrajectory,
+        net_version: i32,
+    ) -> Option<()> {
+        let location = self.location.unwrap_or(Vector3i { x: 0, y: 0, z: 0 });
+        let rotation = self.rotation.unwrap_or(Rotation {
+            yaw: None,
+            pitch: None,
+            roll: None,
+        });
+
+        match sp {
+            SpawnTrajectory::None => Some(()),
+            SpawnTrajectory::Location => location.encode(bits, net_version),
+            SpawnTrajectory::LocationAndRotation => {
+                location.encode(bits, net_version)?;
+                rotation.encode(bits);
+                Some(())
+            }
+        }
+    }
 }
 
 /// Oftentimes a replay contains many different objects of the same type. For instance, each rumble
@@ -407,4 +572,140 @@ mod tests {
             }
         );
     }
+
+    fn encode_vector(vec: &Vector3i, net_version: i32) -> Option<Vector3i> {
+        let mut writer = BitWriter::new();
+        vec.encode(&mut writer, net_version)?;
+        let data = writer.into_inner();
+        Vector3i::decode(&mut LittleEndianReader::new(&data), net_version)
+    }
+
+    #[test]
+    fn test_encode_vector() {
+        for &net_version in &[5, 7] {
+            let max = if net_version >= 7 { 1 << 22 } else { 1 << 20 };
+            let edges = [0, 1, -1, 93, -93, max - 1, -max];
+            for &x in &edges {
+                for &y in &edges {
+                    let vec = Vector3i { x, y, z: 1 };
+                    assert_eq!(encode_vector(&vec, net_version), Some(vec));
+                }
+            }
+
+            let vec = Vector3i { x: max, y: 0, z: 0 };
+            assert_eq!(encode_vector(&vec, net_version), None);
+            let vec = Vector3i {
+                x: 0,
+                y: -max - 1,
+                z: 0,
+            };
+            assert_eq!(encode_vector(&vec, net_version), None);
+        }
+    }
+
+    #[test]
+    fn test_encode_vector_bits() {
+        let mut writer = BitWriter::new();
+        Vector3i { x: 0, y: 0, z: 93 }
+            .encode(&mut writer, 5)
+            .unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            vec![0b0000_0110, 0b0000_1000, 0b1101_1000, 0b0000_1101]
+        );
+    }
+
+    #[test]
+    fn test_encode_float_vector() {
+        let vec = Vector3f {
+            x: -41943.04,
+            y: 41943.03,
+            z: 0.93,
+        };
+        let mut writer = BitWriter::new();
+        vec.encode(&mut writer, 7).unwrap();
+        let data = writer.into_inner();
+        let decoded = Vector3f::decode(&mut LittleEndianReader::new(&data), 7);
+        assert_eq!(decoded, Some(vec));
+    }
+
+    #[test]
+    fn test_pack_quaternion_component() {
+        for val in 0..(1 << 18) {
+            assert_eq!(Quaternion::pack(Quaternion::unpack(val)), Ok(val));
+        }
+    }
+
+    #[test]
+    fn test_encode_compressed_quaternion() {
+        for val in 0..=u16::MAX {
+            let mut writer = BitWriter::new();
+            writer.write_bits(16, u64::from(val));
+            writer.write_bits(32, 0x8000_8000);
+            let data = writer.into_inner();
+            let quat = Quaternion::decode_compressed(&mut LittleEndianReader::new(&data)).unwrap();
+
+            let mut writer = BitWriter::new();
+            quat.encode_compressed(&mut writer);
+            assert_eq!(writer.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn test_encode_quaternion() {
+        for &(largest, a, b, c) in &[
+            (0, 131_072, 131_072, 131_072),
+            (1, 131_071, 131_072, 100_000),
+            (2, 200_000, 100_000, 150_000),
+            (3, 131_071, 131_071, 131_071),
+        ] {
+            let mut writer = BitWriter::new();
+            writer.write_bits(2, largest);
+            writer.write_bits(18, a);
+            writer.write_bits(18, b);
+            writer.write_bits(18, c);
+            let data = writer.into_inner();
+            let quat = Quaternion::decode(&mut LittleEndianReader::new(&data)).unwrap();
+
+            let mut writer = BitWriter::new();
+            quat.encode(&mut writer);
+            let decoded = Quaternion::decode(&mut LittleEndianReader::new(&writer.into_inner()));
+            assert_eq!(decoded, Some(quat));
+        }
+    }
+
+    #[test]
+    fn test_encode_nan_quaternion() {
+        // The remaining components are too large for the omitted one to be recomputed
+        for largest in 0..4 {
+            let mut writer = BitWriter::new();
+            writer.write_bits(2, largest);
+            writer.write_bits(18, 10);
+            writer.write_bits(18, 262_000);
+            writer.write_bits(18, 5);
+            let data = writer.into_inner();
+            let quat = Quaternion::decode(&mut LittleEndianReader::new(&data)).unwrap();
+            assert!(quat.components()[largest as usize].is_nan());
+
+            let mut writer = BitWriter::new();
+            quat.encode(&mut writer);
+            assert_eq!(writer.into_inner(), data);
+        }
+    }
+
+    #[test]
+    fn test_encode_rotation() {
+        let rot = Rotation {
+            yaw: Some(2),
+            pitch: None,
+            roll: Some(-128),
+        };
+        let mut writer = BitWriter::new();
+        rot.encode(&mut writer);
+        let data = writer.into_inner();
+        assert_eq!(
+            Rotation::decode(&mut LittleEndianReader::new(&data)),
+            Some(rot)
+        );
+    }
 }
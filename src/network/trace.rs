@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+/// An element of the network data that was decoded while tracing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The index of the frame the element is in
+    pub frame: usize,
+
+    /// Bit offset into the network data where the element starts
+    pub bit_offset: usize,
+
+    /// The number of bits the element spans
+    pub bit_len: usize,
+
+    /// What the element is (eg: "Actor id", "Stream id", or the name of an attribute)
+    pub component: String,
+
+    /// The decoded value of the element
+    pub value: String,
+}
+
+/// The elements decoded in the frames leading up to a failure, alongside the network data they
+/// were decoded from. Returned in the `FrameContext` of an error when the network data is parsed
+/// with `ParserBuilder::trace_network`.
+///
+/// Attributes are traced as a single element, whose value lists each field of the attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkTrace {
+    /// The elements decoded in the frame prior to the failure and the failed frame
+    pub entries: Vec<TraceEntry>,
+
+    /// Bit offset into the network data of the first bit of `data`
+    pub data_offset: usize,
+
+    /// The network data from the first traced element until shortly past the failure
+    pub data: Vec<u8>,
+}
+
+impl NetworkTrace {
+    /// The number of bits past the failure that are kept in the trace
+    pub(crate) const TRAILING_BITS: usize = 256;
+
+    /// Renders the traced elements that ended within `window` bits of the given bit offset,
+    /// followed by a listing of the bits from the offset onwards. Each element is listed with its
+    /// offset, length, component, value, and bits. Bits are listed in the order they are read,
+    /// which is least significant bit first within each byte.
+    ///
+    /// ```
+    /// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+    /// let mut registry = boxcars::Registry::new();
+    /// registry.add_attribute("TAGame.Car_TA:TeamPaint", boxcars::AttributeTag::NotImplemented);
+    /// let err = boxcars::ParserBuilder::new(&data[..])
+    ///     .with_registry(&registry)
+    ///     .trace_network()
+    ///     .must_parse_network_data()
+    ///     .parse()
+    ///     .unwrap_err();
+    ///
+    /// let listing = match err {
+    ///     boxcars::ParseError::NetworkError(e, _) => match *e {
+    ///         boxcars::NetworkError::FrameError(_, context) => {
+    ///             let trace = context.trace.unwrap();
+    ///             trace.listing(context.bit_offset, 512)
+    ///         }
+    ///         _ => panic!("expected a frame error"),
+    ///     },
+    ///     _ => panic!("expected a network error"),
+    /// };
+    ///
+    /// assert!(listing.contains("Stream id"));
+    /// ```
+    pub fn listing(&self, bit_offset: usize, window: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:>10} {:>6}  {:<40} {:<40} bits",
+            "offset", "len", "component", "value"
+        );
+
+        let start = bit_offset.saturating_sub(window);
+        for entry in self
+            .entries
+            .iter()
+            .filter(|x| x.bit_offset + x.bit_len >= start && x.bit_offset <= bit_offset)
+        {
+            let _ = writeln!(
+                out,
+                "{:>10} {:>6}  {:<40} {:<40} {}",
+                entry.bit_offset,
+                entry.bit_len,
+                truncate(&entry.component, 40),
+                truncate(&entry.value, 40),
+                self.bits(entry.bit_offset, entry.bit_len, 64)
+            );
+        }
+
+        let _ = writeln!(out, "failure at bit {}:", bit_offset);
+        let end = self.data_offset + self.data.len() * 8;
+        let mut offset = bit_offset;
+        while offset < end {
+            let len = (end - offset).min(64);
+            let _ = writeln!(
+                out,
+                "{:>10} {:>6}  {}  {}",
+                offset,
+                len,
+                self.bits(offset, len, 64),
+                self.hex(offset, len)
+            );
+            offset += len;
+        }
+
+        out
+    }
+
+    fn bit(&self, offset: usize) -> Option<bool> {
+        let index = offset.checked_sub(self.data_offset)?;
+        let byte = self.data.get(index / 8)?;
+        Some(byte >> (index % 8) & 1 == 1)
+    }
+
+    /// Lists the bits in groups of eight, eliding those past the limit
+    fn bits(&self, offset: usize, len: usize, limit: usize) -> String {
+        let mut out = String::new();
+        for i in 0..len.min(limit) {
+            if i > 0 && i % 8 == 0 {
+                out.push(' ');
+            }
+
+            out.push(match self.bit(offset + i) {
+                Some(true) => '1',
+                Some(false) => '0',
+                None => '?',
+            });
+        }
+
+        if len > limit {
+            out.push_str(" ...");
+        }
+        out
+    }
+
+    /// Lists the bytes that start at each multiple of eight bits from the offset
+    fn hex(&self, offset: usize, len: usize) -> String {
+        let mut out = String::new();
+        for start in (offset..offset + len).step_by(8) {
+            let byte = (0..8)
+                .take_while(|i| start + i < offset + len)
+                .filter(|i| self.bit(start + i) == Some(true))
+                .fold(0u8, |acc, i| acc | 1 << i);
+            let _ = write!(out, "{:02x} ", byte);
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        String::from(text)
+    } else {
+        let mut result: String = text.chars().take(max - 3).collect();
+        result.push_str("...");
+        result
+    }
+}
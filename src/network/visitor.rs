@@ -0,0 +1,93 @@
+use crate::network::attributes::Attribute;
+use crate::network::models::{ActorId, NewActor, ObjectId, UpdatedAttribute};
+
+/// Receives the network data as it is decoded, as an alternative to collecting it into frames.
+/// Each frame starts with `on_frame_start` and ends with `on_frame_end`, and in between, the new
+/// actors, deleted actors, and attribute updates are received in the order they were replicated.
+/// Nothing is retained once a hook returns, so a visitor only pays for what it keeps.
+///
+/// All hooks default to doing nothing, so a visitor only needs to implement the hooks it is
+/// interested in.
+///
+/// ```
+/// use boxcars::{NetworkVisitor, NewActor, ParserBuilder};
+///
+/// #[derive(Default)]
+/// struct Counter {
+///     frames: usize,
+///     actors: usize,
+/// }
+///
+/// impl NetworkVisitor for Counter {
+///     fn on_new_actor(&mut self, _actor: &NewActor) {
+///         self.actors += 1;
+///     }
+///
+///     fn on_frame_end(&mut self) {
+///         self.frames += 1;
+///     }
+/// }
+///
+/// let data = include_bytes!("../../assets/replays/good/rumble.replay");
+/// let (replay, mut frames) = ParserBuilder::new(&data[..]).frames().unwrap();
+///
+/// let mut counter = Counter::default();
+/// frames.visit(&mut counter).unwrap();
+/// assert_eq!(Some(counter.frames as i32), replay.header().num_frames);
+/// assert!(counter.actors > 0);
+/// ```
+pub trait NetworkVisitor {
+    /// A frame with the given time and delta is being decoded
+    fn on_frame_start(&mut self, _time: f32, _delta: f32) {}
+
+    /// An actor was spawned
+    fn on_new_actor(&mut self, _actor: &NewActor) {}
+
+    /// An actor was destroyed
+    fn on_actor_deleted(&mut self, _actor: ActorId) {}
+
+    /// An attribute (identified by its object id) of the actor was updated
+    fn on_attribute(&mut self, _actor: ActorId, _object_id: ObjectId, _attribute: &Attribute) {}
+
+    /// The frame finished decoding
+    fn on_frame_end(&mut self) {}
+}
+
+/// Where the frame decoder sends the data it decodes. Decoded data is handed over by value so
+/// that collecting it into frames doesn't need to clone it.
+pub(crate) trait FrameSink {
+    fn frame_start(&mut self, time: f32, delta: f32);
+    fn new_actor(&mut self, actor: NewActor);
+    fn actor_deleted(&mut self, actor: ActorId);
+    fn attribute(&mut self, attribute: UpdatedAttribute);
+    fn frame_end(&mut self);
+}
+
+/// Forwards decoded data to a visitor and then drops it
+pub(crate) struct VisitorSink<'a, V: ?Sized>(pub &'a mut V);
+
+impl<'a, V: NetworkVisitor + ?Sized> FrameSink for VisitorSink<'a, V> {
+    fn frame_start(&mut self, time: f32, delta: f32) {
+        self.0.on_frame_start(time, delta)
+    }
+
+    fn new_actor(&mut self, actor: NewActor) {
+        self.0.on_new_actor(&actor)
+    }
+
+    fn actor_deleted(&mut self, actor: ActorId) {
+        self.0.on_actor_deleted(actor)
+    }
+
+    fn attribute(&mut self, attribute: UpdatedAttribute) {
+        self.0.on_attribute(
+            attribute.actor_id,
+            attribute.object_id,
+            &attribute.attribute,
+        )
+    }
+
+    fn frame_end(&mut self) {
+        self.0.on_frame_end()
+    }
+}
@@ -9,25 +9,25 @@
 //!
 //! - First 32 bits: the number of bytes that comprises the header data
 //! - Second 32 bits: the [cyclic redundancy check
-//! (CRC)](https://en.wikipedia.org/wiki/Cyclic_redundancy_check) (the checksum to ensure the
-//! replay isn't corrupt). It should be unsigned.
+//!   (CRC)](https://en.wikipedia.org/wiki/Cyclic_redundancy_check) (the checksum to ensure the
+//!   replay isn't corrupt). It should be unsigned.
 //! - Now we arrive at the header data
 //! - Third 32 bits: the replay major version (it'll be something like 868)
 //! - Fourth 32 bits: the replay minor version (it'll be something like 20)
 //! - Fifth 32 bits:  the replay network version (very old replays won't have this, you'll need
-//! to check that the major _version > 865 and minor_version > 17.
+//!   to check that the major _version > 865 and minor_version > 17.
 //!
 //! Now we get to where the game type is encoded as a string. Below is the formula for decoding a
 //! string.
 //!
 //! - The size of text as a 32bit integer
 //!   - If the size is positive, we're dealing with a windows-1252 encoding, so we don't need to do
-//!   anything to get the number of bytes that the string consumes (as windows-1252 is a 8bit
-//!   encoding).
+//!     anything to get the number of bytes that the string consumes (as windows-1252 is a 8bit
+//!     encoding).
 //!   - If the size is negative, the string is encoded with UTF-16, so multiply it by -2 to get the
-//!   number of bytes needed to read the string.
+//!     number of bytes needed to read the string.
 //! - Consume the number of bytes determined, but drop the last letter (1 byte for windows-1252, 2
-//! for UTF-16) as this will be a null character which we don't want.
+//!   for UTF-16) as this will be a null character which we don't want.
 //!
 //! ### Header Properties
 //!
@@ -79,7 +79,9 @@
 //! - Names: a string list
 //! - List of class indices: (class: string, index: 32 bit integer)
 //! - List of network attribute encodings: (object_ind: 32 bit integer, parent_id: 32 bit integer,
-//! cache_id: 32 bit integer, properties: a list of 32bit pairs (object_ind and stream_id))
+//!   cache_id: 32 bit integer, properties: a list of 32bit pairs (object_ind and stream_id))
+//! - Replays with a network version of 10 or greater end with 32 bits of unknown data (only ever
+//!   seen as zero)
 //!
 //! ## Network Body
 //!
@@ -189,16 +191,25 @@
 //! attribute type of a new attribute introduced in a released patch. Basically the recommendation
 //! is to look at the source code. Attribute parsing reuses all the concepts we've gone over
 //!
+//! Some of the guesswork can be automated with `FrameIterator::guess_attribute`, which tries every
+//! attribute type in place of an attribute that failed to decode and reports which ones allow the
+//! network data that follows to decode.
+//!
 //! The only thing left is the other branch when the "actor is alive" bit is off. This means that
 //! the actor is deleted and that the given actor id can be recycled.
 
 use crate::core_parser::CoreParser;
 use crate::crc::calc_crc;
-use crate::errors::{NetworkError, ParseError};
-use crate::header::{self, Header};
+use crate::errors::{NetworkError, ParseError, ReadError};
+use crate::filter::NetworkFilter;
+use crate::header::{self, BorrowedHeader};
 use crate::models::*;
-use crate::network;
+use crate::network::{self, DecodeOptions, FrameIterator};
 use crate::parsing_utils::{le_f32, le_i32};
+use crate::reader::read_section;
+use crate::registry::Registry;
+use std::borrow::Cow;
+use std::io::Read;
 
 /// Determines under what circumstances the parser should perform the crc check for replay
 /// corruption. Since the crc check is the most time consuming part when parsing the header,
@@ -232,8 +243,14 @@ pub enum NetworkParse {
     Never,
 
     /// Attempt to parse the network data, but if unsuccessful ignore the error
-    /// and continue parsing
+    /// and continue parsing. The frames decoded prior to the error are kept and
+    /// marked as truncated.
     IgnoreOnError,
+
+    /// Parse as much of the network data as possible. A frame that fails to decode is recorded in
+    /// the network frames' failures, and parsing resumes at the next keyframe. The network data is
+    /// only absent when decoding can't start at all.
+    BestEffort,
 }
 
 /// The main entry point to parsing replays in boxcars. Allows one to customize parsing options,
@@ -243,6 +260,7 @@ pub struct ParserBuilder<'a> {
     data: &'a [u8],
     crc_check: Option<CrcCheck>,
     network_parse: Option<NetworkParse>,
+    decode_options: DecodeOptions<'a>,
 }
 
 impl<'a> ParserBuilder<'a> {
@@ -251,6 +269,7 @@ impl<'a> ParserBuilder<'a> {
             data,
             crc_check: None,
             network_parse: None,
+            decode_options: DecodeOptions::default(),
         }
     }
 
@@ -289,54 +308,263 @@ impl<'a> ParserBuilder<'a> {
         self
     }
 
+    pub fn best_effort_network_data(mut self) -> ParserBuilder<'a> {
+        self.network_parse = Some(NetworkParse::BestEffort);
+        self
+    }
+
     pub fn with_network_parse(mut self, parse: NetworkParse) -> ParserBuilder<'a> {
         self.network_parse = Some(parse);
         self
     }
 
+    /// Decodes the network data with the given registry instead of only the built-in tables
+    pub fn with_registry(mut self, registry: &'a Registry) -> ParserBuilder<'a> {
+        self.decode_options.registry = Some(registry);
+        self
+    }
+
+    /// Only keeps the actors and attributes selected by the filter in the decoded network data
+    pub fn network_filter(mut self, filter: &'a NetworkFilter) -> ParserBuilder<'a> {
+        self.decode_options.filter = Some(filter);
+        self
+    }
+
+    /// Traces each element decoded from the network data, so that a frame that fails to decode
+    /// has a `NetworkTrace` in its error context. Tracing slows down decoding considerably, so
+    /// it is meant for working out the layout of data that fails to decode.
+    pub fn trace_network(mut self) -> ParserBuilder<'a> {
+        self.decode_options.trace = true;
+        self
+    }
+
     pub fn parse(self) -> Result<Replay, ParseError> {
+        self.parse_borrowed().map(BorrowedReplay::into_owned)
+    }
+
+    /// Parses the replay like `parse`, but text is borrowed from the replay data where possible
+    /// instead of allocated, which is useful when parsing many replays.
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// let data = include_bytes!("../assets/replays/good/rumble.replay");
+    /// let replay = boxcars::ParserBuilder::new(&data[..])
+    ///     .never_parse_network_data()
+    ///     .parse_borrowed()
+    ///     .unwrap();
+    ///
+    /// assert!(replay.objects.iter().all(|x| matches!(x, Cow::Borrowed(_))));
+    /// assert!(replay.properties.iter().all(|(key, _)| matches!(key, Cow::Borrowed(_))));
+    /// let replay = replay.into_owned();
+    /// assert_eq!(replay.objects[0], "Core.Object");
+    /// ```
+    pub fn parse_borrowed(self) -> Result<BorrowedReplay<'a>, ParseError> {
         let mut parser = Parser::new(
             self.data,
             self.crc_check.unwrap_or(CrcCheck::OnError),
             self.network_parse.unwrap_or(NetworkParse::IgnoreOnError),
+            self.decode_options,
         );
         parser.parse()
     }
+
+    /// Parses the header and body, but instead of decoding all the network data up front, returns
+    /// an iterator that decodes the network data one frame at a time. This allows replays to be
+    /// processed in constant memory and for processing to stop early. The returned replay will
+    /// not contain any network frames and the network parse option is ignored.
+    ///
+    /// ```
+    /// let data = include_bytes!("../assets/replays/good/rumble.replay");
+    /// let (replay, frames) = boxcars::ParserBuilder::new(&data[..])
+    ///     .frames()
+    ///     .unwrap();
+    ///
+    /// assert!(replay.network_frames.is_none());
+    /// let first_goal_frame = replay.tick_marks[0].frame as usize;
+    /// let frames = frames.take(first_goal_frame).collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(frames.len(), first_goal_frame);
+    /// ```
+    pub fn frames(self) -> Result<(Replay, FrameIterator<'a>), ParseError> {
+        let mut parser = Parser::new(
+            self.data,
+            self.crc_check.unwrap_or(CrcCheck::OnError),
+            NetworkParse::Never,
+            self.decode_options,
+        );
+        parser.parse_frames()
+    }
+
+    /// Parses only the header section, which holds the versions and header properties, and
+    /// stops before the body. Only the crc of the header is checked.
+    ///
+    /// ```
+    /// let data = include_bytes!("../assets/replays/good/rumble.replay");
+    /// let section = boxcars::ParserBuilder::new(&data[..]).header_only().unwrap();
+    /// assert_eq!(section.major_version, 868);
+    /// assert_eq!(section.header().num_frames, Some(7744));
+    /// ```
+    pub fn header_only(self) -> Result<HeaderSection, ParseError> {
+        let mut parser = Parser::new(
+            self.data,
+            self.crc_check.unwrap_or(CrcCheck::OnError),
+            NetworkParse::Never,
+            self.decode_options,
+        );
+        parser.parse_header_section()
+    }
+}
+
+/// Reads and parses only the header section of a replay. Reading stops at the end of the header
+/// section, which is typically a few kilobytes into the replay, so the rest of the replay is
+/// never read. The crc of the header is checked only when parsing fails.
+///
+/// ```
+/// let file = std::fs::File::open("assets/replays/good/rumble.replay").unwrap();
+/// let section = boxcars::scan_header(std::io::BufReader::new(file)).unwrap();
+/// assert_eq!(section.header().goals.len(), 7);
+/// ```
+pub fn scan_header<R: Read>(mut reader: R) -> Result<HeaderSection, ReadError> {
+    let mut data = Vec::new();
+    read_section(&mut reader, &mut data)?;
+    Ok(ParserBuilder::new(&data).header_only()?)
 }
 
 /// Intermediate parsing structure for the body / footer
 #[derive(Debug, PartialEq)]
 pub struct ReplayBody<'a> {
-    pub levels: Vec<String>,
+    pub levels: Vec<Cow<'a, str>>,
     pub keyframes: Vec<KeyFrame>,
     pub debug_info: Vec<DebugInfo>,
     pub tick_marks: Vec<TickMark>,
-    pub packages: Vec<String>,
-    pub objects: Vec<String>,
-    pub names: Vec<String>,
-    pub class_indices: Vec<ClassIndex>,
+    pub packages: Vec<Cow<'a, str>>,
+    pub objects: Vec<Cow<'a, str>>,
+    pub names: Vec<Cow<'a, str>>,
+    pub class_indices: Vec<BorrowedClassIndex<'a>>,
     pub net_cache: Vec<ClassNetCache>,
     pub network_data: &'a [u8],
 }
 
+/// The header and body sections of a replay prior to decoding the network data
+#[derive(Debug, PartialEq)]
+struct ReplaySections<'a> {
+    header_size: i32,
+    header_crc: u32,
+    header: BorrowedHeader<'a>,
+    content_size: i32,
+    content_crc: u32,
+    body: ReplayBody<'a>,
+}
+
+impl<'a> ReplaySections<'a> {
+    fn into_replay(self, network_frames: Option<NetworkFrames>) -> Replay {
+        self.into_borrowed_replay(network_frames).into_owned()
+    }
+
+    fn into_borrowed_replay(self, network_frames: Option<NetworkFrames>) -> BorrowedReplay<'a> {
+        let header = self.header;
+        let body = self.body;
+        BorrowedReplay {
+            header_size: self.header_size,
+            header_crc: self.header_crc,
+            major_version: header.major_version,
+            minor_version: header.minor_version,
+            net_version: header.net_version,
+            game_type: header.game_type,
+            properties: header.properties,
+            content_size: self.content_size,
+            content_crc: self.content_crc,
+            network_frames,
+            levels: body.levels,
+            keyframes: body.keyframes,
+            debug_info: body.debug_info,
+            tick_marks: body.tick_marks,
+            packages: body.packages,
+            objects: body.objects,
+            names: body.names,
+            class_indices: body.class_indices,
+            net_cache: body.net_cache,
+        }
+    }
+}
+
 /// Holds the current state of parsing a replay
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parser<'a> {
     core: CoreParser<'a>,
     crc_check: CrcCheck,
     network_parse: NetworkParse,
+    decode_options: DecodeOptions<'a>,
 }
 
 impl<'a> Parser<'a> {
-    fn new(data: &'a [u8], crc_check: CrcCheck, network_parse: NetworkParse) -> Self {
+    fn new(
+        data: &'a [u8],
+        crc_check: CrcCheck,
+        network_parse: NetworkParse,
+        decode_options: DecodeOptions<'a>,
+    ) -> Self {
         Parser {
             core: CoreParser::new(data),
             crc_check,
             network_parse,
+            decode_options,
         }
     }
 
-    fn parse(&mut self) -> Result<Replay, ParseError> {
+    fn parse(&mut self) -> Result<BorrowedReplay<'a>, ParseError> {
+        let sections = self.parse_sections()?;
+        let result = match self.network_parse {
+            NetworkParse::Always | NetworkParse::IgnoreOnError => {
+                self.parse_network(&sections.header, &sections.body)
+            }
+            NetworkParse::BestEffort => {
+                network::parse_best_effort(&sections.header, &sections.body, self.decode_options)
+            }
+            NetworkParse::Never => return Ok(sections.into_borrowed_replay(None)),
+        };
+
+        match result {
+            Ok(network) => Ok(sections.into_borrowed_replay(Some(network))),
+            Err(e) => match self.network_parse {
+                // The frames that decoded prior to the failure stay in the error, where they
+                // describe the failure, until the replay is recovered from it
+                NetworkParse::Always => Err(ParseError::NetworkError(
+                    Box::new(e),
+                    Box::new(sections.into_replay(None)),
+                )),
+                _ => Ok(sections.into_borrowed_replay(network::truncated_frames(e).ok())),
+            },
+        }
+    }
+
+    fn parse_frames(&mut self) -> Result<(Replay, FrameIterator<'a>), ParseError> {
+        let sections = self.parse_sections()?;
+        match network::frame_iter(&sections.header, &sections.body, self.decode_options) {
+            Ok(frames) => Ok((sections.into_replay(None), frames)),
+            Err(e) => Err(ParseError::NetworkError(
+                Box::new(e),
+                Box::new(sections.into_replay(None)),
+            )),
+        }
+    }
+
+    fn parse_header_section(&mut self) -> Result<HeaderSection, ParseError> {
+        let (header_size, header_crc, header) = self.parse_header_data()?;
+        let header = header.into_owned();
+        Ok(HeaderSection {
+            header_size,
+            header_crc,
+            major_version: header.major_version,
+            minor_version: header.minor_version,
+            net_version: header.net_version,
+            game_type: header.game_type,
+            properties: header.properties,
+        })
+    }
+
+    /// Parses the header size, crc, and the header with its text borrowed from the replay data
+    fn parse_header_data(&mut self) -> Result<(i32, u32, BorrowedHeader<'a>), ParseError> {
         let header_size = self.core.take_i32("header size")?;
         let header_crc = self.core.take_u32("header crc")?;
 
@@ -344,8 +572,12 @@ impl<'a> Parser<'a> {
             ParseError::ParseError("header data", self.core.bytes_read(), Box::new(e))
         })?;
 
-        let header =
-            self.crc_section(header_data, header_crc as u32, "header", Self::parse_header)?;
+        let header = self.crc_section(header_data, header_crc, "header", Self::parse_header)?;
+        Ok((header_size, header_crc, header))
+    }
+
+    fn parse_sections(&mut self) -> Result<ReplaySections<'a>, ParseError> {
+        let (header_size, header_crc, header) = self.parse_header_data()?;
 
         let content_size = self.core.take_i32("content size")?;
         let content_crc = self.core.take_u32("content crc")?;
@@ -354,52 +586,27 @@ impl<'a> Parser<'a> {
             ParseError::ParseError("content data", self.core.bytes_read(), Box::new(e))
         })?;
 
-        let body = self.crc_section(content_data, content_crc as u32, "body", Self::parse_body)?;
-
-        let network: Option<NetworkFrames> = match self.network_parse {
-            NetworkParse::Always => Some(
-                self.parse_network(&header, &body)
-                    .map_err(|x| ParseError::NetworkError(Box::new(x)))?,
-            ),
-            NetworkParse::IgnoreOnError => self
-                .parse_network(&header, &body)
-                .map_err(|x| ParseError::NetworkError(Box::new(x)))
-                .ok(),
-            NetworkParse::Never => None,
-        };
+        let body = self.crc_section(content_data, content_crc, "body", Self::parse_body)?;
 
-        Ok(Replay {
+        Ok(ReplaySections {
             header_size,
             header_crc,
-            major_version: header.major_version,
-            minor_version: header.minor_version,
-            net_version: header.net_version,
-            game_type: header.game_type,
-            properties: header.properties,
+            header,
             content_size,
             content_crc,
-            network_frames: network,
-            levels: body.levels,
-            keyframes: body.keyframes,
-            debug_info: body.debug_info,
-            tick_marks: body.tick_marks,
-            packages: body.packages,
-            objects: body.objects,
-            names: body.names,
-            class_indices: body.class_indices,
-            net_cache: body.net_cache,
+            body,
         })
     }
 
     fn parse_network(
         &mut self,
-        header: &Header,
+        header: &BorrowedHeader<'_>,
         body: &ReplayBody<'_>,
     ) -> Result<NetworkFrames, NetworkError> {
-        network::parse(header, body)
+        network::parse(header, body, self.decode_options)
     }
 
-    fn parse_header(&mut self) -> Result<Header, ParseError> {
+    fn parse_header(&mut self) -> Result<BorrowedHeader<'a>, ParseError> {
         header::parse_header(&mut self.core)
     }
 
@@ -419,7 +626,7 @@ impl<'a> Parser<'a> {
         match self.crc_check {
             CrcCheck::Always => {
                 let actual = calc_crc(data);
-                if actual != crc as u32 {
+                if actual != crc {
                     Err(ParseError::CrcMismatch(crc, actual))
                 } else {
                     result
@@ -427,7 +634,7 @@ impl<'a> Parser<'a> {
             }
             CrcCheck::OnError => result.map_err(|e| -> ParseError {
                 let actual = calc_crc(data);
-                if actual != crc as u32 {
+                if actual != crc {
                     ParseError::CorruptReplay(String::from(section), Box::new(e))
                 } else {
                     e
@@ -525,10 +732,10 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_classindex(&mut self) -> Result<Vec<ClassIndex>, ParseError> {
+    fn parse_classindex(&mut self) -> Result<Vec<BorrowedClassIndex<'a>>, ParseError> {
         self.core.list_of(|s| {
-            Ok(ClassIndex {
-                class: s.parse_str().map(String::from)?,
+            Ok(BorrowedClassIndex {
+                class: s.parse_str().map(Cow::Borrowed)?,
                 index: s.take(4, le_i32)?,
             })
         })
@@ -554,7 +761,11 @@ impl<'a> Parser<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::errors::FrameError;
     use crate::models::TickMark;
+    use crate::network::attributes::AttributeTag;
+    use crate::network::AttributeLayout;
+    use crate::timeline::Timeline;
     use std::error::Error;
 
     #[test]
@@ -566,6 +777,7 @@ mod tests {
             &data[0x12ca..0x12ca + 508],
             CrcCheck::Never,
             NetworkParse::Never,
+            DecodeOptions::default(),
         );
         let frames = parser.parse_keyframe().unwrap();
         assert_eq!(frames.len(), 42);
@@ -580,6 +792,7 @@ mod tests {
             &data[0xf6cce..0xf6d50],
             CrcCheck::Never,
             NetworkParse::Never,
+            DecodeOptions::default(),
         );
         let ticks = parser.parse_tickmarks().unwrap();
 
@@ -595,21 +808,36 @@ mod tests {
 
     #[test]
     fn test_the_parsing_empty() {
-        let mut parser = Parser::new(&[], CrcCheck::Never, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &[],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         assert!(parser.parse().is_err());
     }
 
     #[test]
     fn test_the_parsing_text_too_long() {
         let data = include_bytes!("../assets/replays/bad/fuzz-string-too-long.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         assert!(parser.parse().is_err())
     }
 
     #[test]
     fn test_the_parsing_text_too_long2() {
         let data = include_bytes!("../assets/replays/bad/fuzz-string-too-long2.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Always);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Always,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert!(format!("{}", err).contains("Unexpected size for string: -1912602609"));
     }
@@ -617,21 +845,36 @@ mod tests {
     #[test]
     fn test_fuzz_corpus_slice_index() {
         let data = include_bytes!("../assets/replays/bad/fuzz-slice-index.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         assert!(parser.parse().is_err())
     }
 
     #[test]
     fn test_the_fuzz_corpus_abs_panic() {
         let data = include_bytes!("../assets/replays/bad/fuzz-corpus.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         assert!(parser.parse().is_err())
     }
 
     #[test]
     fn test_the_fuzz_corpus_large_list() {
         let data = include_bytes!("../assets/replays/bad/fuzz-list-too-large.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert!(format!("{}", err)
             .starts_with("Could not decode replay debug info at offset (1010894): list of size"));
@@ -640,7 +883,12 @@ mod tests {
     #[test]
     fn test_the_fuzz_corpus_large_list_on_error_crc() {
         let data = include_bytes!("../assets/replays/bad/fuzz-list-too-large.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::OnError, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::OnError,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert_eq!(
             "Failed to parse body and crc check failed. Replay is corrupt",
@@ -654,7 +902,12 @@ mod tests {
     #[test]
     fn test_the_fuzz_corpus_large_list_always_crc() {
         let data = include_bytes!("../assets/replays/bad/fuzz-list-too-large.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Always, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Always,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert_eq!(
             "Crc mismatch. Expected 3765941959 but received 1314727725",
@@ -666,7 +919,12 @@ mod tests {
     #[test]
     fn test_the_fuzz_object_id_too_large() {
         let data = include_bytes!("../assets/replays/bad/fuzz-large-object-id.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Always);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Always,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert_eq!("Object Id of 1547 exceeds range", format!("{}", err));
         assert!(err.source().is_some());
@@ -675,7 +933,12 @@ mod tests {
     #[test]
     fn test_the_fuzz_too_many_frames() {
         let data = include_bytes!("../assets/replays/bad/fuzz-too-many-frames.replay");
-        let mut parser = Parser::new(&data[..], CrcCheck::Never, NetworkParse::Always);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Always,
+            DecodeOptions::default(),
+        );
         let err = parser.parse().unwrap_err();
         assert_eq!("Too many frames to decode: 738197735", format!("{}", err));
         assert!(err.source().is_some());
@@ -687,7 +950,12 @@ mod tests {
 
         // Changing this byte won't make the parsing fail but will make the crc check fail
         data[4775] = 100;
-        let mut parser = Parser::new(&data[..], CrcCheck::Always, NetworkParse::Never);
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Always,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         let res = parser.parse();
         assert!(res.is_err());
         assert_eq!(
@@ -695,7 +963,138 @@ mod tests {
             format!("{}", res.unwrap_err())
         );
 
-        parser = Parser::new(&data[..], CrcCheck::OnError, NetworkParse::Never);
+        parser = Parser::new(
+            &data[..],
+            CrcCheck::OnError,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
         assert!(parser.parse().is_ok());
     }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let borrowed = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse_borrowed()
+            .unwrap();
+        assert!(borrowed
+            .names
+            .iter()
+            .chain(borrowed.objects.iter())
+            .all(|x| matches!(x, Cow::Borrowed(_))));
+        assert!(borrowed
+            .class_indices
+            .iter()
+            .all(|x| matches!(x.class, Cow::Borrowed(_))));
+        assert!(matches!(borrowed.game_type, Cow::Borrowed(_)));
+        assert!(borrowed.properties.iter().all(|(key, prop)| {
+            matches!(key, Cow::Borrowed(_))
+                && !matches!(
+                    prop,
+                    BorrowedHeaderProp::Str(Cow::Owned(_))
+                        | BorrowedHeaderProp::Name(Cow::Owned(_))
+                )
+        }));
+
+        let replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+        assert_eq!(borrowed.header(), replay.header());
+
+        // The borrowed objects can drive the same analyses as owned objects
+        let frames = &replay.network_frames.as_ref().unwrap().frames;
+        assert_eq!(
+            Timeline::from_frames(&borrowed.objects, frames),
+            Timeline::from_frames(&replay.objects, frames)
+        );
+        assert_eq!(borrowed.into_owned(), replay);
+    }
+
+    #[test]
+    fn test_guess_attribute_with_unknown_stream_id() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut parser = Parser::new(
+            &data[..],
+            CrcCheck::Never,
+            NetworkParse::Never,
+            DecodeOptions::default(),
+        );
+        let mut sections = parser.parse_sections().unwrap();
+
+        // Without a stream mapping, every throttle update of a car has an unknown stream id
+        let throttle = sections
+            .body
+            .objects
+            .iter()
+            .position(|x| x == "TAGame.Vehicle_TA:ReplicatedThrottle")
+            .unwrap() as i32;
+        for cache in &mut sections.body.net_cache {
+            cache.properties.retain(|x| x.object_ind != throttle);
+        }
+
+        let mut frames =
+            network::frame_iter(&sections.header, &sections.body, DecodeOptions::default())
+                .unwrap();
+        let err = frames.by_ref().find_map(|x| x.err()).unwrap();
+        assert!(matches!(
+            err,
+            NetworkError::FrameError(FrameError::MissingAttribute { .. }, _)
+        ));
+
+        // The throttle updates that follow are decoded with the guessed layout
+        let guesses = frames.guess_attribute(100).unwrap();
+        let best = &guesses[0];
+        assert_eq!(best.layout, AttributeLayout::Tag(AttributeTag::Byte));
+        assert!(best.consistent);
+        assert_eq!(best.updates, 100);
+    }
+
+    #[test]
+    fn test_header_only() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let replay = ParserBuilder::new(&data[..])
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+        let section = ParserBuilder::new(&data[..])
+            .always_check_crc()
+            .header_only()
+            .unwrap();
+
+        assert_eq!(section.header_size, replay.header_size);
+        assert_eq!(section.header_crc, replay.header_crc);
+        assert_eq!(section.major_version, replay.major_version);
+        assert_eq!(section.minor_version, replay.minor_version);
+        assert_eq!(section.net_version, replay.net_version);
+        assert_eq!(section.game_type, replay.game_type);
+        assert_eq!(section.properties, replay.properties);
+
+        // The body isn't needed
+        let header_len = 8 + section.header_size as usize;
+        let truncated = ParserBuilder::new(&data[..header_len]).header_only();
+        assert_eq!(truncated, Ok(section));
+    }
+
+    #[test]
+    fn test_scan_header_reads_only_header() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut reader = &data[..];
+        let section = scan_header(&mut reader).unwrap();
+        assert_eq!(reader.len(), data.len() - 8 - section.header_size as usize);
+        assert_eq!(Ok(section), ParserBuilder::new(&data[..]).header_only());
+
+        let err = scan_header(&data[..4]).unwrap_err();
+        assert!(
+            matches!(err, ReadError::Io(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+        );
+
+        let err = scan_header(&data[..100]).unwrap_err();
+        assert!(matches!(
+            err,
+            ReadError::Parse(ParseError::ParseError("header data", _, _))
+        ));
+    }
 }
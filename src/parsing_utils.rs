@@ -1,5 +1,6 @@
 use crate::errors::ParseError;
 use encoding_rs::{UTF_16LE, WINDOWS_1252};
+use std::borrow::Cow;
 use std::convert::TryInto;
 
 #[inline]
@@ -40,10 +41,15 @@ pub fn decode_utf16(input: &[u8]) -> Result<String, ParseError> {
 }
 
 pub fn decode_windows1252(input: &[u8]) -> Result<String, ParseError> {
+    decode_windows1252_borrowed(input).map(Cow::into_owned)
+}
+
+/// Decodes like `decode_windows1252`, but text that is ASCII is borrowed from the input
+pub fn decode_windows1252_borrowed(input: &[u8]) -> Result<Cow<'_, str>, ParseError> {
     if input.is_empty() {
         Err(ParseError::ZeroSize)
     } else {
         let (s, _) = WINDOWS_1252.decode_without_bom_handling(&input[..input.len() - 1]);
-        Ok(String::from(s))
+        Ok(s)
     }
 }
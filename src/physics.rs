@@ -0,0 +1,818 @@
+//! # Physics
+//!
+//! Rigid body updates are only sent when the state of an actor changes, so the network data is a
+//! sparse log of physics states. This module turns it into a dense, column oriented time series
+//! with a row for every frame.
+
+use crate::entities::EntityTracker;
+use crate::network::attributes::{Attribute, RigidBody};
+use crate::network::{ActorId, Frame, ObjectId, Quaternion, Vector3f};
+use crate::world::WorldActor;
+use fnv::FnvHashSet;
+
+const RIGID_BODY: &str = "TAGame.RBActor_TA:ReplicatedRBState";
+
+/// A column for each component of a vector. A missing vector is recorded as NaN components.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Vector3Columns {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+}
+
+impl Vector3Columns {
+    fn push(&mut self, vec: Option<Vector3f>) {
+        let vec = vec.unwrap_or(Vector3f {
+            x: f32::NAN,
+            y: f32::NAN,
+            z: f32::NAN,
+        });
+        self.x.push(vec.x);
+        self.y.push(vec.y);
+        self.z.push(vec.z);
+    }
+}
+
+/// A column for each component of a quaternion. A missing quaternion is recorded as NaN
+/// components.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct QuaternionColumns {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+    pub w: Vec<f32>,
+}
+
+impl QuaternionColumns {
+    fn push(&mut self, quat: Option<Quaternion>) {
+        let quat = quat.unwrap_or(Quaternion {
+            x: f32::NAN,
+            y: f32::NAN,
+            z: f32::NAN,
+            w: f32::NAN,
+        });
+        self.x.push(quat.x);
+        self.y.push(quat.y);
+        self.z.push(quat.z);
+        self.w.push(quat.w);
+    }
+}
+
+/// The rigid body state of an entity with a row for every frame
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct PhysicsSeries {
+    /// Whether the entity's actor was live in the frame
+    pub present: Vec<bool>,
+
+    /// The actor that the row was recorded from, as an entity may move between actors (eg: a
+    /// respawned car or a replaced ball)
+    pub actor_id: Vec<Option<ActorId>>,
+
+    /// Whether the actor was live but did not send a rigid body update in the frame, so the row
+    /// carries forward the last known state
+    pub inferred: Vec<bool>,
+
+    pub sleeping: Vec<bool>,
+    pub location: Vector3Columns,
+    pub rotation: QuaternionColumns,
+    pub linear_velocity: Vector3Columns,
+    pub angular_velocity: Vector3Columns,
+}
+
+impl PhysicsSeries {
+    /// The number of rows in the series
+    pub fn len(&self) -> usize {
+        self.present.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.present.is_empty()
+    }
+
+    /// The state of the entity at the given row, if the entity was live and its state was known
+    pub fn rigid_body(&self, row: usize) -> Option<RigidBody> {
+        if !self.present.get(row).copied().unwrap_or(false) || self.location.x[row].is_nan() {
+            return None;
+        }
+
+        let vector = |columns: &Vector3Columns| {
+            Some(Vector3f {
+                x: columns.x[row],
+                y: columns.y[row],
+                z: columns.z[row],
+            })
+            .filter(|x| !x.x.is_nan())
+        };
+
+        Some(RigidBody {
+            sleeping: self.sleeping[row],
+            location: vector(&self.location)?,
+            rotation: Quaternion {
+                x: self.rotation.x[row],
+                y: self.rotation.y[row],
+                z: self.rotation.z[row],
+                w: self.rotation.w[row],
+            },
+            linear_velocity: vector(&self.linear_velocity),
+            angular_velocity: vector(&self.angular_velocity),
+        })
+    }
+
+    /// The rows where the entity sent a rigid body update, given the time of each row. A sample
+    /// that follows rows where the entity was absent, or that is from a different actor than the
+    /// previous sample, is marked as discontinuous.
+    pub fn samples(&self, time: &[f32]) -> Vec<RigidBodySample> {
+        let mut result = Vec::new();
+        let mut discontinuous = false;
+        let mut actor_id = None;
+        for (row, &time) in time.iter().enumerate().take(self.len()) {
+            if !self.present[row] {
+                discontinuous = true;
+                continue;
+            }
+
+            if self.actor_id[row] != actor_id {
+                discontinuous |= !result.is_empty();
+                actor_id = self.actor_id[row];
+            }
+
+            if self.inferred[row] {
+                continue;
+            }
+
+            if let Some(body) = self.rigid_body(row) {
+                result.push(RigidBodySample {
+                    time,
+                    body,
+                    discontinuous,
+                });
+                discontinuous = false;
+            }
+        }
+
+        result
+    }
+
+    fn push(&mut self, actor_id: Option<ActorId>, inferred: bool, body: Option<&RigidBody>) {
+        self.present.push(actor_id.is_some());
+        self.actor_id.push(actor_id);
+        self.inferred.push(inferred);
+        self.sleeping.push(matches!(body, Some(x) if x.sleeping));
+        self.location.push(body.map(|x| x.location));
+        self.rotation.push(body.map(|x| x.rotation));
+        self.linear_velocity
+            .push(body.and_then(|x| x.linear_velocity));
+        self.angular_velocity
+            .push(body.and_then(|x| x.angular_velocity));
+    }
+
+    fn push_absent(&mut self) {
+        self.push(None, false, None);
+    }
+}
+
+/// The physics series of the car driven by a player. Cars that are respawned (eg: after a
+/// demolition) are joined into a single series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlayerPhysics {
+    /// The actor id of the player's replication info
+    pub actor_id: ActorId,
+
+    /// The last known name of the player
+    pub name: Option<String>,
+
+    pub car: PhysicsSeries,
+}
+
+/// Physics time series for the ball and each player's car, aligned to the time of each frame.
+///
+/// ```
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = &replay.network_frames.as_ref().unwrap().frames;
+/// let series = boxcars::physics::PhysicsTimeSeries::from_frames(&replay.objects, frames);
+/// assert_eq!(series.time.len(), frames.len());
+/// assert_eq!(series.ball.len(), frames.len());
+/// assert!(series.players.iter().all(|x| x.car.len() == frames.len()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct PhysicsTimeSeries {
+    /// The time of each frame
+    pub time: Vec<f32>,
+
+    /// The state of the live ball with the lowest actor id
+    pub ball: PhysicsSeries,
+
+    /// Players in the order they first appear
+    pub players: Vec<PlayerPhysics>,
+}
+
+impl PhysicsTimeSeries {
+    /// Builds the time series from all frames of a replay
+    pub fn from_frames<S: AsRef<str>>(objects: &[S], frames: &[Frame]) -> Self {
+        let rigid_body_ids: Vec<ObjectId> = objects
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.as_ref() == RIGID_BODY)
+            .map(|(i, _)| ObjectId(i as i32))
+            .collect();
+
+        let mut tracker = EntityTracker::new(objects);
+        let mut result = PhysicsTimeSeries {
+            time: Vec::with_capacity(frames.len()),
+            ..PhysicsTimeSeries::default()
+        };
+
+        for frame in frames {
+            tracker.apply(frame);
+            result.time.push(frame.time);
+            let rows = result.time.len();
+
+            let updated: FnvHashSet<ActorId> = frame
+                .updated_actors
+                .iter()
+                .filter(|x| rigid_body_ids.contains(&x.object_id))
+                .map(|x| x.actor_id)
+                .collect();
+
+            let world = tracker.world();
+            let record = |series: &mut PhysicsSeries, actor: Option<&WorldActor<'_>>| match actor {
+                Some(actor) => {
+                    let body =
+                        rigid_body_ids
+                            .iter()
+                            .find_map(|&id| match actor.attribute_by_id(id) {
+                                Some(Attribute::RigidBody(body)) => Some(body),
+                                _ => None,
+                            });
+                    series.push(
+                        Some(actor.actor_id),
+                        !updated.contains(&actor.actor_id),
+                        body,
+                    );
+                }
+                None => series.push_absent(),
+            };
+
+            let ball = tracker.ball().and_then(|x| world.actor(x.actor_id));
+            record(&mut result.ball, ball);
+
+            for player in tracker.players() {
+                let index = match result
+                    .players
+                    .iter()
+                    .position(|x| x.actor_id == player.actor_id)
+                {
+                    Some(index) => index,
+                    None => {
+                        let mut car = PhysicsSeries::default();
+                        for _ in 1..rows {
+                            car.push_absent();
+                        }
+
+                        result.players.push(PlayerPhysics {
+                            actor_id: player.actor_id,
+                            name: None,
+                            car,
+                        });
+                        result.players.len() - 1
+                    }
+                };
+
+                let entry = &mut result.players[index];
+                if let Some(name) = player.name {
+                    if entry.name.as_deref() != Some(name) {
+                        entry.name = Some(String::from(name));
+                    }
+                }
+
+                let car = player.car.and_then(|x| world.actor(x));
+                record(&mut entry.car, car);
+            }
+
+            // Players without replication info in this frame
+            for entry in &mut result.players {
+                if entry.car.len() < rows {
+                    entry.car.push_absent();
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// The state of a rigid body at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RigidBodySample {
+    pub time: f32,
+    pub body: RigidBody,
+
+    /// Whether the sample should not be interpolated with the previous sample, such as the first
+    /// sample of a respawned actor
+    pub discontinuous: bool,
+}
+
+/// Collects every rigid body update of an actor. Since actor ids are recycled, the first update
+/// after the actor id is given to a new actor is marked as discontinuous. A live actor that is
+/// replicated again with the same object is not a new actor.
+pub fn actor_samples(frames: &[Frame], actor_id: ActorId) -> Vec<RigidBodySample> {
+    let mut result = Vec::new();
+    let mut discontinuous = false;
+    let mut live: Option<ObjectId> = None;
+    for frame in frames {
+        let deleted = frame.deleted_actors.contains(&actor_id);
+        let new_actor = frame.new_actors.iter().find(|x| x.actor_id == actor_id);
+        if let Some(new_actor) = new_actor {
+            if deleted || live != Some(new_actor.object_id) {
+                discontinuous = true;
+            }
+            live = Some(new_actor.object_id);
+        }
+
+        let body = frame
+            .updated_actors
+            .iter()
+            .rev()
+            .filter(|x| x.actor_id == actor_id)
+            .find_map(|x| match x.attribute {
+                Attribute::RigidBody(body) => Some(body),
+                _ => None,
+            });
+
+        if let Some(body) = body {
+            result.push(RigidBodySample {
+                time: frame.time,
+                body,
+                discontinuous: discontinuous && !result.is_empty(),
+            });
+            discontinuous = false;
+        }
+
+        // Without a new actor, the actor is deleted after its updates in the frame
+        if deleted && new_actor.is_none() {
+            live = None;
+        }
+    }
+
+    result
+}
+
+/// How to interpolate the location between two samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Linearly interpolate the location
+    Linear,
+
+    /// Interpolate the location with a cubic hermite spline through the linear velocity of each
+    /// sample. Falls back to linear interpolation when a sample has no velocity.
+    Hermite,
+}
+
+/// Resamples irregular rigid body updates to a fixed rate.
+///
+/// Locations and velocities are interpolated between the two surrounding samples and rotations
+/// are spherically interpolated. Samples are not interpolated across a teleport (eg: a goal reset
+/// or a respawn), which is a discontinuous sample or a jump faster than the teleport speed.
+/// Instead the state before the teleport is held until the teleport occurs.
+///
+/// ```
+/// use boxcars::physics::{Interpolation, PhysicsTimeSeries, Resampler};
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = &replay.network_frames.as_ref().unwrap().frames;
+/// let series = PhysicsTimeSeries::from_frames(&replay.objects, frames);
+/// let samples = series.ball.samples(&series.time);
+/// let resampled = Resampler::new(120.0)
+///     .interpolation(Interpolation::Hermite)
+///     .resample(&samples);
+///
+/// let duration = samples.last().unwrap().time - samples[0].time;
+/// assert_eq!(resampled.len(), (duration * 120.0) as usize + 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resampler {
+    rate: f32,
+    interpolation: Interpolation,
+    teleport_speed: f32,
+}
+
+impl Resampler {
+    /// Creates a resampler that outputs the given number of samples per second
+    pub fn new(rate: f32) -> Self {
+        Resampler {
+            rate,
+            interpolation: Interpolation::Linear,
+            teleport_speed: 10_000.0,
+        }
+    }
+
+    /// Sets how locations are interpolated. Defaults to linear interpolation.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Sets the speed (in unreal units per second) between two samples that is considered a
+    /// teleport. The default of 10,000 is faster than a ball can travel.
+    pub fn teleport_speed(mut self, speed: f32) -> Self {
+        self.teleport_speed = speed;
+        self
+    }
+
+    /// Resamples from the time of the first sample up to the time of the last sample. Samples
+    /// must be sorted by time. The first resampled state after a teleport is marked as
+    /// discontinuous.
+    pub fn resample(&self, samples: &[RigidBodySample]) -> Vec<RigidBodySample> {
+        let (first, last) = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) if self.rate > 0.0 => (first, last),
+            _ => return Vec::new(),
+        };
+
+        let start = first.time;
+        let count = ((last.time - start) * self.rate) as usize + 1;
+        let mut result = Vec::with_capacity(count);
+        let mut index = 0;
+        let mut discontinuous = false;
+
+        for i in 0..count {
+            let time = start + (i as f32) / self.rate;
+
+            // Advance to the pair of samples that surround the time
+            while index + 1 < samples.len() && samples[index + 1].time <= time {
+                index += 1;
+                discontinuous |= self.is_teleport(&samples[index - 1], &samples[index]);
+            }
+
+            let current = &samples[index];
+            let body = match samples.get(index + 1) {
+                Some(next) if !self.is_teleport(current, next) => {
+                    let u = (time - current.time) / (next.time - current.time);
+                    self.interpolate(current, next, u)
+                }
+                _ => current.body,
+            };
+
+            result.push(RigidBodySample {
+                time,
+                body,
+                discontinuous,
+            });
+            discontinuous = false;
+        }
+
+        result
+    }
+
+    fn is_teleport(&self, a: &RigidBodySample, b: &RigidBodySample) -> bool {
+        let dt = b.time - a.time;
+        b.discontinuous
+            || dt <= 0.0
+            || distance(a.body.location, b.body.location) > self.teleport_speed * dt
+    }
+
+    fn interpolate(&self, a: &RigidBodySample, b: &RigidBodySample, u: f32) -> RigidBody {
+        let (a_body, b_body) = (&a.body, &b.body);
+        let dt = b.time - a.time;
+        let location = match (
+            self.interpolation,
+            a_body.linear_velocity,
+            b_body.linear_velocity,
+        ) {
+            (Interpolation::Hermite, Some(v0), Some(v1)) => {
+                hermite(a_body.location, v0, b_body.location, v1, dt, u)
+            }
+            _ => lerp(a_body.location, b_body.location, u),
+        };
+
+        let velocity = |x: Option<Vector3f>, y: Option<Vector3f>| match (x, y) {
+            (Some(x), Some(y)) => Some(lerp(x, y, u)),
+            (x, _) => x,
+        };
+
+        RigidBody {
+            sleeping: a_body.sleeping,
+            location,
+            rotation: slerp(a_body.rotation, b_body.rotation, u),
+            linear_velocity: velocity(a_body.linear_velocity, b_body.linear_velocity),
+            angular_velocity: velocity(a_body.angular_velocity, b_body.angular_velocity),
+        }
+    }
+}
+
+fn distance(a: Vector3f, b: Vector3f) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt()
+}
+
+fn lerp(a: Vector3f, b: Vector3f, u: f32) -> Vector3f {
+    Vector3f {
+        x: a.x + (b.x - a.x) * u,
+        y: a.y + (b.y - a.y) * u,
+        z: a.z + (b.z - a.z) * u,
+    }
+}
+
+/// Cubic hermite interpolation where the tangents are the velocities scaled to the interval
+fn hermite(p0: Vector3f, v0: Vector3f, p1: Vector3f, v1: Vector3f, dt: f32, u: f32) -> Vector3f {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    let h00 = 2.0 * u3 - 3.0 * u2 + 1.0;
+    let h10 = u3 - 2.0 * u2 + u;
+    let h01 = -2.0 * u3 + 3.0 * u2;
+    let h11 = u3 - u2;
+    let f =
+        |p0: f32, v0: f32, p1: f32, v1: f32| h00 * p0 + h10 * dt * v0 + h01 * p1 + h11 * dt * v1;
+
+    Vector3f {
+        x: f(p0.x, v0.x, p1.x, v1.x),
+        y: f(p0.y, v0.y, p1.y, v1.y),
+        z: f(p0.z, v0.z, p1.z, v1.z),
+    }
+}
+
+/// Spherical interpolation along the shortest path between two rotations
+fn slerp(a: Quaternion, b: Quaternion, u: f32) -> Quaternion {
+    let mut dot = a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w;
+    let b = if dot < 0.0 {
+        dot = -dot;
+        Quaternion {
+            x: -b.x,
+            y: -b.y,
+            z: -b.z,
+            w: -b.w,
+        }
+    } else {
+        b
+    };
+
+    // Nearly identical rotations are linearly interpolated to avoid dividing by zero
+    let (s0, s1) = if dot > 0.9995 {
+        (1.0 - u, u)
+    } else {
+        let theta = dot.min(1.0).acos();
+        let sin = theta.sin();
+        (((1.0 - u) * theta).sin() / sin, (u * theta).sin() / sin)
+    };
+
+    let q = Quaternion {
+        x: s0 * a.x + s1 * b.x,
+        y: s0 * a.y + s1 * b.y,
+        z: s0 * a.z + s1 * b.z,
+        w: s0 * a.w + s1 * b.w,
+    };
+
+    let norm = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+    if norm > 0.0 {
+        Quaternion {
+            x: q.x / norm,
+            y: q.y / norm,
+            z: q.z / norm,
+            w: q.w / norm,
+        }
+    } else {
+        q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{NewActor, UpdatedAttribute};
+    use crate::test_utils::{new_actor, objects};
+
+    fn body(x: f32) -> RigidBody {
+        RigidBody {
+            sleeping: false,
+            location: Vector3f { x, y: 0.0, z: 93.0 },
+            rotation: Quaternion {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 1.0,
+            },
+            linear_velocity: Some(Vector3f {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            }),
+            angular_velocity: None,
+        }
+    }
+
+    fn frame(time: f32, new_actors: Vec<NewActor>, updates: Vec<UpdatedAttribute>) -> Frame {
+        Frame {
+            time,
+            delta: 0.5,
+            ..crate::test_utils::frame(new_actors, vec![], updates)
+        }
+    }
+
+    fn update(x: f32) -> UpdatedAttribute {
+        crate::test_utils::update(1, 1, Attribute::RigidBody(body(x)))
+    }
+
+    #[test]
+    fn test_ball_time_series() {
+        let ball = new_actor(1, 0);
+
+        let frames = vec![
+            frame(0.5, vec![], vec![]),
+            frame(1.0, vec![ball], vec![]),
+            frame(1.5, vec![], vec![update(10.0)]),
+            frame(2.0, vec![], vec![]),
+            frame(2.5, vec![], vec![update(20.0)]),
+        ];
+
+        let series = PhysicsTimeSeries::from_frames(
+            &objects(&["Archetypes.Ball.Ball_Default", RIGID_BODY]),
+            &frames,
+        );
+        assert_eq!(series.time, vec![0.5, 1.0, 1.5, 2.0, 2.5]);
+        assert!(series.players.is_empty());
+
+        let ball = series.ball;
+        assert_eq!(ball.present, vec![false, true, true, true, true]);
+        assert_eq!(ball.inferred, vec![false, true, false, true, false]);
+        assert_eq!(ball.location.x[2..], [10.0, 10.0, 20.0]);
+        assert!(ball.location.x[0].is_nan() && ball.location.x[1].is_nan());
+        assert!(ball.angular_velocity.x.iter().all(|x| x.is_nan()));
+        assert_eq!(ball.rigid_body(0), None);
+        assert_eq!(ball.rigid_body(1), None);
+        assert_eq!(ball.rigid_body(3), Some(body(10.0)));
+    }
+
+    #[test]
+    fn test_ball_replaced() {
+        let replaced = Frame {
+            time: 1.5,
+            ..crate::test_utils::frame(
+                vec![new_actor(2, 0)],
+                vec![1],
+                vec![crate::test_utils::update(
+                    2,
+                    1,
+                    Attribute::RigidBody(body(0.0)),
+                )],
+            )
+        };
+
+        // The ball is replaced by another actor without a frame where no ball is live
+        let frames = vec![
+            frame(0.5, vec![new_actor(1, 0)], vec![update(10.0)]),
+            frame(1.0, vec![], vec![update(20.0)]),
+            replaced,
+        ];
+
+        let series = PhysicsTimeSeries::from_frames(
+            &objects(&["Archetypes.Ball.Ball_Default", RIGID_BODY]),
+            &frames,
+        );
+        let ball = &series.ball;
+        assert_eq!(ball.present, vec![true, true, true]);
+        assert_eq!(
+            ball.actor_id,
+            vec![Some(ActorId(1)), Some(ActorId(1)), Some(ActorId(2))]
+        );
+
+        let samples = ball.samples(&series.time);
+        let flags: Vec<bool> = samples.iter().map(|x| x.discontinuous).collect();
+        assert_eq!(flags, vec![false, false, true]);
+    }
+
+    fn sample(time: f32, x: f32) -> RigidBodySample {
+        RigidBodySample {
+            time,
+            body: body(x),
+            discontinuous: false,
+        }
+    }
+
+    #[test]
+    fn test_resample_linear() {
+        let samples = vec![sample(1.0, 0.0), sample(1.5, 50.0), sample(2.0, 60.0)];
+        let resampled = Resampler::new(4.0).resample(&samples);
+        let x: Vec<f32> = resampled.iter().map(|x| x.body.location.x).collect();
+        assert_eq!(x, vec![0.0, 25.0, 50.0, 55.0, 60.0]);
+        assert_eq!(resampled[1].time, 1.25);
+        assert!(resampled.iter().all(|x| !x.discontinuous));
+    }
+
+    #[test]
+    fn test_resample_hermite() {
+        let mut samples = vec![sample(0.0, 0.0), sample(1.0, 0.0)];
+        samples[0].body.linear_velocity = Some(Vector3f {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        });
+        samples[1].body.linear_velocity = samples[0]
+            .body
+            .linear_velocity
+            .map(|v| Vector3f { x: -v.x, ..v });
+
+        let resampled = Resampler::new(2.0)
+            .interpolation(Interpolation::Hermite)
+            .resample(&samples);
+        assert_eq!(resampled[1].body.location.x, 0.25);
+
+        let resampled = Resampler::new(2.0).resample(&samples);
+        assert_eq!(resampled[1].body.location.x, 0.0);
+    }
+
+    #[test]
+    fn test_resample_teleport() {
+        let mut samples = vec![
+            sample(0.0, 0.0),
+            sample(1.0, 20_000.0),
+            sample(2.0, 20_100.0),
+        ];
+        let resampled = Resampler::new(2.0).resample(&samples);
+        let x: Vec<f32> = resampled.iter().map(|x| x.body.location.x).collect();
+        assert_eq!(x, vec![0.0, 0.0, 20_000.0, 20_050.0, 20_100.0]);
+        let flags: Vec<bool> = resampled.iter().map(|x| x.discontinuous).collect();
+        assert_eq!(flags, vec![false, false, true, false, false]);
+
+        samples[2].discontinuous = true;
+        let resampled = Resampler::new(2.0).resample(&samples);
+        assert_eq!(resampled[3].body.location.x, 20_000.0);
+        assert!(resampled[4].discontinuous);
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        };
+        let b = Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+            w: 0.0,
+        };
+
+        let half = slerp(a, b, 0.5);
+        assert!((half.z - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert!((half.w - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+        // The shortest path is taken when the quaternions are in opposite hemispheres
+        let neg_b = Quaternion { z: -1.0, ..b };
+        let half = slerp(a, neg_b, 0.5);
+        assert!((half.z + std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(slerp(a, a, 0.3), a);
+    }
+
+    #[test]
+    fn test_actor_samples() {
+        let car = |id| new_actor(1, id);
+
+        let frames = vec![
+            frame(0.5, vec![car(0)], vec![update(10.0)]),
+            frame(1.0, vec![], vec![update(15.0), update(20.0)]),
+            frame(1.5, vec![car(2)], vec![]),
+            frame(2.0, vec![], vec![update(30.0)]),
+        ];
+
+        let samples = actor_samples(&frames, ActorId(1));
+        let x: Vec<(f32, f32, bool)> = samples
+            .iter()
+            .map(|x| (x.time, x.body.location.x, x.discontinuous))
+            .collect();
+        assert_eq!(
+            x,
+            vec![(0.5, 10.0, false), (1.0, 20.0, false), (2.0, 30.0, true)]
+        );
+    }
+
+    #[test]
+    fn test_actor_samples_replicated() {
+        let car = || new_actor(1, 0);
+        let deleted = |time| Frame {
+            time,
+            ..crate::test_utils::frame(vec![], vec![1], vec![update(25.0)])
+        };
+
+        // The car is replicated again while live, and is only new after it is deleted
+        let frames = vec![
+            frame(0.5, vec![car()], vec![update(10.0)]),
+            frame(1.0, vec![car()], vec![update(20.0)]),
+            deleted(1.5),
+            frame(2.0, vec![car()], vec![update(30.0)]),
+        ];
+
+        let samples = actor_samples(&frames, ActorId(1));
+        let flags: Vec<bool> = samples.iter().map(|x| x.discontinuous).collect();
+        assert_eq!(flags, vec![false, false, false, true]);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::errors::ReadError;
+use crate::parser::ParserBuilder;
+use crate::parsing_utils::le_i32;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::ops::Deref;
+
+/// The bytes of a replay read from a reader or, with the `mmap` feature, memory mapped from a
+/// file. Since a parsed replay borrows from the data it was parsed from, the data is kept here for
+/// as long as the replay is being parsed.
+///
+/// ```
+/// let file = std::fs::File::open("assets/replays/good/rumble.replay").unwrap();
+/// let data = boxcars::ReplayData::from_reader(std::io::BufReader::new(file)).unwrap();
+/// let replay = data.parser().must_parse_network_data().parse().unwrap();
+/// assert!(replay.network_frames.is_some());
+/// ```
+#[derive(Debug)]
+pub struct ReplayData {
+    data: Data,
+}
+
+#[derive(Debug)]
+enum Data {
+    Buffer(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Map(memmap2::Mmap),
+}
+
+impl ReplayData {
+    /// Reads the header and content sections of a replay. Each section is read straight into the
+    /// replay buffer as its length prefix is encountered, and the reader is left positioned right
+    /// after the content section, so a replay can be read out of a larger stream. A section cut
+    /// short by the end of the stream is left for the parser to report.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<ReplayData, ReadError> {
+        let mut data = Vec::new();
+        read_section(&mut reader, &mut data)?;
+        read_section(&mut reader, &mut data)?;
+        Ok(ReplayData {
+            data: Data::Buffer(data),
+        })
+    }
+
+    /// Memory maps the replay file, so that pages are only read as the parser reaches them.
+    ///
+    /// The replay file must not be modified while it is mapped, else the parser may observe
+    /// the modification mid parse.
+    #[cfg(feature = "mmap")]
+    pub fn map<P: AsRef<std::path::Path>>(path: P) -> Result<ReplayData, ReadError> {
+        let file = std::fs::File::open(path)?;
+
+        // Safety: the map is read only and the caller is documented to not modify the file
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(ReplayData {
+            data: Data::Map(map),
+        })
+    }
+
+    /// Returns the bytes of the replay
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.data {
+            Data::Buffer(data) => data,
+            #[cfg(feature = "mmap")]
+            Data::Map(map) => map,
+        }
+    }
+
+    /// Returns a parser builder for the replay
+    pub fn parser(&self) -> ParserBuilder<'_> {
+        ParserBuilder::new(self.as_bytes())
+    }
+}
+
+impl Deref for ReplayData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for ReplayData {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Appends a length prefixed section (size, crc, and data) from the reader to the buffer
+pub(crate) fn read_section<R: Read>(reader: &mut R, data: &mut Vec<u8>) -> Result<(), ReadError> {
+    let start = data.len();
+    data.resize(start + 8, 0);
+    reader.read_exact(&mut data[start..])?;
+    let size = le_i32(&data[start..start + 4]);
+
+    // The size is untrusted, so read up to it instead of allocating it up front
+    let size = u64::try_from(size).unwrap_or(0);
+    reader.take(size).read_to_end(data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_reader_matches_slice() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut stream = data.to_vec();
+        stream.extend_from_slice(b"trailing");
+
+        let mut reader = &stream[..];
+        let replay_data = ReplayData::from_reader(&mut reader).unwrap();
+        assert_eq!(reader, b"trailing");
+        assert_eq!(replay_data.as_bytes(), &data[..]);
+
+        let expected = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse();
+        let actual = replay_data.parser().must_parse_network_data().parse();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_reader_truncated() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let replay_data = ReplayData::from_reader(&data[..data.len() - 100]).unwrap();
+        assert!(replay_data
+            .parser()
+            .must_parse_network_data()
+            .parse()
+            .is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_map_matches_slice() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let replay_data = ReplayData::map("assets/replays/good/rumble.replay").unwrap();
+        assert_eq!(&replay_data[..], &data[..]);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::data::{object_classes, ATTRIBUTES, PARENT_CLASSES, SPAWN_STATS};
+use crate::network::attributes::AttributeDecode;
+use crate::network::{AttributeTag, SpawnTrajectory};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// The tables used to decode the network data: how each attribute is decoded, the class hierarchy
+/// that attributes are inherited through, which class an object is an instance of, and the
+/// initial trajectory of spawned objects.
+///
+/// A registry starts out with the tables built into boxcars, and entries added to it take
+/// precedence over the built-in ones. This allows replays from a Rocket League patch that
+/// introduced a new attribute to be decoded without waiting on a new release of boxcars.
+///
+/// A registry can also be deserialized, so additions can be kept in a JSON or TOML file. All keys
+/// are optional. Decoders for attributes with a new bit layout can only be added with
+/// `add_decoder`.
+///
+/// ```
+/// use boxcars::{AttributeTag, ParserBuilder, Registry};
+///
+/// let mut registry: Registry = serde_json::from_str(r#"{
+///     "attributes": { "TAGame.Car_TA:NewAttribute": "Int" },
+///     "parent_classes": { "TAGame.NewCar_TA": "TAGame.Car_TA" },
+///     "object_classes": { "Archetypes.Car.NewCar": "TAGame.NewCar_TA" },
+///     "spawns": { "Archetypes.Car.NewCar": "LocationAndRotation" }
+/// }"#).unwrap();
+/// registry.add_attribute("TAGame.Car_TA:OtherAttribute", AttributeTag::Boolean);
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = ParserBuilder::new(&data[..])
+///     .with_registry(&registry)
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+/// assert!(replay.network_frames.is_some());
+/// ```
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Registry {
+    attributes: HashMap<String, AttributeTag>,
+    parent_classes: HashMap<String, String>,
+    object_classes: HashMap<String, String>,
+    spawns: HashMap<String, SpawnTrajectory>,
+
+    #[serde(skip)]
+    decoders: HashMap<String, Arc<dyn AttributeDecode>>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("attributes", &self.attributes)
+            .field("parent_classes", &self.parent_classes)
+            .field("object_classes", &self.object_classes)
+            .field("spawns", &self.spawns)
+            .field("decoders", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl PartialEq for Registry {
+    fn eq(&self, other: &Self) -> bool {
+        self.attributes == other.attributes
+            && self.parent_classes == other.parent_classes
+            && self.object_classes == other.object_classes
+            && self.spawns == other.spawns
+            && self.decoders.len() == other.decoders.len()
+            && self.decoders.iter().all(|(name, decoder)| {
+                matches!(other.decoders.get(name), Some(x) if Arc::ptr_eq(x, decoder))
+            })
+    }
+}
+
+impl Registry {
+    /// Creates a registry with only the built-in tables
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Sets how the attribute (eg: "TAGame.Car_TA:TeamPaint") is decoded
+    pub fn add_attribute(&mut self, name: &str, tag: AttributeTag) -> &mut Self {
+        self.attributes.insert(String::from(name), tag);
+        self
+    }
+
+    /// Sets the parent of a class (eg: "TAGame.Car_TA" to "TAGame.Vehicle_TA"). A class inherits
+    /// the attributes of its parent.
+    pub fn add_parent_class(&mut self, class: &str, parent: &str) -> &mut Self {
+        self.parent_classes
+            .insert(String::from(class), String::from(parent));
+        self
+    }
+
+    /// Sets the class an object is an instance of (eg: "Archetypes.Ball.Ball_Default" to
+    /// "TAGame.Ball_TA")
+    pub fn add_object_class(&mut self, object: &str, class: &str) -> &mut Self {
+        self.object_classes
+            .insert(String::from(object), String::from(class));
+        self
+    }
+
+    /// Sets the initial trajectory decoded when the object is spawned as a new actor
+    pub fn add_spawn(&mut self, object: &str, spawn: SpawnTrajectory) -> &mut Self {
+        self.spawns.insert(String::from(object), spawn);
+        self
+    }
+
+    /// Decodes the attribute with the given decoder instead of by its attribute tag
+    pub fn add_decoder<D>(&mut self, name: &str, decoder: D) -> &mut Self
+    where
+        D: AttributeDecode + 'static,
+    {
+        self.decoders.insert(String::from(name), Arc::new(decoder));
+        self
+    }
+
+    /// Adds all the entries of another registry, overwriting any that are already present
+    pub fn extend(&mut self, other: Registry) -> &mut Self {
+        self.attributes.extend(other.attributes);
+        self.parent_classes.extend(other.parent_classes);
+        self.object_classes.extend(other.object_classes);
+        self.spawns.extend(other.spawns);
+        self.decoders.extend(other.decoders);
+        self
+    }
+
+    /// Returns how the attribute is decoded
+    pub fn attribute(&self, name: &str) -> Option<AttributeTag> {
+        self.attributes
+            .get(name)
+            .or_else(|| ATTRIBUTES.get(name))
+            .copied()
+    }
+
+    /// Returns the parent of the class
+    pub fn parent_class(&self, class: &str) -> Option<&str> {
+        self.parent_classes
+            .get(class)
+            .map(|x| x.as_str())
+            .or_else(|| PARENT_CLASSES.get(class).copied())
+    }
+
+    /// Returns the decoder added for the attribute
+    pub(crate) fn decoder(&self, name: &str) -> Option<&Arc<dyn AttributeDecode>> {
+        self.decoders.get(name)
+    }
+
+    /// Returns the initial trajectory of the spawned object
+    pub fn spawn(&self, object: &str) -> Option<SpawnTrajectory> {
+        self.spawns
+            .get(object)
+            .or_else(|| SPAWN_STATS.get(object))
+            .copied()
+    }
+
+    /// Returns all objects with their class
+    pub(crate) fn object_classes(&self) -> Vec<(&str, &str)> {
+        let mut result: Vec<(&str, &str)> = object_classes()
+            .into_iter()
+            .filter(|(obj, _)| !self.object_classes.contains_key(*obj))
+            .collect();
+        result.extend(
+            self.object_classes
+                .iter()
+                .map(|(obj, class)| (obj.as_str(), class.as_str())),
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_builtin() {
+        let registry = Registry::new();
+        assert_eq!(
+            registry.attribute("TAGame.Car_TA:TeamPaint"),
+            Some(AttributeTag::TeamPaint)
+        );
+        assert_eq!(
+            registry.parent_class("TAGame.Car_TA"),
+            Some("TAGame.Vehicle_TA")
+        );
+        assert_eq!(
+            registry.spawn("TAGame.Ball_TA"),
+            Some(SpawnTrajectory::LocationAndRotation)
+        );
+        assert_eq!(registry.attribute("TAGame.Car_TA:Unknown"), None);
+        assert_eq!(registry.object_classes(), object_classes());
+    }
+
+    #[test]
+    fn test_registry_overrides() {
+        let mut registry = Registry::new();
+        registry
+            .add_attribute("TAGame.Car_TA:TeamPaint", AttributeTag::Int)
+            .add_attribute("TAGame.Car_TA:Unknown", AttributeTag::Boolean)
+            .add_parent_class("TAGame.Car_TA", "TAGame.Other_TA")
+            .add_object_class("Archetypes.Ball.Ball_Default", "TAGame.Other_TA")
+            .add_spawn("TAGame.Ball_TA", SpawnTrajectory::None);
+
+        assert_eq!(
+            registry.attribute("TAGame.Car_TA:TeamPaint"),
+            Some(AttributeTag::Int)
+        );
+        assert_eq!(
+            registry.attribute("TAGame.Car_TA:Unknown"),
+            Some(AttributeTag::Boolean)
+        );
+        assert_eq!(
+            registry.parent_class("TAGame.Car_TA"),
+            Some("TAGame.Other_TA")
+        );
+        assert_eq!(
+            registry.spawn("TAGame.Ball_TA"),
+            Some(SpawnTrajectory::None)
+        );
+
+        let classes = registry.object_classes();
+        assert_eq!(classes.len(), object_classes().len());
+        assert!(classes.contains(&("Archetypes.Ball.Ball_Default", "TAGame.Other_TA")));
+    }
+
+    #[test]
+    fn test_registry_toml() {
+        let mut registry: Registry = toml::from_str(
+            r#"
+            [attributes]
+            "TAGame.Car_TA:Unknown" = "Int"
+
+            [parent_classes]
+            "TAGame.NewCar_TA" = "TAGame.Car_TA"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            registry.attribute("TAGame.Car_TA:Unknown"),
+            Some(AttributeTag::Int)
+        );
+        assert_eq!(
+            registry.parent_class("TAGame.NewCar_TA"),
+            Some("TAGame.Car_TA")
+        );
+
+        let other: Registry =
+            serde_json::from_str(r#"{"attributes": {"TAGame.Car_TA:Unknown": "Float"}}"#).unwrap();
+        registry.extend(other);
+        assert_eq!(
+            registry.attribute("TAGame.Car_TA:Unknown"),
+            Some(AttributeTag::Float)
+        );
+        assert_eq!(
+            registry.parent_class("TAGame.NewCar_TA"),
+            Some("TAGame.Car_TA")
+        );
+    }
+}
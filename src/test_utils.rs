@@ -0,0 +1,43 @@
+//! Builders for the network data that unit tests feed to the frame consumers
+
+use crate::network::attributes::Attribute;
+use crate::network::{ActorId, Frame, NewActor, ObjectId, StreamId, Trajectory, UpdatedAttribute};
+
+pub(crate) fn objects(names: &[&str]) -> Vec<String> {
+    names.iter().map(|x| String::from(*x)).collect()
+}
+
+pub(crate) fn new_actor(actor_id: i32, object_id: i32) -> NewActor {
+    NewActor {
+        actor_id: ActorId(actor_id),
+        name_id: None,
+        object_id: ObjectId(object_id),
+        initial_trajectory: Trajectory {
+            location: None,
+            rotation: None,
+        },
+    }
+}
+
+pub(crate) fn update(actor_id: i32, object_id: i32, attribute: Attribute) -> UpdatedAttribute {
+    UpdatedAttribute {
+        actor_id: ActorId(actor_id),
+        stream_id: StreamId(0),
+        object_id: ObjectId(object_id),
+        attribute,
+    }
+}
+
+pub(crate) fn frame(
+    new_actors: Vec<NewActor>,
+    deleted_actors: Vec<i32>,
+    updated_actors: Vec<UpdatedAttribute>,
+) -> Frame {
+    Frame {
+        time: 1.0,
+        delta: 0.03,
+        new_actors,
+        deleted_actors: deleted_actors.into_iter().map(ActorId).collect(),
+        updated_actors,
+    }
+}
@@ -0,0 +1,661 @@
+//! # Timeline
+//!
+//! The header's `Goals` and `HighLights` properties and the replay's tick marks only coarsely
+//! describe what happened in a match. A `Timeline` is derived from the network frames instead, so
+//! each event is tied to the frame it occurred in and the players involved.
+//!
+//! Rocket league periodically resends the current value of an attribute, so an event is only
+//! recorded when an attribute changes value.
+//!
+//! A goal is replicated as both the team that was scored on and the scorer's goals increasing,
+//! which may arrive in different frames. Each is matched with the next of the other kind so that
+//! a goal is only recorded once.
+
+use crate::entities::EntityTracker;
+use crate::network::attributes::Attribute;
+use crate::network::{ActorId, Frame, ObjectId, UpdatedAttribute};
+use std::convert::TryFrom;
+
+const SCORED_ON_TEAM: &str = "TAGame.GameEvent_Soccar_TA:ReplicatedScoredOnTeam";
+const STAT_EVENT: &str = "TAGame.GameEvent_Soccar_TA:ReplicatedStatEvent";
+const DEMOLISH: &str = "TAGame.Car_TA:ReplicatedDemolish";
+const DEMOLISH_FX: &str = "TAGame.Car_TA:ReplicatedDemolish_CustomFX";
+const DEMOLISH_GOAL_EXPLOSION: &str = "TAGame.Car_TA:ReplicatedDemolishGoalExplosion";
+const MATCH_GOALS: &str = "TAGame.PRI_TA:MatchGoals";
+const MATCH_ASSISTS: &str = "TAGame.PRI_TA:MatchAssists";
+const MATCH_SAVES: &str = "TAGame.PRI_TA:MatchSaves";
+const MATCH_SHOTS: &str = "TAGame.PRI_TA:MatchShots";
+
+/// The most seconds between the team scored on and the scorer of the same goal being replicated
+const GOAL_WINDOW: f32 = 30.0;
+
+/// A player involved in an event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventPlayer {
+    /// The actor id of the player's replication info
+    pub actor_id: ActorId,
+
+    /// The name of the player at the time of the event
+    pub name: Option<String>,
+}
+
+/// A stat tracked on each player's replication info
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MatchStat {
+    Goals,
+    Assists,
+    Saves,
+    Shots,
+}
+
+/// What happened in a `TimelineEvent`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum EventKind {
+    /// A goal was scored against the given team. The scorer is the player whose goals increased
+    /// nearest to the team being replicated, and is absent for own goals. Rocket league doesn't
+    /// always replicate the team that was scored on, in which case it is the team opposing the
+    /// scorer.
+    Goal {
+        scored_on_team: Option<u8>,
+        scorer: Option<EventPlayer>,
+    },
+
+    /// A car was demolished. Players are resolved through the cars involved.
+    Demolition {
+        attacker_car: Option<ActorId>,
+        attacker: Option<EventPlayer>,
+        victim_car: ActorId,
+        victim: Option<EventPlayer>,
+    },
+
+    /// A stat event was shown, such as `Save` or `EpicSave`. The name is the stat event object's
+    /// name without the `StatEvents.Events.` prefix.
+    Stat { name: String },
+
+    /// A player's match stat increased to the given value
+    StatIncrement {
+        player: EventPlayer,
+        stat: MatchStat,
+        value: i32,
+    },
+}
+
+/// An event of the timeline and when it occurred. A goal occurs in the frame where the first of
+/// the team scored on and the scorer was replicated.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    /// The index of the frame the event occurred in
+    pub frame: usize,
+
+    /// The time of the frame the event occurred in
+    pub time: f32,
+
+    /// What happened
+    pub kind: EventKind,
+}
+
+/// The object ids of the attributes that events are derived from
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct EventIds {
+    scored_on_team: Option<ObjectId>,
+    stat_event: Option<ObjectId>,
+    demolish: Option<ObjectId>,
+    demolish_fx: Option<ObjectId>,
+    demolish_goal_explosion: Option<ObjectId>,
+    stats: [(Option<ObjectId>, MatchStat); 4],
+}
+
+/// An attribute update whose value differs from the actor's previous value
+#[derive(Debug, Clone, Copy)]
+struct Change<'a> {
+    update: &'a UpdatedAttribute,
+
+    /// Whether an integer increased. Attributes default to zero, so an actor's first value is
+    /// an increase unless the actor was created in the frame, as the values of a replay that
+    /// starts mid-match aren't events.
+    increased: bool,
+}
+
+/// The half of a goal that is still waiting to be matched
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unmatched {
+    Team,
+    Scorer,
+}
+
+/// Events derived from the network frames in the order they occurred.
+///
+/// ```
+/// use boxcars::timeline::{EventKind, Timeline};
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let frames = &replay.network_frames.as_ref().unwrap().frames;
+/// let timeline = Timeline::from_frames(&replay.objects, frames);
+/// let goals = timeline
+///     .events
+///     .iter()
+///     .filter(|x| matches!(x.kind, EventKind::Goal { .. }))
+///     .count();
+///
+/// let header_goals = replay
+///     .properties
+///     .iter()
+///     .find(|(key, _)| key == "Goals")
+///     .and_then(|(_, prop)| prop.as_array())
+///     .map_or(0, |x| x.len());
+/// assert_eq!(goals, header_goals);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Timeline {
+    pub events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    /// Derives the events from all frames of a replay
+    pub fn from_frames<S: AsRef<str>>(objects: &[S], frames: &[Frame]) -> Self {
+        let mut tracker = EntityTracker::new(objects);
+        let world = tracker.world();
+        let ids = EventIds {
+            scored_on_team: world.object_id(SCORED_ON_TEAM),
+            stat_event: world.object_id(STAT_EVENT),
+            demolish: world.object_id(DEMOLISH),
+            demolish_fx: world.object_id(DEMOLISH_FX),
+            demolish_goal_explosion: world.object_id(DEMOLISH_GOAL_EXPLOSION),
+            stats: [
+                (world.object_id(MATCH_GOALS), MatchStat::Goals),
+                (world.object_id(MATCH_ASSISTS), MatchStat::Assists),
+                (world.object_id(MATCH_SAVES), MatchStat::Saves),
+                (world.object_id(MATCH_SHOTS), MatchStat::Shots),
+            ],
+        };
+
+        let mut events: Vec<TimelineEvent> = Vec::new();
+
+        // The index of the last goal event and the half of it that hasn't been replicated yet
+        let mut pending_goal: Option<(usize, Unmatched)> = None;
+        for (index, frame) in frames.iter().enumerate() {
+            // Attributes are compared against their value before the frame is applied
+            let changed: Vec<Change<'_>> = frame
+                .updated_actors
+                .iter()
+                .enumerate()
+                .filter_map(|(i, update)| {
+                    let earlier = frame.updated_actors[..i]
+                        .iter()
+                        .rev()
+                        .find(|x| x.actor_id == update.actor_id && x.object_id == update.object_id)
+                        .map(|x| &x.attribute);
+
+                    // An actor replaced by a new actor in this frame has no previous value
+                    let actor = tracker.world().actor(update.actor_id).filter(|actor| {
+                        !frame
+                            .new_actors
+                            .iter()
+                            .any(|x| x.actor_id == actor.actor_id && x.object_id != actor.object_id)
+                    });
+
+                    let previous =
+                        earlier.or_else(|| actor.and_then(|x| x.attribute_by_id(update.object_id)));
+                    if previous == Some(&update.attribute) {
+                        return None;
+                    }
+
+                    let increased = match (previous, &update.attribute) {
+                        (Some(Attribute::Int(previous)), Attribute::Int(value)) => value > previous,
+                        (None, &Attribute::Int(value)) => value > 0 && actor.is_some(),
+                        _ => false,
+                    };
+
+                    Some(Change { update, increased })
+                })
+                .collect();
+
+            // Demolished cars may be deleted in the same frame, so their players are resolved
+            // before the frame is applied
+            let car_players: Vec<(ActorId, Option<EventPlayer>)> = changed
+                .iter()
+                .filter_map(|x| demolition(&ids, x.update))
+                .flat_map(|(attacker, victim)| attacker.into_iter().chain(Some(victim)))
+                .map(|car| (car, car_player(&tracker, car)))
+                .collect();
+
+            tracker.apply(frame);
+
+            let event = |kind| TimelineEvent {
+                frame: index,
+                time: frame.time,
+                kind,
+            };
+
+            // A goal's halves are only matched when replicated close together, as an own goal
+            // has no scorer to match
+            pending_goal =
+                pending_goal.filter(|&(i, _)| frame.time - events[i].time <= GOAL_WINDOW);
+
+            let resolve_car = |car: ActorId| {
+                car_player(&tracker, car).or_else(|| {
+                    car_players
+                        .iter()
+                        .find(|(x, _)| *x == car)
+                        .and_then(|(_, player)| player.clone())
+                })
+            };
+
+            let mut demolished: Vec<ActorId> = Vec::new();
+            for &Change { update, increased } in &changed {
+                if Some(update.object_id) == ids.scored_on_team {
+                    // The team is reset to 255 after the goal
+                    let team = match update.attribute {
+                        Attribute::Byte(team) if team != 255 => team,
+                        _ => continue,
+                    };
+
+                    // The goal may already be known from the scorer's goals increasing
+                    let matched = pending_goal
+                        .filter(|&(_, unmatched)| unmatched == Unmatched::Team)
+                        .and_then(|(i, _)| match events[i].kind {
+                            EventKind::Goal {
+                                ref mut scored_on_team,
+                                ..
+                            } if scored_on_team.unwrap_or(team) == team => Some(scored_on_team),
+                            _ => None,
+                        });
+
+                    if let Some(scored_on_team) = matched {
+                        *scored_on_team = Some(team);
+                        pending_goal = None;
+                    } else {
+                        events.push(event(EventKind::Goal {
+                            scored_on_team: Some(team),
+                            scorer: None,
+                        }));
+                        pending_goal = Some((events.len() - 1, Unmatched::Scorer));
+                    }
+                } else if Some(update.object_id) == ids.stat_event {
+                    if let Attribute::StatEvent(stat) = update.attribute {
+                        let name = usize::try_from(stat.object_id)
+                            .ok()
+                            .and_then(|x| objects.get(x))
+                            .map(AsRef::as_ref);
+                        if let Some(name) = name {
+                            let name = name.strip_prefix("StatEvents.Events.").unwrap_or(name);
+                            events.push(event(EventKind::Stat {
+                                name: String::from(name),
+                            }));
+                        }
+                    }
+                } else if let Some((attacker_car, victim_car)) = demolition(&ids, update) {
+                    // Demolitions are replicated through several demolish attributes
+                    if demolished.contains(&victim_car) {
+                        continue;
+                    }
+
+                    demolished.push(victim_car);
+                    events.push(event(EventKind::Demolition {
+                        attacker_car,
+                        attacker: attacker_car.and_then(resolve_car),
+                        victim_car,
+                        victim: resolve_car(victim_car),
+                    }));
+                } else if let Some(&(_, stat)) = ids
+                    .stats
+                    .iter()
+                    .find(|(id, _)| *id == Some(update.object_id))
+                {
+                    if !increased {
+                        continue;
+                    }
+
+                    if stat == MatchStat::Goals {
+                        // The goal may already be known from the team scored on, unless the
+                        // scorer is on that team
+                        let team = opposing_team(&tracker, update.actor_id);
+                        let matched = pending_goal
+                            .filter(|&(_, unmatched)| unmatched == Unmatched::Scorer)
+                            .and_then(|(i, _)| match events[i].kind {
+                                EventKind::Goal {
+                                    scored_on_team,
+                                    ref mut scorer,
+                                } if team.is_none() || team == scored_on_team => Some(scorer),
+                                _ => None,
+                            });
+
+                        if let Some(scorer) = matched {
+                            *scorer = event_player(&tracker, update.actor_id);
+                            pending_goal = None;
+                        } else {
+                            events.push(event(EventKind::Goal {
+                                scored_on_team: team,
+                                scorer: event_player(&tracker, update.actor_id),
+                            }));
+                            pending_goal = Some((events.len() - 1, Unmatched::Team));
+                        }
+                    }
+
+                    if let Attribute::Int(value) = update.attribute {
+                        if let Some(player) = event_player(&tracker, update.actor_id) {
+                            events.push(event(EventKind::StatIncrement {
+                                player,
+                                stat,
+                                value,
+                            }));
+                        }
+                    }
+                }
+            }
+        }
+
+        Timeline { events }
+    }
+}
+
+/// The attacking and victim car of a demolition
+fn demolition(ids: &EventIds, update: &UpdatedAttribute) -> Option<(Option<ActorId>, ActorId)> {
+    let id = Some(update.object_id);
+    if id != ids.demolish && id != ids.demolish_fx && id != ids.demolish_goal_explosion {
+        return None;
+    }
+
+    let (attacker_flag, attacker, victim) = match update.attribute {
+        Attribute::Demolish(ref x) => (x.attacker_flag, x.attacker, x.victim),
+        Attribute::DemolishFx(ref x) => (x.attacker_flag, x.attacker, x.victim),
+        _ => return None,
+    };
+
+    Some((Some(attacker).filter(|_| attacker_flag), victim))
+}
+
+fn car_player(tracker: &EntityTracker<'_>, car: ActorId) -> Option<EventPlayer> {
+    tracker
+        .car(car)
+        .and_then(|x| x.player)
+        .and_then(|x| event_player(tracker, x))
+}
+
+/// The index of the team opposing the player, assuming a match of two teams
+fn opposing_team(tracker: &EntityTracker<'_>, actor_id: ActorId) -> Option<u8> {
+    let team = tracker.player(actor_id).and_then(|x| x.team)?;
+    tracker
+        .teams()
+        .into_iter()
+        .find(|x| x.actor_id == team)
+        .and_then(|x| x.index)
+        .and_then(|x| match x {
+            0 => Some(1),
+            1 => Some(0),
+            _ => None,
+        })
+}
+
+fn event_player(tracker: &EntityTracker<'_>, actor_id: ActorId) -> Option<EventPlayer> {
+    tracker.player(actor_id).map(|x| EventPlayer {
+        actor_id: x.actor_id,
+        name: x.name.map(String::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::attributes::{ActiveActor, Demolish, DemolishFx, StatEvent};
+    use crate::network::Vector3f;
+    use crate::test_utils::{frame, new_actor, update};
+
+    fn objects() -> Vec<String> {
+        crate::test_utils::objects(&[
+            "Archetypes.GameEvent.GameEvent_Soccar",
+            "TAGame.Default__PRI_TA",
+            "Archetypes.Car.Car_Default",
+            SCORED_ON_TEAM,
+            STAT_EVENT,
+            DEMOLISH,
+            MATCH_GOALS,
+            "Engine.PlayerReplicationInfo:PlayerName",
+            "Engine.Pawn:PlayerReplicationInfo",
+            "StatEvents.Events.Goal",
+            DEMOLISH_GOAL_EXPLOSION,
+        ])
+    }
+
+    fn demolish(attacker: i32, victim: i32) -> Attribute {
+        let velocity = Vector3f {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        Attribute::Demolish(Box::new(Demolish {
+            attacker_flag: true,
+            attacker: ActorId(attacker),
+            victim_flag: true,
+            victim: ActorId(victim),
+            attack_velocity: velocity,
+            victim_velocity: velocity,
+        }))
+    }
+
+    fn goal_explosion(victim: i32) -> Attribute {
+        let velocity = Vector3f {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        Attribute::DemolishFx(Box::new(DemolishFx {
+            custom_demo_flag: false,
+            custom_demo_id: 0,
+            attacker_flag: false,
+            attacker: ActorId(-1),
+            victim_flag: true,
+            victim: ActorId(victim),
+            attack_velocity: velocity,
+            victim_velocity: velocity,
+        }))
+    }
+
+    fn players() -> Frame {
+        frame(
+            vec![new_actor(1, 0), new_actor(2, 1), new_actor(3, 1)],
+            vec![],
+            vec![
+                update(2, 7, Attribute::String(String::from("alice"))),
+                update(3, 7, Attribute::String(String::from("bob"))),
+            ],
+        )
+    }
+
+    fn player(actor_id: i32, name: &str) -> Option<EventPlayer> {
+        Some(EventPlayer {
+            actor_id: ActorId(actor_id),
+            name: Some(String::from(name)),
+        })
+    }
+
+    #[test]
+    fn test_timeline() {
+        let link = |pri| {
+            Attribute::ActiveActor(ActiveActor {
+                active: true,
+                actor: ActorId(pri),
+            })
+        };
+
+        let frames = vec![
+            frame(
+                vec![
+                    new_actor(1, 0),
+                    new_actor(2, 1),
+                    new_actor(3, 1),
+                    new_actor(4, 2),
+                    new_actor(5, 2),
+                ],
+                vec![],
+                vec![
+                    update(2, 7, Attribute::String(String::from("alice"))),
+                    update(3, 7, Attribute::String(String::from("bob"))),
+                    update(4, 8, link(2)),
+                    update(5, 8, link(3)),
+                ],
+            ),
+            frame(
+                vec![],
+                vec![],
+                vec![
+                    update(
+                        1,
+                        4,
+                        Attribute::StatEvent(StatEvent {
+                            unknown1: false,
+                            object_id: 9,
+                        }),
+                    ),
+                    update(1, 3, Attribute::Byte(1)),
+                    update(2, 6, Attribute::Int(1)),
+                ],
+            ),
+            // Resent values are not events
+            frame(
+                vec![],
+                vec![],
+                vec![
+                    update(1, 3, Attribute::Byte(1)),
+                    update(2, 6, Attribute::Int(1)),
+                ],
+            ),
+            frame(vec![], vec![], vec![update(1, 3, Attribute::Byte(255))]),
+            frame(vec![], vec![], vec![update(3, 6, Attribute::Int(1))]),
+            Frame {
+                deleted_actors: vec![ActorId(5)],
+                ..frame(vec![], vec![], vec![update(5, 5, demolish(4, 5))])
+            },
+            frame(vec![], vec![], vec![update(4, 10, goal_explosion(4))]),
+        ];
+
+        let timeline = Timeline::from_frames(&objects(), &frames);
+        let events: Vec<_> = timeline.events.iter().map(|x| (x.frame, &x.kind)).collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    1,
+                    &EventKind::Stat {
+                        name: String::from("Goal")
+                    }
+                ),
+                (
+                    1,
+                    &EventKind::Goal {
+                        scored_on_team: Some(1),
+                        scorer: player(2, "alice"),
+                    }
+                ),
+                (
+                    1,
+                    &EventKind::StatIncrement {
+                        player: player(2, "alice").unwrap(),
+                        stat: MatchStat::Goals,
+                        value: 1,
+                    }
+                ),
+                (
+                    4,
+                    &EventKind::Goal {
+                        scored_on_team: None,
+                        scorer: player(3, "bob"),
+                    }
+                ),
+                (
+                    4,
+                    &EventKind::StatIncrement {
+                        player: player(3, "bob").unwrap(),
+                        stat: MatchStat::Goals,
+                        value: 1,
+                    }
+                ),
+                (
+                    5,
+                    &EventKind::Demolition {
+                        attacker_car: Some(ActorId(4)),
+                        attacker: player(2, "alice"),
+                        victim_car: ActorId(5),
+                        victim: player(3, "bob"),
+                    }
+                ),
+                (
+                    6,
+                    &EventKind::Demolition {
+                        attacker_car: None,
+                        attacker: None,
+                        victim_car: ActorId(4),
+                        victim: player(2, "alice"),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timeline_matches_goals_across_frames() {
+        let frames = vec![
+            players(),
+            frame(vec![], vec![], vec![update(1, 3, Attribute::Byte(0))]),
+            frame(vec![], vec![], vec![update(2, 6, Attribute::Int(1))]),
+            // Decreases are not events
+            frame(vec![], vec![], vec![update(2, 6, Attribute::Int(0))]),
+            // An own goal has no scorer
+            frame(vec![], vec![], vec![update(1, 3, Attribute::Byte(1))]),
+            Frame {
+                time: 40.0,
+                ..frame(vec![], vec![], vec![update(3, 6, Attribute::Int(1))])
+            },
+        ];
+
+        let timeline = Timeline::from_frames(&objects(), &frames);
+        let events: Vec<_> = timeline.events.iter().map(|x| (x.frame, &x.kind)).collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    1,
+                    &EventKind::Goal {
+                        scored_on_team: Some(0),
+                        scorer: player(2, "alice"),
+                    }
+                ),
+                (
+                    2,
+                    &EventKind::StatIncrement {
+                        player: player(2, "alice").unwrap(),
+                        stat: MatchStat::Goals,
+                        value: 1,
+                    }
+                ),
+                (
+                    4,
+                    &EventKind::Goal {
+                        scored_on_team: Some(1),
+                        scorer: None,
+                    }
+                ),
+                (
+                    5,
+                    &EventKind::Goal {
+                        scored_on_team: None,
+                        scorer: player(3, "bob"),
+                    }
+                ),
+                (
+                    5,
+                    &EventKind::StatIncrement {
+                        player: player(3, "bob").unwrap(),
+                        stat: MatchStat::Goals,
+                        value: 1,
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! # World
+//!
+//! The network data is a stream of deltas: actors are created, updated, and deleted. A
+//! `ReplayWorld` folds these deltas, one frame at a time, into the state of every live actor.
+
+use crate::data::object_classes;
+use crate::network::attributes::Attribute;
+use crate::network::{normalize_object, ActorId, Frame, ObjectId, Trajectory};
+use fnv::FnvHashMap;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// A notable change to the actors in the world that occurred while applying a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WorldEvent {
+    /// A new actor was created
+    Spawned {
+        actor_id: ActorId,
+        object_id: ObjectId,
+    },
+
+    /// An actor was deleted. Also emitted when an actor is replaced by a new actor with the same
+    /// id without first being deleted.
+    Destroyed {
+        actor_id: ActorId,
+        object_id: ObjectId,
+    },
+
+    /// A new actor was given the id of a previous actor. Emitted before the new actor is spawned.
+    Recycled {
+        actor_id: ActorId,
+        previous_object_id: ObjectId,
+        object_id: ObjectId,
+    },
+}
+
+/// The current state of a live actor
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldActor<'a> {
+    objects: Arc<[&'a str]>,
+
+    /// The actor's id, which is only unique among live actors
+    pub actor_id: ActorId,
+
+    /// The actor's object id
+    pub object_id: ObjectId,
+
+    /// The name id the actor spawned with
+    pub name_id: Option<i32>,
+
+    /// The index of the frame that the actor spawned in
+    pub spawn_frame: usize,
+
+    /// The location and rotation that the actor spawned with
+    pub trajectory: Trajectory,
+
+    class_name: Option<&'static str>,
+    attributes: FnvHashMap<ObjectId, Attribute>,
+}
+
+impl<'a> WorldActor<'a> {
+    /// The name of the object that the actor is an instance of (eg:
+    /// `Archetypes.Car.Car_Default`)
+    pub fn object_name(&self) -> &'a str {
+        object_name(&self.objects, self.object_id)
+    }
+
+    /// The class of the actor's object (eg: `TAGame.Car_TA`), if known
+    pub fn class_name(&self) -> Option<&'static str> {
+        self.class_name
+    }
+
+    /// The latest value of the attribute with the given name (eg:
+    /// `Engine.PlayerReplicationInfo:PlayerName`)
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes
+            .iter()
+            .find(|(&id, _)| object_name(&self.objects, id) == name)
+            .map(|(_, attr)| attr)
+    }
+
+    /// The latest value of the attribute with the given object id
+    pub fn attribute_by_id(&self, object_id: ObjectId) -> Option<&Attribute> {
+        self.attributes.get(&object_id)
+    }
+
+    /// The latest value of every attribute that has been updated on the actor, keyed by the
+    /// attribute's name. The order is unspecified.
+    pub fn attributes(&self) -> impl Iterator<Item = (&'a str, &Attribute)> + '_ {
+        let objects = &self.objects;
+        self.attributes
+            .iter()
+            .map(move |(&id, attr)| (object_name(objects, id), attr))
+    }
+}
+
+fn object_name<'a>(objects: &[&'a str], object_id: ObjectId) -> &'a str {
+    usize::try_from(object_id.0)
+        .ok()
+        .and_then(|x| objects.get(x))
+        .map_or("", |x| *x)
+}
+
+/// Tracks the state of every live actor as network frames are applied in order.
+///
+/// A new actor that has the id and object of a live actor is the live actor being replicated
+/// again, which rocket league does periodically, so it is not treated as a new actor.
+///
+/// ```
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let mut world = boxcars::ReplayWorld::new(&replay.objects);
+/// for frame in &replay.network_frames.unwrap().frames {
+///     world.apply(frame);
+/// }
+///
+/// let names: Vec<_> = world
+///     .actors()
+///     .filter_map(|x| x.attribute("Engine.PlayerReplicationInfo:PlayerName"))
+///     .collect();
+/// assert_eq!(names.len(), 8);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayWorld<'a> {
+    objects: Arc<[&'a str]>,
+    object_ids: HashMap<&'a str, ObjectId>,
+    classes: Vec<Option<&'static str>>,
+    actors: FnvHashMap<ActorId, WorldActor<'a>>,
+    previous: FnvHashMap<ActorId, ObjectId>,
+    events: Vec<WorldEvent>,
+    frames: usize,
+    time: f32,
+}
+
+impl<'a> ReplayWorld<'a> {
+    /// Creates an empty world for a replay's objects
+    pub fn new<S: AsRef<str>>(objects: &'a [S]) -> Self {
+        let objects: Arc<[&'a str]> = objects.iter().map(|x| x.as_ref()).collect();
+        let object_classes: HashMap<&str, &'static str> = object_classes().into_iter().collect();
+        let classes = objects
+            .iter()
+            .map(|x| object_classes.get(normalize_object(x)).copied())
+            .collect();
+
+        let object_ids = objects
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, &name)| (name, ObjectId(i as i32)))
+            .collect();
+
+        ReplayWorld {
+            objects,
+            object_ids,
+            classes,
+            actors: FnvHashMap::default(),
+            previous: FnvHashMap::default(),
+            events: Vec::new(),
+            frames: 0,
+            time: 0.0,
+        }
+    }
+
+    /// Applies the next frame to the world and returns the events that occurred in the frame.
+    ///
+    /// Frames must be applied in the order that they were decoded. Updates to an actor that isn't
+    /// live are ignored.
+    pub fn apply(&mut self, frame: &Frame) -> &[WorldEvent] {
+        self.events.clear();
+        let frame_index = self.frames;
+        self.frames += 1;
+        self.time = frame.time;
+
+        // An actor that is updated and then deleted in the same frame is deleted after the
+        // updates, mirroring the order in the network data
+        let (deleted, deleted_last): (Vec<ActorId>, Vec<ActorId>) =
+            frame.deleted_actors.iter().partition(|&id| {
+                frame.new_actors.iter().any(|x| x.actor_id == *id)
+                    || !frame.updated_actors.iter().any(|x| x.actor_id == *id)
+            });
+
+        for actor_id in deleted {
+            self.destroy(actor_id);
+        }
+
+        for new_actor in &frame.new_actors {
+            let actor_id = new_actor.actor_id;
+
+            // Live actors are periodically replicated again as new actors (eg: at keyframes).
+            // These are not new actors and retain their state.
+            let replicated = matches!(
+                self.actors.get(&actor_id),
+                Some(x) if x.object_id == new_actor.object_id
+            );
+            if replicated {
+                continue;
+            }
+
+            self.destroy(actor_id);
+            if let Some(&previous_object_id) = self.previous.get(&actor_id) {
+                self.events.push(WorldEvent::Recycled {
+                    actor_id,
+                    previous_object_id,
+                    object_id: new_actor.object_id,
+                });
+            }
+
+            let class_name = usize::try_from(new_actor.object_id.0)
+                .ok()
+                .and_then(|x| self.classes.get(x).copied())
+                .flatten();
+
+            self.actors.insert(
+                actor_id,
+                WorldActor {
+                    objects: Arc::clone(&self.objects),
+                    actor_id,
+                    object_id: new_actor.object_id,
+                    name_id: new_actor.name_id,
+                    spawn_frame: frame_index,
+                    trajectory: new_actor.initial_trajectory,
+                    class_name,
+                    attributes: FnvHashMap::default(),
+                },
+            );
+
+            self.previous.insert(actor_id, new_actor.object_id);
+            self.events.push(WorldEvent::Spawned {
+                actor_id,
+                object_id: new_actor.object_id,
+            });
+        }
+
+        for update in &frame.updated_actors {
+            if let Some(actor) = self.actors.get_mut(&update.actor_id) {
+                actor
+                    .attributes
+                    .insert(update.object_id, update.attribute.clone());
+            }
+        }
+
+        for actor_id in deleted_last {
+            self.destroy(actor_id);
+        }
+
+        &self.events
+    }
+
+    fn destroy(&mut self, actor_id: ActorId) {
+        if let Some(actor) = self.actors.remove(&actor_id) {
+            self.events.push(WorldEvent::Destroyed {
+                actor_id,
+                object_id: actor.object_id,
+            });
+        }
+    }
+
+    /// The object id of the first object with the given name. Looking up attributes by object id
+    /// is faster than by name.
+    pub fn object_id(&self, name: &str) -> Option<ObjectId> {
+        self.object_ids.get(name).copied()
+    }
+
+    /// The live actor with the given id
+    pub fn actor(&self, actor_id: ActorId) -> Option<&WorldActor<'a>> {
+        self.actors.get(&actor_id)
+    }
+
+    /// All live actors. The order is unspecified.
+    pub fn actors(&self) -> impl Iterator<Item = &WorldActor<'a>> {
+        self.actors.values()
+    }
+
+    /// The live actors that are instances of the given class (eg: `TAGame.Car_TA`)
+    pub fn actors_of_class<'b>(
+        &'b self,
+        class_name: &'b str,
+    ) -> impl Iterator<Item = &'b WorldActor<'a>> {
+        self.actors()
+            .filter(move |x| x.class_name() == Some(class_name))
+    }
+
+    /// The events that occurred in the last applied frame
+    pub fn events(&self) -> &[WorldEvent] {
+        &self.events
+    }
+
+    /// The number of frames that have been applied
+    pub fn frames_applied(&self) -> usize {
+        self.frames
+    }
+
+    /// The time of the last applied frame
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::UpdatedAttribute;
+    use crate::test_utils::{new_actor, update};
+
+    fn objects() -> Vec<String> {
+        crate::test_utils::objects(&[
+            "Archetypes.Car.Car_Default",
+            "Engine.PlayerReplicationInfo:Ping",
+            "TAGame.Default__PRI_TA",
+        ])
+    }
+
+    fn frame() -> Frame {
+        crate::test_utils::frame(vec![], vec![], vec![])
+    }
+
+    fn ping(actor_id: i32, ping: u8) -> UpdatedAttribute {
+        update(actor_id, 1, Attribute::Byte(ping))
+    }
+
+    #[test]
+    fn test_world_spawn_and_update() {
+        let objects = objects();
+        let mut world = ReplayWorld::new(&objects);
+        let events = world.apply(&Frame {
+            new_actors: vec![new_actor(1, 0)],
+            updated_actors: vec![ping(1, 10), ping(1, 20), ping(2, 30)],
+            ..frame()
+        });
+
+        assert_eq!(
+            events,
+            &[WorldEvent::Spawned {
+                actor_id: ActorId(1),
+                object_id: ObjectId(0),
+            }]
+        );
+
+        let actor = world.actor(ActorId(1)).unwrap();
+        assert_eq!(actor.object_name(), "Archetypes.Car.Car_Default");
+        assert_eq!(actor.class_name(), Some("TAGame.Car_TA"));
+        assert_eq!(
+            actor.attribute("Engine.PlayerReplicationInfo:Ping"),
+            Some(&Attribute::Byte(20))
+        );
+        assert_eq!(world.actor(ActorId(2)), None);
+        assert_eq!(world.frames_applied(), 1);
+    }
+
+    #[test]
+    fn test_world_destroy_and_recycle() {
+        let objects = objects();
+        let mut world = ReplayWorld::new(&objects);
+        world.apply(&Frame {
+            new_actors: vec![new_actor(1, 0)],
+            ..frame()
+        });
+
+        // The update applies to the actor before it is deleted
+        let events = world.apply(&Frame {
+            deleted_actors: vec![ActorId(1)],
+            updated_actors: vec![ping(1, 10)],
+            ..frame()
+        });
+        assert_eq!(
+            events,
+            &[WorldEvent::Destroyed {
+                actor_id: ActorId(1),
+                object_id: ObjectId(0),
+            }]
+        );
+        assert_eq!(world.actors().count(), 0);
+
+        let events = world.apply(&Frame {
+            new_actors: vec![new_actor(1, 2)],
+            ..frame()
+        });
+        assert_eq!(
+            events,
+            &[
+                WorldEvent::Recycled {
+                    actor_id: ActorId(1),
+                    previous_object_id: ObjectId(0),
+                    object_id: ObjectId(2),
+                },
+                WorldEvent::Spawned {
+                    actor_id: ActorId(1),
+                    object_id: ObjectId(2),
+                }
+            ]
+        );
+
+        let actor = world.actor(ActorId(1)).unwrap();
+        assert_eq!(actor.class_name(), Some("TAGame.PRI_TA"));
+        assert_eq!(actor.attributes().count(), 0);
+        assert_eq!(world.actors_of_class("TAGame.PRI_TA").count(), 1);
+    }
+
+    #[test]
+    fn test_world_replaces_live_actor() {
+        let objects = objects();
+        let mut world = ReplayWorld::new(&objects);
+        world.apply(&Frame {
+            new_actors: vec![new_actor(1, 0)],
+            ..frame()
+        });
+
+        let events = world.apply(&Frame {
+            new_actors: vec![new_actor(1, 2)],
+            ..frame()
+        });
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], WorldEvent::Destroyed { .. }));
+        assert!(matches!(events[1], WorldEvent::Recycled { .. }));
+        assert_eq!(world.actor(ActorId(1)).unwrap().spawn_frame, 1);
+    }
+
+    #[test]
+    fn test_world_replicated_actor() {
+        let objects = objects();
+        let mut world = ReplayWorld::new(&objects);
+        world.apply(&Frame {
+            new_actors: vec![new_actor(1, 0)],
+            updated_actors: vec![ping(1, 10)],
+            ..frame()
+        });
+
+        let events = world.apply(&Frame {
+            new_actors: vec![new_actor(1, 0)],
+            ..frame()
+        });
+        assert_eq!(events, &[]);
+
+        let actor = world.actor(ActorId(1)).unwrap();
+        assert_eq!(actor.spawn_frame, 0);
+        assert_eq!(
+            actor.attribute_by_id(ObjectId(1)),
+            Some(&Attribute::Byte(10))
+        );
+    }
+}
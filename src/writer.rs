@@ -0,0 +1,551 @@
+//! # Writing
+//!
+//! Encodes a `Replay` back into the binary replay format described in the parsing module. The
+//! header and content sizes along with their CRCs are recomputed from the written data, so the
+//! sizes and CRCs stored on the `Replay` are ignored.
+//!
+//! A `HeaderEditor` rewrites only the header properties of an existing replay and keeps the body
+//! bytes as they are.
+
+use crate::core_parser::CoreParser;
+use crate::core_writer::CoreWriter;
+use crate::crc::calc_crc;
+use crate::errors::{ParseError, WriteError};
+use crate::header::{self, Header};
+use crate::models::*;
+use crate::network::{self, Frame};
+use crate::registry::Registry;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// Serializes a `Replay` to the rocket league replay format.
+///
+/// When raw network data is supplied, it is written verbatim. Otherwise the replay's network
+/// frames are encoded and the keyframe positions are updated to point at the encoded frames, with
+/// keyframes past the last frame dropped. Frames that failed to decode can't be encoded, so
+/// network frames with failures are an error. The `NumFrames` header property is written as is,
+/// so it should agree with the number of frames. A replay without network frames is written with
+/// an empty network data section.
+///
+/// ```
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let replay = boxcars::ParserBuilder::new(&data[..])
+///     .never_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// let written = boxcars::ReplayWriter::new(&replay).write().unwrap();
+/// let reparsed = boxcars::ParserBuilder::new(&written[..])
+///     .always_check_crc()
+///     .never_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(reparsed.properties, replay.properties);
+/// assert_eq!(reparsed.header_crc, replay.header_crc);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayWriter<'a> {
+    replay: &'a Replay,
+    network_data: Option<&'a [u8]>,
+    registry: Option<&'a Registry>,
+}
+
+impl<'a> ReplayWriter<'a> {
+    pub fn new(replay: &'a Replay) -> Self {
+        ReplayWriter {
+            replay,
+            network_data: None,
+            registry: None,
+        }
+    }
+
+    /// Writes the given bytes verbatim as the network data section
+    pub fn with_network_data(mut self, data: &'a [u8]) -> ReplayWriter<'a> {
+        self.network_data = Some(data);
+        self
+    }
+
+    /// Encodes the network frames with the registry that was used to decode them
+    pub fn with_registry(mut self, registry: &'a Registry) -> ReplayWriter<'a> {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, WriteError> {
+        let replay = self.replay;
+        let header = Header {
+            major_version: replay.major_version,
+            minor_version: replay.minor_version,
+            net_version: replay.net_version,
+            game_type: replay.game_type.clone(),
+            properties: replay.properties.clone(),
+        };
+
+        let mut out = CoreWriter::new();
+        write_section(&mut out, |w| header::write_header(w, &header))?;
+        write_section(&mut out, |w| self.write_body(w, &header))?;
+        Ok(out.into_inner())
+    }
+
+    /// Encodes the network frames and returns the data along with keyframes that point to
+    /// the bit offset of their frame in the data
+    fn encode_network_data(
+        &self,
+        header: &Header,
+        frames: &[Frame],
+    ) -> Result<(Vec<u8>, Vec<KeyFrame>), WriteError> {
+        let replay = self.replay;
+        let builtin = Registry::new();
+        let registry = self.registry.unwrap_or(&builtin);
+        let encoded = network::encode(header, &replay.objects, &replay.net_cache, frames, registry)
+            .map_err(|e| WriteError::NetworkError(Box::new(e)))?;
+
+        let keyframes = replay
+            .keyframes
+            .iter()
+            .filter_map(|x| {
+                let position = usize::try_from(x.frame)
+                    .ok()
+                    .and_then(|frame| encoded.frame_positions.get(frame))?;
+                Some(KeyFrame {
+                    position: *position as i32,
+                    ..*x
+                })
+            })
+            .collect();
+
+        Ok((encoded.data, keyframes))
+    }
+
+    fn write_body(&self, w: &mut CoreWriter, header: &Header) -> Result<(), WriteError> {
+        let replay = self.replay;
+        let (network_data, keyframes) = match (self.network_data, &replay.network_frames) {
+            (Some(data), _) => (Cow::Borrowed(data), Cow::Borrowed(&replay.keyframes[..])),
+            (None, Some(network)) => {
+                if network.truncated || !network.failures.is_empty() {
+                    return Err(WriteError::IncompleteFrames(network.failures.len()));
+                }
+
+                let (data, keyframes) = self.encode_network_data(header, &network.frames)?;
+                (Cow::Owned(data), Cow::Owned(keyframes))
+            }
+            (None, None) => (Cow::Borrowed(&[][..]), Cow::Borrowed(&replay.keyframes[..])),
+        };
+
+        w.text_list(&replay.levels)?;
+        w.list_of(&keyframes, |s, x| {
+            s.write_f32(x.time);
+            s.write_i32(x.frame);
+            s.write_i32(x.position);
+            Ok(())
+        })?;
+
+        w.write_i32(network_data.len() as i32);
+        w.write_data(&network_data);
+
+        w.list_of(&replay.debug_info, |s, x| {
+            s.write_i32(x.frame);
+            s.write_text(&x.user)?;
+            s.write_text(&x.text)
+        })?;
+
+        w.list_of(&replay.tick_marks, |s, x| {
+            s.write_text(&x.description)?;
+            s.write_i32(x.frame);
+            Ok(())
+        })?;
+
+        w.text_list(&replay.packages)?;
+        w.text_list(&replay.objects)?;
+        w.text_list(&replay.names)?;
+
+        w.list_of(&replay.class_indices, |s, x| {
+            s.write_str(&x.class)?;
+            s.write_i32(x.index);
+            Ok(())
+        })?;
+
+        w.list_of(&replay.net_cache, |s, x| {
+            s.write_i32(x.object_ind);
+            s.write_i32(x.parent_id);
+            s.write_i32(x.cache_id);
+            s.list_of(&x.properties, |s, prop| {
+                s.write_i32(prop.object_ind);
+                s.write_i32(prop.stream_id);
+                Ok(())
+            })
+        })?;
+
+        // Newer replays end the body with 32 bits of unknown data that has only been seen as zero
+        if matches!(replay.net_version, Some(x) if x >= 10) {
+            w.write_u32(0);
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a section prefixed by its size and crc
+fn write_section<F>(out: &mut CoreWriter, f: F) -> Result<(), WriteError>
+where
+    F: FnOnce(&mut CoreWriter) -> Result<(), WriteError>,
+{
+    let mut section = CoreWriter::new();
+    f(&mut section)?;
+    let data = section.into_inner();
+    out.write_i32(data.len() as i32);
+    out.write_u32(calc_crc(&data));
+    out.write_data(&data);
+    Ok(())
+}
+
+/// Edits the header properties of an existing replay. The header is re-encoded with a new size
+/// and CRC while the body is copied verbatim, so the network data is never decoded.
+///
+/// ```
+/// use boxcars::{HeaderEditor, HeaderProp};
+///
+/// let data = include_bytes!("../assets/replays/good/rumble.replay");
+/// let mut editor = HeaderEditor::new(&data[..]).unwrap();
+/// editor.set("ReplayName", HeaderProp::Str(String::from("renamed")));
+/// editor.remove("PlayerName");
+///
+/// let written = editor.write().unwrap();
+/// let replay = boxcars::ParserBuilder::new(&written[..])
+///     .always_check_crc()
+///     .must_parse_network_data()
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(replay.header().replay_name.as_deref(), Some("renamed"));
+/// assert!(replay.properties.iter().all(|(key, _)| key != "PlayerName"));
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct HeaderEditor<'a> {
+    header: Header,
+    body: &'a [u8],
+}
+
+impl<'a> HeaderEditor<'a> {
+    /// Decodes the header of the replay data. The header CRC is not checked.
+    pub fn new(data: &'a [u8]) -> Result<Self, ParseError> {
+        let mut core = CoreParser::new(data);
+        let header_size = core.take_i32("header size")?;
+        core.take_u32("header crc")?;
+        let header_data = core
+            .take_data(header_size as usize)
+            .map_err(|e| ParseError::ParseError("header data", core.bytes_read(), Box::new(e)))?;
+
+        let header = header::parse_header(&mut CoreParser::new(header_data))?.into_owned();
+        Ok(HeaderEditor {
+            header,
+            body: &data[core.bytes_read() as usize..],
+        })
+    }
+
+    pub fn properties(&self) -> &[(String, HeaderProp)] {
+        &self.header.properties
+    }
+
+    /// The properties for edits that go beyond a single key, like reordering or editing
+    /// duplicated keys
+    pub fn properties_mut(&mut self) -> &mut Vec<(String, HeaderProp)> {
+        &mut self.header.properties
+    }
+
+    /// Returns the first property with the key
+    pub fn get(&self, key: &str) -> Option<&HeaderProp> {
+        self.header
+            .properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, prop)| prop)
+    }
+
+    /// Updates the first property with the key and returns its previous value. A new property is
+    /// appended when the key doesn't exist.
+    pub fn set(&mut self, key: &str, prop: HeaderProp) -> Option<HeaderProp> {
+        match self.header.properties.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, prop)),
+            None => {
+                self.header.properties.push((String::from(key), prop));
+                None
+            }
+        }
+    }
+
+    /// Removes all properties with the key and returns the first one removed
+    pub fn remove(&mut self, key: &str) -> Option<HeaderProp> {
+        let position = self.header.properties.iter().position(|(k, _)| k == key)?;
+        let removed = self.header.properties.remove(position).1;
+        self.header.properties.retain(|(k, _)| k != key);
+        Some(removed)
+    }
+
+    /// Encodes the edited header section followed by the original body
+    pub fn write(&self) -> Result<Vec<u8>, WriteError> {
+        let mut out = CoreWriter::new();
+        write_section(&mut out, |w| header::write_header(w, &self.header))?;
+        out.write_data(self.body);
+        Ok(out.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{FrameError, NetworkError};
+    use crate::ParserBuilder;
+    use std::convert::TryInto;
+
+    /// Locates the raw network data in a replay by skipping the header and the lists that precede
+    /// the network data in the body
+    fn network_data<'a>(data: &'a [u8], replay: &Replay) -> &'a [u8] {
+        let mut w = CoreWriter::new();
+        w.text_list(&replay.levels).unwrap();
+        let start = 8 + replay.header_size as usize + 8 + w.bytes_written();
+        let start = start + 4 + replay.keyframes.len() * 12;
+        let size = i32::from_le_bytes(data[start..start + 4].try_into().unwrap()) as usize;
+        &data[start + 4..start + 4 + size]
+    }
+
+    fn round_trip(data: &[u8]) {
+        let replay = ParserBuilder::new(data)
+            .always_check_crc()
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let written = ReplayWriter::new(&replay)
+            .with_network_data(network_data(data, &replay))
+            .write()
+            .unwrap();
+
+        assert!(written == data);
+    }
+
+    #[test]
+    fn write_rumble_identical() {
+        round_trip(include_bytes!("../assets/replays/good/rumble.replay"));
+    }
+
+    #[test]
+    fn write_utf16_identical() {
+        round_trip(include_bytes!("../assets/replays/good/3381.replay"));
+    }
+
+    #[test]
+    fn write_net_version_10_identical() {
+        round_trip(include_bytes!("../assets/replays/good/4742.replay"));
+    }
+
+    #[test]
+    fn write_version_zero_replay() {
+        // Ancient replays encode bools as 32 bits, which the parser only tolerates when followed
+        // by a "None" key, so the written replay is not identical but it is equivalent
+        let data = include_bytes!("../assets/replays/good/7588d.replay");
+        let replay = ParserBuilder::new(&data[..])
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let written = ReplayWriter::new(&replay)
+            .with_network_data(network_data(data, &replay))
+            .write()
+            .unwrap();
+
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+        assert_eq!(reparsed.properties, replay.properties);
+        assert_eq!(reparsed.net_cache, replay.net_cache);
+    }
+
+    #[test]
+    fn write_recomputes_crc() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut replay = ParserBuilder::new(&data[..])
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        replay.properties.push((
+            String::from("ReplayName"),
+            HeaderProp::Str(String::from("\u{2623} boxcars")),
+        ));
+
+        let written = ReplayWriter::new(&replay).write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        assert_eq!(reparsed.properties, replay.properties);
+        assert_eq!(reparsed.objects, replay.objects);
+        assert_eq!(reparsed.net_cache, replay.net_cache);
+        assert_eq!(
+            reparsed.header_size as usize + 8 + 8 + reparsed.content_size as usize,
+            written.len()
+        );
+    }
+
+    fn frames_round_trip(data: &[u8]) {
+        let replay = ParserBuilder::new(data)
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let written = ReplayWriter::new(&replay).write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        assert_eq!(reparsed.network_frames, replay.network_frames);
+        assert_eq!(reparsed.keyframes, replay.keyframes);
+
+        // Rocket league pads the network data with zeros
+        let original = network_data(data, &replay);
+        let encoded = network_data(&written, &reparsed);
+        let (prefix, padding) = original.split_at(encoded.len());
+        assert!(prefix == encoded);
+        assert!(padding.iter().all(|&x| x == 0));
+    }
+
+    #[test]
+    fn write_rumble_frames() {
+        frames_round_trip(include_bytes!("../assets/replays/good/rumble.replay"));
+    }
+
+    #[test]
+    fn write_utf16_frames() {
+        frames_round_trip(include_bytes!("../assets/replays/good/3381.replay"));
+    }
+
+    #[test]
+    fn write_lan_frames() {
+        frames_round_trip(include_bytes!("../assets/replays/good/soccar-lan.replay"));
+    }
+
+    #[test]
+    fn edit_header_unchanged() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let editor = HeaderEditor::new(&data[..]).unwrap();
+        assert!(editor.write().unwrap() == data[..]);
+    }
+
+    #[test]
+    fn edit_header_keeps_body() {
+        let data = include_bytes!("../assets/replays/good/3381.replay");
+        let replay = ParserBuilder::new(&data[..])
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let mut editor = HeaderEditor::new(&data[..]).unwrap();
+        let previous = editor.set("Date", HeaderProp::Str(String::from("2020-01-01 00-00-00")));
+        assert!(previous.is_some());
+        assert_eq!(
+            editor.set("Comment", HeaderProp::Str(String::from("\u{2623}"))),
+            None
+        );
+
+        let written = editor.write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .always_check_crc()
+            .never_parse_network_data()
+            .parse()
+            .unwrap();
+
+        assert_eq!(reparsed.properties.len(), replay.properties.len() + 1);
+        assert_eq!(
+            reparsed.header().date.as_deref(),
+            Some("2020-01-01 00-00-00")
+        );
+        assert_eq!(reparsed.content_crc, replay.content_crc);
+
+        let body_start = 8 + replay.header_size as usize;
+        let written_start = 8 + reparsed.header_size as usize;
+        assert!(written[written_start..] == data[body_start..]);
+    }
+
+    #[test]
+    fn write_frames_error() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let frames = &mut replay.network_frames.as_mut().unwrap().frames;
+        let actor_id = frames[0].new_actors[0].actor_id;
+        frames[0].new_actors.clear();
+        frames[0].updated_actors.retain(|x| x.actor_id == actor_id);
+
+        match ReplayWriter::new(&replay).write() {
+            Err(WriteError::NetworkError(e)) => match *e {
+                NetworkError::FrameError(FrameError::MissingActor { actor }, _) => {
+                    assert_eq!(actor, actor_id)
+                }
+                e => panic!("unexpected network error: {}", e),
+            },
+            x => panic!("unexpected result: {:?}", x.map(|x| x.len())),
+        }
+    }
+
+    #[test]
+    fn write_truncated_frames_error() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut registry = Registry::new();
+        registry.add_attribute(
+            "TAGame.GameEvent_Soccar_TA:ReplicatedScoredOnTeam",
+            crate::network::attributes::AttributeTag::NotImplemented,
+        );
+
+        let replay = ParserBuilder::new(&data[..])
+            .with_registry(&registry)
+            .ignore_network_data_on_error()
+            .parse()
+            .unwrap();
+
+        assert!(replay.network_frames.as_ref().unwrap().truncated);
+        assert_eq!(
+            ReplayWriter::new(&replay).write(),
+            Err(WriteError::IncompleteFrames(1))
+        );
+    }
+
+    #[test]
+    fn write_fewer_frames_drops_keyframes() {
+        let data = include_bytes!("../assets/replays/good/rumble.replay");
+        let mut replay = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        let frames = &mut replay.network_frames.as_mut().unwrap().frames;
+        frames.truncate(500);
+        let (_, num_frames) = replay
+            .properties
+            .iter_mut()
+            .find(|(key, _)| key == "NumFrames")
+            .unwrap();
+        *num_frames = HeaderProp::Int(500);
+
+        let written = ReplayWriter::new(&replay).write().unwrap();
+        let reparsed = ParserBuilder::new(&written[..])
+            .must_parse_network_data()
+            .parse()
+            .unwrap();
+
+        assert_eq!(reparsed.network_frames, replay.network_frames);
+        assert!(!reparsed.keyframes.is_empty());
+        assert!(reparsed.keyframes.len() < replay.keyframes.len());
+        assert!(reparsed.keyframes.iter().all(|x| x.frame < 500));
+    }
+}
@@ -1,7 +1,9 @@
 use boxcars::attributes::{ActiveActor, Demolish, Pickup, RigidBody, StatEvent, Welded};
+use boxcars::timeline::{EventKind, Timeline};
 use boxcars::{
-    self, ActorId, NetworkError, ParseError, ParserBuilder, Quaternion, Trajectory, Vector3f,
-    Vector3i,
+    self, ActorId, Attribute, AttributeLayout, AttributeTag, Frame, FrameError, NetworkError,
+    NetworkFilter, NetworkVisitor, NewActor, ObjectId, ParseError, ParserBuilder, Quaternion,
+    Registry, StreamId, Trajectory, UpdatedAttribute, Vector3f, Vector3i,
 };
 
 #[test]
@@ -225,14 +227,14 @@ fn test_preserve_endian() {
             boxcars::Attribute::LoadoutsOnline(x) => Some(x.blue.iter().flat_map(|pr| pr.iter())),
             _ => None,
         })
-        .flat_map(|x| x)
+        .flatten()
         .filter_map(|x| match x.value {
             boxcars::attributes::ProductValue::NewPaint(p) => Some(p),
             _ => None,
         })
         .collect::<Vec<_>>();
 
-    assert_eq!(*new_paints.get(0).unwrap(), 11);
+    assert_eq!(*new_paints.first().unwrap(), 11);
 }
 
 #[test]
@@ -245,7 +247,7 @@ fn test_error_extraction() {
         .unwrap_err();
 
     let ne = match err {
-        ParseError::NetworkError(e) => e,
+        ParseError::NetworkError(e, _) => e,
         _ => panic!("Expecting network error"),
     };
 
@@ -462,3 +464,615 @@ fn test_rumble_actor_id() {
         .collect();
     assert_eq!(pickups[264].instigator, Some(ActorId(-1)));
 }
+
+#[test]
+fn test_frame_iterator() {
+    let data = include_bytes!("../assets/replays/good/3d07e.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .never_check_crc()
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+
+    let (header, frames) = ParserBuilder::new(&data[..])
+        .never_check_crc()
+        .frames()
+        .unwrap();
+
+    assert!(header.network_frames.is_none());
+    assert_eq!(header.objects, replay.objects);
+
+    let frames = frames.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(frames, replay.network_frames.unwrap().frames);
+}
+
+#[test]
+fn test_frame_iterator_error() {
+    let data = include_bytes!("../assets/replays/bad/fuzz-string-too-long2.replay");
+    let (_, mut frames) = ParserBuilder::new(&data[..])
+        .never_check_crc()
+        .frames()
+        .unwrap();
+
+    let err = frames.find_map(|x| x.err()).unwrap();
+    assert!(format!("{}", err).contains("Unexpected size for string: -1912602609"));
+    assert!(frames.next().is_none());
+}
+
+/// Replays in `assets/replays/good` whose network data fails to decode
+const UNDECODABLE_REPLAYS: &[&str] = &[
+    "140a5",
+    "204c",
+    "4742",
+    "59d3",
+    "5f97d",
+    "epic",
+    "fecd",
+    "gridiron",
+    "voice_update",
+];
+
+/// Every replay in `assets/replays/good` whose network data decodes
+fn decodable_replays() -> Vec<(String, boxcars::Replay)> {
+    let mut paths: Vec<_> = std::fs::read_dir("assets/replays/good")
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .collect();
+    paths.sort();
+
+    let mut replays = Vec::new();
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let data = std::fs::read(&path).unwrap();
+        let parsed = ParserBuilder::new(&data[..])
+            .must_parse_network_data()
+            .parse();
+        match parsed {
+            Ok(replay) => {
+                assert!(!UNDECODABLE_REPLAYS.contains(&name.as_str()), "{}", name);
+                replays.push((name, replay));
+            }
+            Err(e) => assert!(
+                UNDECODABLE_REPLAYS.contains(&name.as_str()),
+                "{}: {}",
+                name,
+                e
+            ),
+        }
+    }
+
+    replays
+}
+
+#[test]
+fn test_timeline_goals_match_header() {
+    for (name, replay) in decodable_replays() {
+        let frames = &replay.network_frames.as_ref().unwrap().frames;
+        let timeline = Timeline::from_frames(&replay.objects, frames);
+        let goals: Vec<_> = timeline
+            .events
+            .iter()
+            .filter_map(|x| match x.kind {
+                EventKind::Goal { ref scorer, .. } => Some(scorer),
+                _ => None,
+            })
+            .collect();
+
+        // Goals scored on the last frame aren't in the network data
+        let header_goals: Vec<&str> = replay
+            .properties
+            .iter()
+            .find(|(key, _)| key == "Goals")
+            .and_then(|(_, prop)| prop.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|goal| {
+                let frame = goal
+                    .iter()
+                    .find(|(key, _)| key == "frame")
+                    .and_then(|(_, prop)| prop.as_i32());
+                matches!(frame, Some(x) if (x as usize) < frames.len())
+            })
+            .filter_map(|goal| {
+                goal.iter()
+                    .find(|(key, _)| key == "PlayerName")
+                    .and_then(|(_, prop)| prop.as_string())
+            })
+            .collect();
+
+        // The header doesn't list the own goal in d044
+        if name == "d044" {
+            assert_eq!(goals.len(), header_goals.len() + 1);
+            continue;
+        }
+
+        assert_eq!(goals.len(), header_goals.len(), "{}", name);
+
+        // Older replays may not replicate the scorer's goals
+        for (scorer, header_scorer) in goals.iter().zip(header_goals) {
+            if let Some(scorer) = scorer {
+                assert_eq!(scorer.name.as_deref(), Some(header_scorer), "{}", name);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_seek_to_keyframes() {
+    let data = include_bytes!("../assets/replays/good/3381.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    let all_frames = replay.network_frames.unwrap().frames;
+
+    let (_, mut frames) = ParserBuilder::new(&data[..]).frames().unwrap();
+    for (i, keyframe) in replay.keyframes.iter().enumerate().rev() {
+        let start = frames.seek_to_keyframe(i).unwrap();
+        assert_eq!(start, keyframe.frame as usize);
+
+        let decoded = frames
+            .by_ref()
+            .take(100)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.frame_index(), start + decoded.len());
+        assert!(decoded[..] == all_frames[start..start + decoded.len()]);
+    }
+
+    let keyframes = replay.keyframes.len();
+    assert!(matches!(
+        frames.seek_to_keyframe(keyframes),
+        Err(NetworkError::KeyFrameOutOfRange(x)) if x == keyframes
+    ));
+    assert!(matches!(
+        frames.decode_from(all_frames.len()),
+        Err(NetworkError::FrameOutOfRange(_))
+    ));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_decode_matches_serial() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    assert!(replay.keyframes.len() > 1);
+
+    let (_, frames) = ParserBuilder::new(&data[..]).frames().unwrap();
+    let serial = frames.collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(replay.network_frames.unwrap().frames == serial);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_filtered_decode_matches_serial() {
+    let mut filter = NetworkFilter::new();
+    filter.add_class("TAGame.Ball_TA");
+
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .network_filter(&filter)
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+
+    let (_, frames) = ParserBuilder::new(&data[..])
+        .network_filter(&filter)
+        .frames()
+        .unwrap();
+    let serial = frames.collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(replay.network_frames.unwrap().frames == serial);
+}
+
+#[test]
+fn test_registry_used_for_decoding() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let mut registry = Registry::new();
+    registry.add_attribute("TAGame.Car_TA:TeamPaint", AttributeTag::NotImplemented);
+    let err = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .must_parse_network_data()
+        .parse()
+        .unwrap_err();
+    assert!(matches!(err, ParseError::NetworkError(..)));
+
+    let mut registry = Registry::new();
+    registry
+        .add_parent_class("Engine.Actor", "TAGame.Car_TA")
+        .add_parent_class("TAGame.Car_TA", "TAGame.Vehicle_TA");
+    let err = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .frames()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        ParseError::NetworkError(e, _) if matches!(*e, NetworkError::ParentClassCycle(_))
+    ));
+}
+
+#[test]
+fn test_network_filter_keeps_selected() {
+    use std::collections::HashSet;
+
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+
+    let mut filter = NetworkFilter::new();
+    filter
+        .add_class("TAGame.Ball_TA")
+        .add_class("TAGame.Car_TA")
+        .add_attribute("TAGame.RBActor_TA:ReplicatedRBState");
+    let filtered = ParserBuilder::new(&data[..])
+        .network_filter(&filter)
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+
+    let rb_state = replay
+        .objects
+        .iter()
+        .position(|x| x == "TAGame.RBActor_TA:ReplicatedRBState")
+        .map(|x| ObjectId(x as i32))
+        .unwrap();
+
+    let all_frames = replay.network_frames.unwrap().frames;
+    let frames = filtered.network_frames.unwrap().frames;
+    assert_eq!(frames.len(), all_frames.len());
+
+    let mut live = HashSet::new();
+    let mut updates = 0;
+    for (frame, all) in frames.iter().zip(all_frames.iter()) {
+        assert_eq!(frame.time, all.time);
+        for actor in &frame.new_actors {
+            let name = &replay.objects[usize::from(actor.object_id)];
+            assert!(name.contains("Ball") || name.contains("Car"), "{}", name);
+            live.insert(actor.actor_id);
+        }
+
+        let expected: Vec<_> = all
+            .updated_actors
+            .iter()
+            .filter(|x| x.object_id == rb_state && live.contains(&x.actor_id))
+            .cloned()
+            .collect();
+        assert_eq!(frame.updated_actors, expected);
+        updates += expected.len();
+
+        for actor in &frame.deleted_actors {
+            live.remove(actor);
+        }
+    }
+
+    assert!(updates > 0);
+}
+
+#[test]
+fn test_network_filter_skips_all_attributes() {
+    let mut filter = NetworkFilter::new();
+    filter.add_attribute("None");
+
+    for (name, replay) in decodable_replays() {
+        let frames = replay.network_frames.unwrap().frames;
+        let data = std::fs::read(format!("assets/replays/good/{}.replay", name)).unwrap();
+        let filtered = ParserBuilder::new(&data[..])
+            .network_filter(&filter)
+            .must_parse_network_data()
+            .parse()
+            .unwrap()
+            .network_frames
+            .unwrap()
+            .frames;
+
+        assert_eq!(filtered.len(), frames.len(), "{}", name);
+        for (frame, all) in filtered.iter().zip(frames.iter()) {
+            assert_eq!(frame.time, all.time);
+            assert_eq!(frame.new_actors, all.new_actors);
+            assert_eq!(frame.deleted_actors, all.deleted_actors);
+            assert!(frame.updated_actors.is_empty());
+        }
+    }
+}
+
+#[test]
+fn test_visitor_matches_frames() {
+    #[derive(Default)]
+    struct Collector {
+        frames: Vec<Frame>,
+    }
+
+    impl NetworkVisitor for Collector {
+        fn on_frame_start(&mut self, time: f32, delta: f32) {
+            self.frames.push(Frame {
+                time,
+                delta,
+                new_actors: Vec::new(),
+                deleted_actors: Vec::new(),
+                updated_actors: Vec::new(),
+            });
+        }
+
+        fn on_new_actor(&mut self, actor: &NewActor) {
+            let frame = self.frames.last_mut().unwrap();
+            frame.new_actors.push(*actor);
+        }
+
+        fn on_actor_deleted(&mut self, actor: ActorId) {
+            let frame = self.frames.last_mut().unwrap();
+            frame.deleted_actors.push(actor);
+        }
+
+        fn on_attribute(&mut self, actor: ActorId, object_id: ObjectId, attribute: &Attribute) {
+            let frame = self.frames.last_mut().unwrap();
+            frame.updated_actors.push(UpdatedAttribute {
+                actor_id: actor,
+                stream_id: StreamId(0),
+                object_id,
+                attribute: attribute.clone(),
+            });
+        }
+    }
+
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let replay = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    let mut frames = replay.network_frames.unwrap().frames;
+    for attribute in frames.iter_mut().flat_map(|x| x.updated_actors.iter_mut()) {
+        attribute.stream_id = StreamId(0);
+    }
+
+    let (_, mut iter) = ParserBuilder::new(&data[..]).frames().unwrap();
+    let mut collector = Collector::default();
+    iter.visit(&mut collector).unwrap();
+    assert!(collector.frames == frames);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_custom_attribute_decoder() {
+    use bitter::LittleEndianReader;
+    use boxcars::attributes::{AttributeContext, AttributeDecode, RawAttribute};
+    use boxcars::AttributeError;
+
+    struct TeamPaintBits;
+
+    impl AttributeDecode for TeamPaintBits {
+        fn decode(
+            &self,
+            bits: &mut LittleEndianReader<'_>,
+            ctx: &AttributeContext<'_>,
+        ) -> Result<Attribute, AttributeError> {
+            assert_eq!(ctx.attribute, "TAGame.Car_TA:TeamPaint");
+            RawAttribute::read(bits, 88)
+                .map(Attribute::Raw)
+                .ok_or(AttributeError::NotEnoughDataFor("Team Paint"))
+        }
+    }
+
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let mut registry = Registry::new();
+    registry.add_decoder("TAGame.Car_TA:TeamPaint", TeamPaintBits);
+    let replay = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+
+    let raw_count = replay
+        .network_frames
+        .iter()
+        .flat_map(|x| x.frames.iter())
+        .flat_map(|x| x.updated_actors.iter())
+        .filter(|x| matches!(x.attribute, Attribute::Raw(_)))
+        .count();
+    assert!(raw_count > 0);
+
+    // The raw bits are written back as is, so the default decoders see the original data
+    let written = boxcars::ReplayWriter::new(&replay).write().unwrap();
+    let reparsed = ParserBuilder::new(&written[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    let expected = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    assert!(reparsed.network_frames == expected.network_frames);
+}
+
+#[test]
+fn test_best_effort_resumes_at_keyframes() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let expected = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    let expected_frames = expected.network_frames.unwrap().frames;
+
+    // Goals can no longer be decoded
+    let mut registry = Registry::new();
+    registry.add_attribute(
+        "TAGame.GameEvent_Soccar_TA:ReplicatedScoredOnTeam",
+        AttributeTag::NotImplemented,
+    );
+    let replay = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .best_effort_network_data()
+        .parse()
+        .unwrap();
+
+    let keyframes = replay.keyframes;
+    let network = replay.network_frames.unwrap();
+    assert!(network.failures.len() > 1);
+
+    let mut decoded = 0;
+    for failure in &network.failures {
+        assert!(matches!(failure.error, FrameError::MissingAttribute { .. }));
+        assert!(failure.bit_offset > 0);
+        assert!(network.frames[decoded..failure.frame] == expected_frames[decoded..failure.frame]);
+
+        // The last goal is scored after the last keyframe
+        let resume = failure.resumed_at.unwrap_or(network.frames.len());
+        assert!(network.frames[failure.frame..resume]
+            .iter()
+            .all(|x| x.updated_actors.is_empty() && x.delta > 0.0));
+
+        // Time keeps moving through the skipped frames up to the keyframe
+        if let Some(resume) = failure.resumed_at {
+            let keyframe = keyframes
+                .iter()
+                .find(|x| x.frame as usize == resume)
+                .unwrap();
+            let skipped = &network.frames[failure.frame - 1..resume];
+            assert!(skipped.windows(2).all(|x| x[0].time < x[1].time));
+            assert!(skipped[skipped.len() - 1].time < keyframe.time);
+        }
+        decoded = resume;
+    }
+
+    let last = network.failures.last().unwrap();
+    assert_eq!(last.resumed_at, None);
+    assert_eq!(network.frames.len(), last.frame);
+    assert!(network.failures[..network.failures.len() - 1]
+        .iter()
+        .all(|x| keyframes
+            .iter()
+            .any(|k| Some(k.frame as usize) == x.resumed_at)));
+    assert_eq!(decoded, network.frames.len());
+}
+
+#[test]
+fn test_truncated_frames_on_error() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let expected = ParserBuilder::new(&data[..])
+        .must_parse_network_data()
+        .parse()
+        .unwrap();
+    let expected_frames = expected.network_frames.unwrap().frames;
+
+    let mut registry = Registry::new();
+    registry.add_attribute(
+        "TAGame.GameEvent_Soccar_TA:ReplicatedScoredOnTeam",
+        AttributeTag::NotImplemented,
+    );
+    let replay = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .ignore_network_data_on_error()
+        .parse()
+        .unwrap();
+
+    let network = replay.network_frames.unwrap();
+    assert!(network.truncated);
+    assert_eq!(network.failures.len(), 1);
+    assert_eq!(network.failures[0].frame, network.frames.len());
+    assert_eq!(network.failures[0].resumed_at, None);
+    assert!(network.frames[..] == expected_frames[..network.frames.len()]);
+
+    let err = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .must_parse_network_data()
+        .parse()
+        .unwrap_err();
+    match err {
+        ParseError::NetworkError(ref e, ref replay) => {
+            assert!(matches!(**e, NetworkError::FrameError(..)));
+            assert_eq!(replay.network_frames, None);
+        }
+        ref e => panic!("unexpected error: {}", e),
+    }
+
+    let partial = err.into_partial_replay().unwrap();
+    assert_eq!(partial.network_frames, Some(network));
+    assert_eq!(partial.objects, expected.objects);
+}
+
+#[test]
+fn test_trace_covers_bits_up_to_failure() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let mut registry = Registry::new();
+    registry.add_attribute("TAGame.Car_TA:TeamPaint", AttributeTag::NotImplemented);
+
+    let untraced = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .must_parse_network_data()
+        .parse();
+    match untraced {
+        Err(ParseError::NetworkError(e, _)) => match *e {
+            NetworkError::FrameError(_, context) => assert!(context.trace.is_none()),
+            e => panic!("unexpected error: {}", e),
+        },
+        _ => panic!("expected a network error"),
+    }
+
+    let err = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .trace_network()
+        .must_parse_network_data()
+        .parse()
+        .unwrap_err();
+
+    let context = match err {
+        ParseError::NetworkError(e, _) => match *e {
+            NetworkError::FrameError(_, context) => context,
+            e => panic!("unexpected error: {}", e),
+        },
+        _ => panic!("expected a network error"),
+    };
+
+    let trace = context.trace.unwrap();
+    let first = &trace.entries[0];
+    assert_eq!(trace.data_offset, first.bit_offset / 8 * 8);
+
+    // Every bit from the first traced element up to the failure is accounted for
+    let end = trace
+        .entries
+        .iter()
+        .fold(first.bit_offset, |offset, entry| {
+            assert_eq!(entry.bit_offset, offset, "{:?}", entry);
+            offset + entry.bit_len
+        });
+    assert_eq!(end, context.bit_offset);
+
+    let last = trace.entries.last().unwrap();
+    assert_eq!(last.component, "Stream id");
+    assert!(last.value.contains("TAGame.Car_TA:TeamPaint"));
+    assert!(trace.data.len() * 8 >= context.bit_offset - trace.data_offset);
+}
+
+#[test]
+fn test_guess_attribute_finds_rigid_body() {
+    let data = include_bytes!("../assets/replays/good/rumble.replay");
+    let (_, mut frames) = ParserBuilder::new(&data[..]).frames().unwrap();
+    assert!(frames.by_ref().all(|x| x.is_ok()));
+    assert!(frames.guess_attribute(10).is_none());
+
+    let mut registry = Registry::new();
+    registry.add_attribute(
+        "TAGame.RBActor_TA:ReplicatedRBState",
+        AttributeTag::NotImplemented,
+    );
+    let (_, mut frames) = ParserBuilder::new(&data[..])
+        .with_registry(&registry)
+        .frames()
+        .unwrap();
+    assert!(frames.by_ref().any(|x| x.is_err()));
+
+    let guesses = frames.guess_attribute(200).unwrap();
+    let best = &guesses[0];
+    assert_eq!(best.layout, AttributeLayout::Tag(AttributeTag::RigidBody));
+    assert!(best.consistent);
+    assert_eq!(best.updates, 200);
+    assert!(matches!(best.attribute, Attribute::RigidBody(_)));
+    assert!(guesses
+        .iter()
+        .filter(|x| x.consistent)
+        .all(|x| x.bit_len == best.bit_len));
+}